@@ -100,6 +100,15 @@ pub fn process_instruction(
     let mut record_event_fn = |e: MarketEvent<Pubkey>| event_recorder.add_event(e);
     let mut order_ids = Vec::new();
 
+    // A scheduled status transition is applied before the instruction that first touches the
+    // market after its deadline
+    if instruction != PhoenixInstruction::InitializeMarket {
+        governance::apply_scheduled_market_status(
+            &market_context.market_info,
+            &mut record_event_fn,
+        )?;
+    }
+
     match instruction {
         PhoenixInstruction::InitializeMarket => {
             initialize::process_initialize_market(program_id, &market_context, accounts, data)?
@@ -176,6 +185,24 @@ pub fn process_instruction(
             false,
             &mut record_event_fn,
         )?,
+        PhoenixInstruction::IncreaseOrder => increase_order::process_increase_order(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            false,
+            &mut record_event_fn,
+            &mut order_ids,
+        )?,
+        PhoenixInstruction::IncreaseOrderWithFreeFunds => increase_order::process_increase_order(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            true,
+            &mut record_event_fn,
+            &mut order_ids,
+        )?,
         PhoenixInstruction::CancelAllOrders => cancel_multiple_orders::process_cancel_all_orders(
             program_id,
             &market_context,
@@ -233,11 +260,21 @@ pub fn process_instruction(
             )?
         }
         PhoenixInstruction::WithdrawFunds => {
-            withdraw::process_withdraw_funds(program_id, &market_context, accounts, data)?;
-        }
-        PhoenixInstruction::DepositFunds => {
-            deposit::process_deposit_funds(program_id, &market_context, accounts, data)?
+            withdraw::process_withdraw_funds(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+                &mut record_event_fn,
+            )?;
         }
+        PhoenixInstruction::DepositFunds => deposit::process_deposit_funds(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
         PhoenixInstruction::ForceCancelOrders => governance::process_force_cancel_orders(
             program_id,
             &market_context,
@@ -245,39 +282,193 @@ pub fn process_instruction(
             data,
             &mut record_event_fn,
         )?,
-        PhoenixInstruction::EvictSeat => {
-            governance::process_evict_seat(program_id, &market_context, accounts, data)?
+        PhoenixInstruction::EvictSeat => governance::process_evict_seat(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ClaimAuthority => governance::process_claim_authority(
+            program_id,
+            &market_context,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::NameSuccessor => governance::process_name_successor(
+            program_id,
+            &market_context,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::CancelSuccessor => governance::process_cancel_successor(
+            program_id,
+            &market_context,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ChangeMarketStatus => governance::process_change_market_status(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::RequestSeatAuthorized => manage_seat::process_request_seat_authorized(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::RequestSeat => manage_seat::process_request_seat(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ChangeSeatStatus => manage_seat::process_change_seat_status(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::CollectFees => fees::process_collect_fees(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ChangeFeeRecipient => fees::process_change_fee_recipient(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ChangeSelfTradePreventionGroup => {
+            self_trade_prevention::process_change_self_trade_prevention_group(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+            )?
         }
-        PhoenixInstruction::ClaimAuthority => {
-            governance::process_claim_authority(program_id, &market_context, data)?
+        PhoenixInstruction::ChangeSelfTradePreventionGroupAuthorized => {
+            self_trade_prevention::process_change_self_trade_prevention_group_authorized(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+            )?
         }
-        PhoenixInstruction::NameSuccessor => {
-            governance::process_name_successor(program_id, &market_context, data)?
+        PhoenixInstruction::ChangeBookFullPolicy => {
+            governance::process_change_book_full_policy(program_id, &market_context, data)?
         }
-        PhoenixInstruction::ChangeMarketStatus => {
-            governance::process_change_market_status(program_id, &market_context, accounts, data)?
+        PhoenixInstruction::ExpandMarket => {
+            expand_market::process_expand_market(program_id, &market_context, accounts, data)?
         }
-        PhoenixInstruction::RequestSeatAuthorized => manage_seat::process_request_seat_authorized(
+        PhoenixInstruction::CloseMarket => {
+            governance::process_close_market(program_id, &market_context, accounts, data)?
+        }
+        PhoenixInstruction::CloseSeat => governance::process_close_seat(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::RequestSeatWithProof => manage_seat::process_request_seat_with_proof(
             program_id,
             &market_context,
             accounts,
             data,
+            &mut record_event_fn,
         )?,
-        PhoenixInstruction::RequestSeat => {
-            manage_seat::process_request_seat(program_id, &market_context, accounts, data)?
+        PhoenixInstruction::ChangeSeatAllowlistRoot => {
+            manage_seat::process_change_seat_allowlist_root(
+                program_id,
+                &market_context,
+                data,
+                &mut record_event_fn,
+            )?
         }
-        PhoenixInstruction::ChangeSeatStatus => {
-            manage_seat::process_change_seat_status(program_id, &market_context, accounts, data)?;
+        PhoenixInstruction::ChangeSeatApprovalParams => {
+            manage_seat::process_change_seat_approval_params(program_id, &market_context, data)?
         }
-        PhoenixInstruction::CollectFees => fees::process_collect_fees(
+        PhoenixInstruction::ForceWithdrawFunds => governance::process_force_withdraw_funds(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::EvictInactiveTrader => evict_trader::process_evict_inactive_trader(
             program_id,
             &market_context,
             accounts,
             data,
             &mut record_event_fn,
         )?,
-        PhoenixInstruction::ChangeFeeRecipient => {
-            fees::process_change_fee_recipient(program_id, &market_context, accounts, data)?
+        PhoenixInstruction::WithdrawEvictedFunds => evict_trader::process_withdraw_evicted_funds(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::ChangeTraderEvictionPolicy => {
+            evict_trader::process_change_trader_eviction_policy(program_id, &market_context, data)?
+        }
+        PhoenixInstruction::ChangeMarketRole => {
+            governance::process_change_market_role(program_id, &market_context, data)?
+        }
+        PhoenixInstruction::ProposeGovernanceAction => {
+            governance_proposals::process_propose_governance_action(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+            )?
+        }
+        PhoenixInstruction::ExecuteGovernanceAction => {
+            governance_proposals::process_execute_governance_action(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+                &mut record_event_fn,
+            )?
+        }
+        PhoenixInstruction::CancelGovernanceAction => {
+            governance_proposals::process_cancel_governance_action(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+            )?
+        }
+        PhoenixInstruction::BatchEvictSeats => governance::process_batch_evict_seats(
+            program_id,
+            &market_context,
+            accounts,
+            data,
+            &mut record_event_fn,
+        )?,
+        PhoenixInstruction::BatchForceCancelOrders => {
+            governance::process_batch_force_cancel_orders(
+                program_id,
+                &market_context,
+                accounts,
+                data,
+                &mut record_event_fn,
+            )?
+        }
+        PhoenixInstruction::ScheduleMarketStatus => {
+            governance::process_schedule_market_status(program_id, &market_context, data)?
         }
         _ => unreachable!(),
     }
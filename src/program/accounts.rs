@@ -27,6 +27,10 @@ pub fn get_discriminant<T>() -> Result<u64, ProgramError> {
     Ok(discriminant)
 }
 
+/// The capacities of the bids, the asks and the trader map of a market. Each capacity must be a
+/// power of two from 4 to 65536, or one of the capacities of markets created before market sizes
+/// became configurable (1025, 1153, 2049, 2177, 4097, 4225, 8193 or 8321). Other values, such as
+/// 100 or 300, are rejected with `InvalidMarketParameters`.
 #[derive(Default, Debug, Copy, Clone, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct MarketSizeParams {
@@ -36,6 +40,31 @@ pub struct MarketSizeParams {
 }
 impl ZeroCopy for MarketSizeParams {}
 
+/// These parameters define how seats requested by traders are approved.
+#[derive(
+    Default, Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Zeroable, Pod,
+)]
+#[repr(C)]
+pub struct SeatApprovalParams {
+    /// If set to 1, `RequestSeat` immediately approves the seat and registers the trader on the
+    /// market. If set to 0, seats must be approved by the market authority.
+    pub permissionless: u64,
+
+    /// Number of lamports paid by the trader to the fee recipient for a permissionless seat.
+    pub seat_fee_in_lamports: u64,
+
+    /// Number of quote lots paid by the trader to the quote token account of the fee recipient
+    /// for a permissionless seat.
+    pub seat_fee_in_quote_lots: u64,
+}
+impl ZeroCopy for SeatApprovalParams {}
+
+impl SeatApprovalParams {
+    pub fn is_permissionless(&self) -> bool {
+        self.permissionless != 0
+    }
+}
+
 #[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
 #[repr(C)]
 pub struct TokenParams {
@@ -70,7 +99,37 @@ pub struct MarketHeader {
     pub successor: Pubkey,
     pub raw_base_units_per_base_unit: u32,
     _padding1: u32,
-    _padding2: [u64; 32],
+    pub seat_approval_params: SeatApprovalParams,
+    /// Root of the Merkle tree of traders that can approve their own seat with `RequestSeatWithProof`.
+    /// The allowlist is disabled if the root is zero.
+    pub seat_allowlist_root: [u8; 32],
+    /// If set to 1, the least recently active trader without open orders can be evicted from the
+    /// market when all seats are taken. Its free funds remain claimable through its seat.
+    pub evict_inactive_traders: u64,
+    /// Can only move the market to `PostOnly` or `Paused`. Unassigned if set to the default pubkey.
+    pub pause_guardian: Pubkey,
+    /// Can approve, retire and evict seats. Unassigned if set to the default pubkey.
+    pub seat_manager: Pubkey,
+    /// Can change the fee recipient. Unassigned if set to the default pubkey.
+    pub fee_manager: Pubkey,
+    /// Number of slots that must pass between proposing and executing a governance action.
+    pub governance_delay_in_slots: u64,
+    /// Number of seconds that must pass between proposing and executing a governance action.
+    pub governance_delay_in_seconds: u64,
+    /// Number of governance actions proposed on the market, used to derive the address of the
+    /// next proposal.
+    pub governance_proposal_count: u64,
+    /// Unix timestamp after which the successor can no longer claim the authority. The nomination
+    /// does not expire if this is zero.
+    pub successor_expiry_unix_timestamp: i64,
+    /// Status that the market moves to once `scheduled_status_unix_timestamp` has passed. No
+    /// transition is scheduled if this is zero (`Uninitialized`).
+    pub scheduled_status: u64,
+    pub scheduled_status_unix_timestamp: i64,
+    /// Set to 1 if the scheduled transition was made by the market authority, in which case the
+    /// pause guardian cannot replace it.
+    pub scheduled_by_authority: u64,
+    _padding2: [u64; 5],
 }
 impl ZeroCopy for MarketHeader {}
 
@@ -103,7 +162,20 @@ impl MarketHeader {
             successor,
             raw_base_units_per_base_unit,
             _padding1: 0,
-            _padding2: [0; 32],
+            seat_approval_params: SeatApprovalParams::default(),
+            seat_allowlist_root: [0; 32],
+            evict_inactive_traders: 0,
+            pause_guardian: Pubkey::default(),
+            seat_manager: Pubkey::default(),
+            fee_manager: Pubkey::default(),
+            governance_delay_in_slots: 0,
+            governance_delay_in_seconds: 0,
+            governance_proposal_count: 0,
+            successor_expiry_unix_timestamp: 0,
+            scheduled_status: 0,
+            scheduled_status_unix_timestamp: 0,
+            scheduled_by_authority: 0,
+            _padding2: [0; 5],
         }
     }
 
@@ -127,6 +199,53 @@ impl MarketHeader {
     pub fn increment_sequence_number(&mut self) {
         self.market_sequence_number += 1;
     }
+
+    pub fn get_role(&self, role: MarketRole) -> &Pubkey {
+        match role {
+            MarketRole::PauseGuardian => &self.pause_guardian,
+            MarketRole::SeatManager => &self.seat_manager,
+            MarketRole::FeeManager => &self.fee_manager,
+        }
+    }
+
+    pub fn get_role_mut(&mut self, role: MarketRole) -> &mut Pubkey {
+        match role {
+            MarketRole::PauseGuardian => &mut self.pause_guardian,
+            MarketRole::SeatManager => &mut self.seat_manager,
+            MarketRole::FeeManager => &mut self.fee_manager,
+        }
+    }
+
+    /// Returns true if timelocked governance actions must be proposed and can only be executed
+    /// once the governance delay has passed.
+    pub fn is_governance_timelocked(&self) -> bool {
+        self.governance_delay_in_slots != 0 || self.governance_delay_in_seconds != 0
+    }
+
+    pub fn has_scheduled_status(&self) -> bool {
+        self.scheduled_status != MarketStatus::Uninitialized as u64
+    }
+
+    pub fn clear_scheduled_status(&mut self) {
+        self.scheduled_status = MarketStatus::Uninitialized as u64;
+        self.scheduled_status_unix_timestamp = 0;
+        self.scheduled_by_authority = 0;
+    }
+
+    /// Returns true if the key is the market authority or has been assigned the given role.
+    pub fn has_role(&self, key: &Pubkey, role: MarketRole) -> bool {
+        let role_key = self.get_role(role);
+        &self.authority == key || (role_key != &Pubkey::default() && role_key == key)
+    }
+}
+
+/// Roles that the market authority can delegate to other keys. The market authority
+/// holds every role.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum MarketRole {
+    PauseGuardian,
+    SeatManager,
+    FeeManager,
 }
 
 /// This struct represents the state of a seat. Only traders with seats can
@@ -140,8 +259,12 @@ pub struct Seat {
     pub market: Pubkey,
     pub trader: Pubkey,
     pub approval_status: u64,
+    /// Free base lots of the trader at the time it was evicted from the market for inactivity
+    pub evicted_base_lots: u64,
+    /// Free quote lots of the trader at the time it was evicted from the market for inactivity
+    pub evicted_quote_lots: u64,
     // Padding
-    _padding: [u64; 6],
+    _padding: [u64; 4],
 }
 
 impl ZeroCopy for Seat {}
@@ -153,11 +276,90 @@ impl Seat {
             market,
             trader,
             approval_status: SeatApprovalStatus::NotApproved as u64,
-            _padding: [0; 6],
+            evicted_base_lots: 0,
+            evicted_quote_lots: 0,
+            _padding: [0; 4],
         })
     }
 }
 
+/// Governance actions that can be proposed by the market authority. While the market is
+/// governance timelocked, these actions cannot be taken directly.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum GovernanceAction {
+    ChangeTakerFee {
+        taker_fee_bps: u16,
+    },
+    ChangeFeeRecipient {
+        fee_recipient: Pubkey,
+    },
+    NameSuccessor {
+        successor: Pubkey,
+        expiry_unix_timestamp: Option<i64>,
+    },
+    ChangeMarketStatus {
+        status: MarketStatus,
+    },
+    ChangeGovernanceDelay {
+        delay_in_slots: u64,
+        delay_in_seconds: u64,
+    },
+}
+
+/// This struct represents a pending governance action. It can be executed by the market authority
+/// once the current governance delay of the market has passed since it was proposed, in both slots
+/// and seconds, or cancelled at any time.
+#[derive(Debug, Clone, Copy, BorshDeserialize, BorshSerialize, Zeroable, Pod)]
+#[repr(C)]
+pub struct GovernanceProposal {
+    pub discriminant: u64,
+    pub market: Pubkey,
+    pub proposal_id: u64,
+    pub proposed_slot: u64,
+    pub proposed_unix_timestamp: i64,
+    /// Borsh serialized `GovernanceAction`, padded with zeros
+    pub action: [u8; 48],
+}
+
+impl ZeroCopy for GovernanceProposal {}
+
+impl GovernanceProposal {
+    pub fn new_init(
+        market: Pubkey,
+        proposal_id: u64,
+        proposed_slot: u64,
+        proposed_unix_timestamp: i64,
+        action: &GovernanceAction,
+    ) -> Result<Self, ProgramError> {
+        let mut action_bytes = [0; 48];
+        action.serialize(&mut action_bytes.as_mut_slice())?;
+        Ok(Self {
+            discriminant: get_discriminant::<GovernanceProposal>()?,
+            market,
+            proposal_id,
+            proposed_slot,
+            proposed_unix_timestamp,
+            action: action_bytes,
+        })
+    }
+
+    pub fn get_action(&self) -> Result<GovernanceAction, ProgramError> {
+        Ok(GovernanceAction::deserialize(&mut self.action.as_slice())?)
+    }
+
+    /// Returns the slot and unix timestamp at which the proposal can be executed. The current delay
+    /// of the market is used so that raising the delay also applies to pending proposals.
+    pub fn get_executable_slot_and_unix_timestamp(&self, header: &MarketHeader) -> (u64, i64) {
+        (
+            self.proposed_slot
+                .saturating_add(header.governance_delay_in_slots),
+            self.proposed_unix_timestamp.saturating_add(
+                i64::try_from(header.governance_delay_in_seconds).unwrap_or(i64::MAX),
+            ),
+        )
+    }
+}
+
 // Always run tests before every deploy
 #[test]
 fn test_valid_discriminants() {
@@ -175,3 +377,41 @@ fn test_valid_discriminants() {
     );
     assert_eq!(get_discriminant::<Seat>().unwrap(), 2002603505298356104);
 }
+
+#[test]
+fn test_governance_proposal_action() {
+    let actions = [
+        GovernanceAction::ChangeTakerFee { taker_fee_bps: 5 },
+        GovernanceAction::ChangeFeeRecipient {
+            fee_recipient: Pubkey::new_unique(),
+        },
+        GovernanceAction::ChangeMarketStatus {
+            status: MarketStatus::Closed,
+        },
+        GovernanceAction::ChangeGovernanceDelay {
+            delay_in_slots: u64::MAX,
+            delay_in_seconds: u64::MAX,
+        },
+    ];
+    for action in actions {
+        let proposal =
+            GovernanceProposal::new_init(Pubkey::new_unique(), 0, 0, 0, &action).unwrap();
+        assert_eq!(proposal.get_action().unwrap(), action);
+    }
+}
+
+#[test]
+fn test_market_roles() {
+    let mut header = MarketHeader::zeroed();
+    let authority = Pubkey::new_unique();
+    let pause_guardian = Pubkey::new_unique();
+    header.authority = authority;
+    // Unassigned roles are only held by the authority
+    assert!(header.has_role(&authority, MarketRole::PauseGuardian));
+    assert!(!header.has_role(&Pubkey::default(), MarketRole::PauseGuardian));
+
+    *header.get_role_mut(MarketRole::PauseGuardian) = pause_guardian;
+    assert!(header.has_role(&pause_guardian, MarketRole::PauseGuardian));
+    assert!(!header.has_role(&pause_guardian, MarketRole::SeatManager));
+    assert!(header.has_role(&authority, MarketRole::PauseGuardian));
+}
@@ -0,0 +1,85 @@
+//! Helpers for the seat allowlist of a market.
+//!
+//! The allowlist is committed to the market header as the root of a Merkle tree. Each leaf is the
+//! hash of a trader pubkey, and each internal node is the hash of its two children in sorted order,
+//! so an inclusion proof is simply the list of sibling hashes from the leaf to the root. Leaves and
+//! internal nodes are hashed with different prefixes so that a node can never be passed off as a leaf.
+
+use solana_program::{keccak, pubkey::Pubkey};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Returns the leaf of the allowlist Merkle tree for the given trader.
+pub fn get_allowlist_leaf(trader: &Pubkey) -> [u8; 32] {
+    keccak::hashv(&[LEAF_PREFIX, trader.as_ref()]).to_bytes()
+}
+
+/// Returns the parent of two nodes of the allowlist Merkle tree.
+pub fn get_allowlist_parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+/// Returns true if the proof shows that the trader is included in the allowlist with the given root.
+pub fn verify_allowlist_proof(root: &[u8; 32], trader: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let computed_root = proof
+        .iter()
+        .fold(get_allowlist_leaf(trader), |node, sibling| {
+            get_allowlist_parent(&node, sibling)
+        });
+    &computed_root == root
+}
+
+/// Computes the allowlist root of a list of traders along with the inclusion proof of each trader.
+/// An unpaired node at the end of a level is carried up to the next level unchanged.
+pub fn get_allowlist_root_and_proofs(traders: &[Pubkey]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut proofs = vec![vec![]; traders.len()];
+    // The index of the node on the current level that each trader's proof leads to
+    let mut positions = (0..traders.len()).collect::<Vec<_>>();
+    let mut level = traders.iter().map(get_allowlist_leaf).collect::<Vec<_>>();
+    if level.is_empty() {
+        return ([0; 32], proofs);
+    }
+    while level.len() > 1 {
+        for (proof, position) in proofs.iter_mut().zip(positions.iter_mut()) {
+            if let Some(sibling) = level.get(*position ^ 1) {
+                proof.push(*sibling);
+            }
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => get_allowlist_parent(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    (level[0], proofs)
+}
+
+#[test]
+fn test_allowlist_proofs() {
+    for num_traders in 1..20 {
+        let traders = (0..num_traders)
+            .map(|_| Pubkey::new_unique())
+            .collect::<Vec<_>>();
+        let (root, proofs) = get_allowlist_root_and_proofs(&traders);
+        for (trader, proof) in traders.iter().zip(proofs.iter()) {
+            assert!(verify_allowlist_proof(&root, trader, proof));
+            assert!(!verify_allowlist_proof(&root, &Pubkey::new_unique(), proof));
+        }
+        if num_traders > 1 {
+            // A proof is only valid for its own trader
+            assert!(!verify_allowlist_proof(&root, &traders[0], &proofs[1]));
+            // Internal nodes cannot be used as leaves
+            assert!(!verify_allowlist_proof(
+                &root,
+                &traders[0],
+                &proofs[0][..proofs[0].len() - 1]
+            ));
+        }
+    }
+}
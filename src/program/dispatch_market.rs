@@ -1,34 +1,12 @@
 use super::error::{assert_with_msg, PhoenixError};
-use super::MarketSizeParams;
+use super::{MarketHeader, MarketSizeParams};
 use crate::state::markets::{
     FIFOMarket, FIFOOrderId, FIFORestingOrder, Market, MarketWrapper, MarketWrapperMut,
-    WritableMarket,
 };
 use crate::state::OrderPacket;
-use sokoban::node_allocator::ZeroCopy;
-use solana_program::{program_error::ProgramError, pubkey::Pubkey};
-
-macro_rules! fifo_market_mut {
-    ($num_bids:literal, $num_asks:literal, $num_seats:literal, $bytes:expr) => {
-        FIFOMarket::<Pubkey, $num_bids, $num_asks, $num_seats>::load_mut_bytes($bytes)
-            .ok_or(PhoenixError::FailedToLoadMarketFromAccount)?
-            as &mut dyn WritableMarket<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>
-    };
-}
-
-macro_rules! fifo_market {
-    ($num_bids:literal, $num_asks:literal, $num_seats:literal, $bytes:expr) => {
-        FIFOMarket::<Pubkey, $num_bids, $num_asks, $num_seats>::load_bytes($bytes)
-            .ok_or(PhoenixError::FailedToLoadMarketFromAccount)?
-            as &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>
-    };
-}
-
-macro_rules! fifo_market_size {
-    ($num_bids:literal, $num_asks:literal, $num_seats:literal) => {
-        std::mem::size_of::<FIFOMarket<Pubkey, $num_bids, $num_asks, $num_seats>>()
-    };
-}
+use solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+use std::mem::size_of;
 
 pub(crate) fn load_with_dispatch_mut<'a>(
     market_size_params: &'a MarketSizeParams,
@@ -52,41 +30,24 @@ pub(crate) fn dispatch_market_mut<'a>(
     is_initial: bool,
 ) -> Result<MarketWrapperMut<'a, Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>, ProgramError>
 {
-    let MarketSizeParams {
-        bids_size,
-        asks_size,
-        num_seats,
-    } = market_size_params;
-    let market = match (bids_size, asks_size, num_seats) {
-        (512, 512, 128) => fifo_market_mut!(512, 512, 128, bytes),
-        (512, 512, 1025) => fifo_market_mut!(512, 512, 1025, bytes),
-        (512, 512, 1153) => fifo_market_mut!(512, 512, 1153, bytes),
-        (1024, 1024, 128) => fifo_market_mut!(1024, 1024, 128, bytes),
-        (1024, 1024, 2049) => fifo_market_mut!(1024, 1024, 2049, bytes),
-        (1024, 1024, 2177) => fifo_market_mut!(1024, 1024, 2177, bytes),
-        (2048, 2048, 128) => fifo_market_mut!(2048, 2048, 128, bytes),
-        (2048, 2048, 4097) => fifo_market_mut!(2048, 2048, 4097, bytes),
-        (2048, 2048, 4225) => fifo_market_mut!(2048, 2048, 4225, bytes),
-        (4096, 4096, 128) => fifo_market_mut!(4096, 4096, 128, bytes),
-        (4096, 4096, 8193) => fifo_market_mut!(4096, 4096, 8193, bytes),
-        (4096, 4096, 8321) => fifo_market_mut!(4096, 4096, 8321, bytes),
-        _ => {
-            return Err(PhoenixError::InvalidMarketParameters.into());
-        }
-    };
+    let (bids_size, asks_size, num_seats) = get_capacities(market_size_params)?;
+    let market = FIFOMarket::<Pubkey>::load_mut_bytes(bytes, bids_size, asks_size, num_seats)
+        .ok_or(PhoenixError::FailedToLoadMarketFromAccount)?;
     if !is_initial {
         assert_with_msg(
             market.get_sequence_number() > 0,
             PhoenixError::MarketUninitialized,
             "Market is not initialized",
         )?;
+        // Orders rely on the stored policy when a side of the book is full
+        market.get_book_full_policy()?;
     }
     Ok(MarketWrapperMut::<
         Pubkey,
         FIFOOrderId,
         FIFORestingOrder,
         OrderPacket,
-    >::new(market))
+    >::new(Box::new(market)))
 }
 
 /// Loads a market from a given buffer and known market params.
@@ -101,79 +62,89 @@ fn dispatch_market<'a>(
     market_size_params: &'a MarketSizeParams,
     bytes: &'a [u8],
 ) -> Result<MarketWrapper<'a, Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>, ProgramError> {
-    let market = match (
-        market_size_params.bids_size,
-        market_size_params.asks_size,
-        market_size_params.num_seats,
-    ) {
-        (512, 512, 128) => fifo_market!(512, 512, 128, bytes),
-        (512, 512, 1025) => fifo_market!(512, 512, 1025, bytes),
-        (512, 512, 1153) => fifo_market!(512, 512, 1153, bytes),
-        (1024, 1024, 128) => fifo_market!(1024, 1024, 128, bytes),
-        (1024, 1024, 2049) => fifo_market!(1024, 1024, 2049, bytes),
-        (1024, 1024, 2177) => fifo_market!(1024, 1024, 2177, bytes),
-        (2048, 2048, 128) => fifo_market!(2048, 2048, 128, bytes),
-        (2048, 2048, 4097) => fifo_market!(2048, 2048, 4097, bytes),
-        (2048, 2048, 4225) => fifo_market!(2048, 2048, 4225, bytes),
-        (4096, 4096, 128) => fifo_market!(4096, 4096, 128, bytes),
-        (4096, 4096, 8193) => fifo_market!(4096, 4096, 8193, bytes),
-        (4096, 4096, 8321) => fifo_market!(4096, 4096, 8321, bytes),
-        _ => {
-            return Err(PhoenixError::InvalidMarketParameters.into());
-        }
-    };
+    let (bids_size, asks_size, num_seats) = get_capacities(market_size_params)?;
+    let market = FIFOMarket::<Pubkey>::load_bytes(bytes, bids_size, asks_size, num_seats)
+        .ok_or(PhoenixError::FailedToLoadMarketFromAccount)?;
     Ok(MarketWrapper::<
         Pubkey,
         FIFOOrderId,
         FIFORestingOrder,
         OrderPacket,
-    >::new(market))
+    >::new(Box::new(market)))
 }
 
-pub fn get_market_size(market_size_params: &MarketSizeParams) -> Result<usize, ProgramError> {
+/// Moves the market data into the layout for larger size params. `bytes` must already be large
+/// enough to hold the market with the new size params.
+pub(crate) fn expand_market_with_dispatch(
+    market_size_params: &MarketSizeParams,
+    new_market_size_params: &MarketSizeParams,
+    bytes: &mut [u8],
+) -> ProgramResult {
+    FIFOMarket::<Pubkey>::expand(
+        bytes,
+        get_capacities(market_size_params)?,
+        get_capacities(new_market_size_params)?,
+    )
+    .ok_or(PhoenixError::InvalidMarketParameters)?;
+    Ok(())
+}
+
+/// Validates the market size params and returns the capacities of the bids, the asks and the
+/// trader map. Every capacity must be one of the tree capacities supported by `get_tree_size`
+/// (see `MarketSizeParams`) and the market account, including its header, must fit within the
+/// maximum account size.
+fn get_capacities(
+    market_size_params: &MarketSizeParams,
+) -> Result<(usize, usize, usize), ProgramError> {
     let MarketSizeParams {
         bids_size,
         asks_size,
         num_seats,
-    } = market_size_params;
-    let size = match (bids_size, asks_size, num_seats) {
-        (512, 512, 128) => fifo_market_size!(512, 512, 128),
-        (512, 512, 1025) => fifo_market_size!(512, 512, 1025),
-        (512, 512, 1153) => fifo_market_size!(512, 512, 1153),
-        (1024, 1024, 128) => fifo_market_size!(1024, 1024, 128),
-        (1024, 1024, 2049) => fifo_market_size!(1024, 1024, 2049),
-        (1024, 1024, 2177) => fifo_market_size!(1024, 1024, 2177),
-        (2048, 2048, 128) => fifo_market_size!(2048, 2048, 128),
-        (2048, 2048, 4097) => fifo_market_size!(2048, 2048, 4097),
-        (2048, 2048, 4225) => fifo_market_size!(2048, 2048, 4225),
-        (4096, 4096, 128) => fifo_market_size!(4096, 4096, 128),
-        (4096, 4096, 8193) => fifo_market_size!(4096, 4096, 8193),
-        (4096, 4096, 8321) => fifo_market_size!(4096, 4096, 8321),
-        _ => {
-            return Err(PhoenixError::InvalidMarketParameters.into());
+    } = *market_size_params;
+    let capacities = [bids_size, asks_size, num_seats].map(|size| usize::try_from(size).ok());
+    match capacities {
+        [Some(bids_size), Some(asks_size), Some(num_seats)]
+            if FIFOMarket::<Pubkey>::get_size(bids_size, asks_size, num_seats).is_some_and(
+                |size| size_of::<MarketHeader>() + size <= MAX_PERMITTED_DATA_LENGTH as usize,
+            ) =>
+        {
+            Ok((bids_size, asks_size, num_seats))
         }
-    };
-    Ok(size)
+        _ => Err(PhoenixError::InvalidMarketParameters.into()),
+    }
+}
+
+pub fn get_market_size(market_size_params: &MarketSizeParams) -> Result<usize, ProgramError> {
+    let (bids_size, asks_size, num_seats) = get_capacities(market_size_params)?;
+    FIFOMarket::<Pubkey>::get_size(bids_size, asks_size, num_seats)
+        .ok_or_else(|| PhoenixError::InvalidMarketParameters.into())
 }
 
 #[test]
 fn test_market_size() {
     use solana_program::rent::Rent;
-    let valid_configs = [
-        (512, 512, 128),
-        (512, 512, 1025),
-        (512, 512, 1153),
-        (1024, 1024, 128),
-        (1024, 1024, 2049),
-        (1024, 1024, 2177),
-        (2048, 2048, 128),
-        (2048, 2048, 4097),
-        (2048, 2048, 4225),
-        (4096, 4096, 128),
-        (4096, 4096, 8193),
-        (4096, 4096, 8321),
+    // Sizes of the markets supported before market sizes were computed at runtime. These only
+    // change with the layout of the market, e.g. when `FIFORestingOrder` grew to hold `linked_order`.
+    let legacy_configs = [
+        ((512, 512, 128), 92560),
+        ((512, 512, 1025), 221728),
+        ((512, 512, 1153), 240160),
+        ((1024, 1024, 128), 166288),
+        ((1024, 1024, 2049), 442912),
+        ((1024, 1024, 2177), 461344),
+        ((2048, 2048, 128), 313744),
+        ((2048, 2048, 4097), 885280),
+        ((2048, 2048, 4225), 903712),
+        ((4096, 4096, 128), 608656),
+        ((4096, 4096, 8193), 1770016),
+        ((4096, 4096, 8321), 1788448),
     ];
-    for (bids_size, asks_size, num_seats) in valid_configs.into_iter() {
+    let other_configs = [(256, 256, 16), (1024, 128, 64), (4, 4, 4), (65536, 4, 4)];
+    for (bids_size, asks_size, num_seats) in legacy_configs
+        .iter()
+        .map(|(config, _)| *config)
+        .chain(other_configs)
+    {
         let market_size_params = MarketSizeParams {
             bids_size,
             asks_size,
@@ -188,14 +159,1282 @@ fn test_market_size() {
                 size,
                 Rent::default().minimum_balance(size) as f64 / 1e9
             );
+            let mut data = vec![0_u64; size / 8];
+            let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+            {
+                let market = load_with_dispatch_init(&market_size_params, bytes).unwrap();
+                assert_eq!(market.inner.get_data_size(), size);
+            }
+            assert!(load_with_dispatch_init(&market_size_params, &mut bytes[..size - 8]).is_err());
         } else {
             panic!("Invalid market size params")
         }
     }
-    assert!(get_market_size(&MarketSizeParams {
-        bids_size: 1234,
-        asks_size: 89345,
-        num_seats: 2134
-    })
-    .is_err());
+    for ((bids_size, asks_size, num_seats), expected_size) in legacy_configs.into_iter() {
+        let market_size_params = MarketSizeParams {
+            bids_size,
+            asks_size,
+            num_seats,
+        };
+        assert_eq!(get_market_size(&market_size_params).unwrap(), expected_size);
+    }
+    for (bids_size, asks_size, num_seats) in [
+        (0, 512, 128),
+        (512, 0, 128),
+        (512, 512, 0),
+        (1, 1, 1),
+        (512, 512, 100),
+        (300, 512, 128),
+        (512, 300, 128),
+        (100_000, 100_000, 2134),
+        (65536, 65536, 65536),
+        (u64::MAX, 1, 1),
+    ] {
+        assert!(get_market_size(&MarketSizeParams {
+            bids_size,
+            asks_size,
+            num_seats
+        })
+        .is_err());
+    }
+}
+
+#[test]
+fn test_expand_market() {
+    use crate::quantities::{
+        BaseLots, BaseLotsPerBaseUnit, QuoteLotsPerBaseUnitPerTick, WrapperU64,
+    };
+    use crate::state::Side;
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 16,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let new_market_size_params = MarketSizeParams {
+        bids_size: 64,
+        asks_size: 8,
+        num_seats: 16,
+    };
+    let new_size = get_market_size(&new_market_size_params).unwrap();
+    let mut data = vec![0_u64; new_size / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+
+    let snapshot = |market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>| {
+        let mut snapshot = vec![market.get_sequence_number()];
+        for side in [Side::Bid, Side::Ask] {
+            for (order_id, order) in market.get_book(side).iter() {
+                snapshot.extend_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(order_id)));
+                snapshot.extend_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(order)));
+            }
+        }
+        for (trader, state) in market.get_registered_traders().iter() {
+            snapshot.push(market.get_trader_index(trader).unwrap() as u64);
+            snapshot.extend_from_slice(bytemuck::cast_slice(bytemuck::bytes_of(state)));
+        }
+        snapshot
+    };
+
+    let expected = {
+        let mut market = load_with_dispatch_init(&market_size_params, bytes)
+            .unwrap()
+            .inner;
+        market.initialize_with_params(
+            QuoteLotsPerBaseUnitPerTick::new(10),
+            BaseLotsPerBaseUnit::new(1),
+        );
+        let traders = (0..4)
+            .map(|_| {
+                market
+                    .get_or_register_trader(&Pubkey::new_unique())
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        for i in 0..16_u64 {
+            let trader_index = traders[i as usize % traders.len()];
+            let order = FIFORestingOrder::new_default(trader_index as u64, BaseLots::new(i + 1));
+            market
+                .get_book_mut(Side::Bid)
+                .insert(FIFOOrderId::new_from_untyped(100 - i, !i), order)
+                .unwrap();
+            if i < 8 {
+                market
+                    .get_book_mut(Side::Ask)
+                    .insert(FIFOOrderId::new_from_untyped(200 + i, i), order)
+                    .unwrap();
+            }
+        }
+        // Leave holes in the free lists of the trees
+        market
+            .get_book_mut(Side::Bid)
+            .remove(&FIFOOrderId::new_from_untyped(95, !5))
+            .unwrap();
+        let trader_id = market.get_trader_id_from_index(traders[1]);
+        market
+            .get_registered_traders_mut()
+            .remove(&trader_id)
+            .unwrap();
+        snapshot(market.as_ref())
+    };
+
+    assert!(
+        expand_market_with_dispatch(&new_market_size_params, &market_size_params, bytes).is_err()
+    );
+    expand_market_with_dispatch(&market_size_params, &new_market_size_params, bytes).unwrap();
+
+    let mut market = load_with_dispatch_mut(&new_market_size_params, bytes)
+        .unwrap()
+        .inner;
+    assert_eq!(snapshot(market.as_ref()), expected);
+    // The additional capacity is usable
+    for i in 0..49_u64 {
+        let order = FIFORestingOrder::new_default(1, BaseLots::new(1));
+        market
+            .get_book_mut(Side::Bid)
+            .insert(FIFOOrderId::new_from_untyped(1000 + i, !(1000 + i)), order)
+            .unwrap();
+    }
+    assert_eq!(market.get_book(Side::Bid).len(), 64);
+    while market.get_registered_traders().len() < 16 {
+        market
+            .get_or_register_trader(&Pubkey::new_unique())
+            .unwrap();
+    }
+}
+
+#[test]
+fn test_evict_inactive_trader() {
+    use crate::quantities::{
+        BaseLots, BaseLotsPerBaseUnit, QuoteLots, QuoteLotsPerBaseUnitPerTick, WrapperU64,
+    };
+    use crate::state::Side;
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    let mut market = load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner;
+    market.initialize_with_params(
+        QuoteLotsPerBaseUnitPerTick::new(10),
+        BaseLotsPerBaseUnit::new(1),
+    );
+    let traders = (0..4).map(|_| Pubkey::new_unique()).collect::<Vec<_>>();
+    for (slot, trader) in traders.iter().enumerate() {
+        market.get_or_register_trader(trader).unwrap();
+        let trader_state = market.get_trader_state_mut(trader).unwrap();
+        trader_state.last_activity_slot = 10 - slot as u64;
+        trader_state.quote_lots_free = QuoteLots::new(slot as u64);
+    }
+    // The least recently active trader has an open order
+    let trader_index = market.get_trader_index(&traders[3]).unwrap();
+    market
+        .get_book_mut(Side::Bid)
+        .insert(
+            FIFOOrderId::new_from_untyped(100, 0),
+            FIFORestingOrder::new_default(trader_index as u64, BaseLots::new(1)),
+        )
+        .unwrap();
+    market
+        .get_trader_state_mut(&traders[3])
+        .unwrap()
+        .quote_lots_locked = QuoteLots::new(1000);
+
+    // Only the least recently active trader without open orders can be evicted
+    assert!(market.evict_inactive_trader(&traders[3]).is_none());
+    assert!(market.evict_inactive_trader(&traders[0]).is_none());
+    let evicted = market.evict_inactive_trader(&traders[2]).unwrap();
+    assert_eq!(evicted.quote_lots_free, QuoteLots::new(2));
+    assert!(market.get_trader_index(&traders[2]).is_none());
+
+    // Traders cannot be evicted while there are free seats
+    assert!(market.evict_inactive_trader(&traders[1]).is_none());
+    let new_trader = Pubkey::new_unique();
+    market.get_or_register_trader(&new_trader).unwrap();
+    market
+        .get_trader_state_mut(&new_trader)
+        .unwrap()
+        .last_activity_slot = 20;
+    assert!(market.evict_inactive_trader(&traders[1]).is_some());
+}
+
+#[test]
+fn test_fill_event_fees() {
+    use crate::quantities::{
+        BaseLots, BaseLotsPerBaseUnit, QuoteLots, QuoteLotsPerBaseUnitPerTick, WrapperU64,
+    };
+    use crate::state::{markets::MarketEvent, SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    let mut market = load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner;
+    market.initialize_with_params(
+        QuoteLotsPerBaseUnitPerTick::new(10),
+        BaseLotsPerBaseUnit::new(10),
+    );
+    market.set_fee(7);
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    for (price_in_ticks, num_base_lots) in [(101, 3), (103, 7), (107, 11)] {
+        market
+            .place_order(
+                &maker,
+                OrderPacket::new_post_only_default(Side::Ask, price_in_ticks, num_base_lots),
+                &mut |_| {},
+                &mut || (0, 0),
+            )
+            .unwrap();
+    }
+
+    let mut events = vec![];
+    market
+        .place_order(
+            &taker,
+            OrderPacket::new_ioc_by_lots(
+                Side::Bid,
+                107,
+                15,
+                SelfTradeBehavior::Abort,
+                None,
+                0,
+                false,
+            ),
+            &mut |e| events.push(e),
+            &mut || (0, 0),
+        )
+        .unwrap();
+
+    let mut num_fills = 0;
+    let mut total_quote_lots_filled = QuoteLots::ZERO;
+    let mut total_fees = QuoteLots::ZERO;
+    for event in events.iter() {
+        if let MarketEvent::Fill {
+            maker_id,
+            price_in_ticks,
+            base_lots_filled,
+            taker_id,
+            taker_side,
+            quote_lots_filled,
+            fee_in_quote_lots,
+            ..
+        } = *event
+        {
+            assert_eq!(maker_id, maker);
+            assert_eq!(taker_id, taker);
+            assert_eq!(taker_side, Side::Bid);
+            // Each fill is within a quote lot of its exact amount
+            let exact_quote_lots = price_in_ticks.as_u64() * base_lots_filled.as_u64();
+            assert!(quote_lots_filled.as_u64().abs_diff(exact_quote_lots) <= 1);
+            num_fills += 1;
+            total_quote_lots_filled += quote_lots_filled;
+            total_fees += fee_in_quote_lots;
+        }
+    }
+    assert_eq!(num_fills, 3);
+    // The fees of the fills add up to the fee charged for the whole order
+    match events.last().unwrap() {
+        MarketEvent::FillSummary {
+            total_base_lots_filled,
+            total_quote_lots_filled: summary_quote_lots_filled,
+            total_fee_in_quote_lots,
+            ..
+        } => {
+            assert_eq!(*total_base_lots_filled, BaseLots::new(15));
+            assert_eq!(
+                *summary_quote_lots_filled,
+                total_quote_lots_filled + total_fees
+            );
+            assert_eq!(*total_fee_in_quote_lots, total_fees);
+            assert!(total_fees > QuoteLots::ZERO);
+        }
+        e => panic!("Expected a fill summary, found {:?}", e),
+    }
+}
+
+/// Runs a test against a newly initialized market with the given capacities, tick size (in quote
+/// lots per base unit per tick) and number of base lots per base unit
+#[cfg(test)]
+pub(crate) fn with_test_market(
+    market_size_params: MarketSizeParams,
+    tick_size: u64,
+    base_lots_per_base_unit: u64,
+    test: impl FnOnce(
+        &mut dyn crate::state::markets::WritableMarket<
+            Pubkey,
+            FIFOOrderId,
+            FIFORestingOrder,
+            OrderPacket,
+        >,
+    ),
+) {
+    use crate::quantities::{BaseLotsPerBaseUnit, QuoteLotsPerBaseUnitPerTick, WrapperU64};
+
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    let mut market = load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner;
+    market.initialize_with_params(
+        QuoteLotsPerBaseUnitPerTick::new(tick_size),
+        BaseLotsPerBaseUnit::new(base_lots_per_base_unit),
+    );
+    test(market.as_mut());
+}
+
+#[test]
+fn test_book_full_policies() {
+    use crate::quantities::{Ticks, WrapperU64};
+    use crate::state::{markets::MarketEvent, BookFullPolicy, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 4,
+        asks_size: 4,
+        num_seats: 4,
+    };
+    // (price in ticks, base lots) of the bids resting on the full book, from oldest to newest.
+    // The least aggressive bid is at 99, the smallest notional at 102 and the oldest at 100.
+    let resting_bids = [(100, 20), (101, 50), (99, 10), (102, 2)];
+    // Returns the price of the bid that is evicted to place a new bid, if the bid is placed
+    let evicted_price = |policy: BookFullPolicy, min_improvement: u64, new_price: u64| {
+        let mut evicted = None;
+        let mut placed = false;
+        with_test_market(market_size_params, 10, 1, |market| {
+            market.set_book_full_policy(policy, Ticks::new(min_improvement));
+            let maker = Pubkey::new_unique();
+            for (price_in_ticks, num_base_lots) in resting_bids {
+                market
+                    .place_order(
+                        &maker,
+                        OrderPacket::new_post_only_default(
+                            Side::Bid,
+                            price_in_ticks,
+                            num_base_lots,
+                        ),
+                        &mut |_| {},
+                        &mut || (0, 0),
+                    )
+                    .unwrap();
+            }
+            let book_before = market.get_book(Side::Bid).iter().count();
+            placed = market
+                .place_order(
+                    &maker,
+                    OrderPacket::new_post_only_default(Side::Bid, new_price, 1),
+                    &mut |e| {
+                        if let MarketEvent::Evict { price_in_ticks, .. } = e {
+                            evicted = Some(price_in_ticks.as_u64());
+                        }
+                    },
+                    &mut || (0, 0),
+                )
+                .is_some();
+            assert_eq!(market.get_book(Side::Bid).iter().count(), book_before);
+        });
+        assert_eq!(placed, evicted.is_some());
+        evicted
+    };
+
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictLeastAggressive, 0, 103),
+        Some(99)
+    );
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictSmallestNotional, 0, 103),
+        Some(102)
+    );
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictOldest, 0, 103),
+        Some(100)
+    );
+    assert_eq!(evicted_price(BookFullPolicy::RejectNewOrder, 0, 103), None);
+
+    // Only orders that the new order improves on by the minimum improvement can be evicted
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictSmallestNotional, 2, 103),
+        Some(99)
+    );
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictOldest, 3, 103),
+        Some(100)
+    );
+    assert_eq!(evicted_price(BookFullPolicy::EvictOldest, 4, 103), Some(99));
+    assert_eq!(evicted_price(BookFullPolicy::EvictOldest, 5, 103), None);
+    // An order at the same price as the least aggressive order never evicts it
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictLeastAggressive, 0, 99),
+        None
+    );
+    assert_eq!(
+        evicted_price(BookFullPolicy::EvictLeastAggressive, 0, 100),
+        Some(99)
+    );
+
+    assert_eq!(
+        BookFullPolicy::try_from(4),
+        Err(PhoenixError::InvalidBookFullPolicy)
+    );
+
+    // A market with a corrupt policy fails to load instead of silently rejecting evictions
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner
+        .initialize_with_params(
+            crate::quantities::QuoteLotsPerBaseUnitPerTick::new(10),
+            crate::quantities::BaseLotsPerBaseUnit::new(1),
+        );
+    assert!(load_with_dispatch_mut(&market_size_params, bytes).is_ok());
+    // The policy is the first field of the market state
+    bytes[..8].copy_from_slice(&4_u64.to_le_bytes());
+    assert_eq!(
+        load_with_dispatch_mut(&market_size_params, bytes).err(),
+        Some(PhoenixError::InvalidBookFullPolicy.into())
+    );
+}
+
+#[test]
+fn test_self_trade_behaviors() {
+    use crate::quantities::{BaseLots, WrapperU64};
+    use crate::state::{markets::MarketEvent, SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let trader = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    // (trader, price in ticks, base lots) of the asks, in the order they are placed
+    let resting_asks = [
+        (other, 100, 7),
+        (trader, 100, 5),
+        (other, 100, 2),
+        (other, 101, 4),
+        (trader, 102, 6),
+    ];
+    // Returns the asks left on the book as (trader, price in ticks, order sequence number, base
+    // lots), along with the events of a bid from `trader` that crosses all of the asks
+    let run = |self_trade_behavior: SelfTradeBehavior| {
+        let mut book = vec![];
+        let mut events = vec![];
+        with_test_market(market_size_params, 10, 1, |market| {
+            for (maker, price_in_ticks, num_base_lots) in resting_asks {
+                market
+                    .place_order(
+                        &maker,
+                        OrderPacket::new_post_only_default(
+                            Side::Ask,
+                            price_in_ticks,
+                            num_base_lots,
+                        ),
+                        &mut |_| {},
+                        &mut || (0, 0),
+                    )
+                    .unwrap();
+            }
+            let trader_base_lots_locked =
+                market.get_trader_state(&trader).unwrap().base_lots_locked;
+            assert_eq!(trader_base_lots_locked, BaseLots::new(11));
+            market
+                .place_order(
+                    &trader,
+                    OrderPacket::new_ioc_by_lots(
+                        Side::Bid,
+                        102,
+                        14,
+                        self_trade_behavior,
+                        None,
+                        0,
+                        false,
+                    ),
+                    &mut |e| events.push(e),
+                    &mut || (0, 0),
+                )
+                .unwrap();
+            book = market
+                .get_book(Side::Ask)
+                .iter()
+                .map(|(order_id, order)| {
+                    (
+                        market.get_trader_id_from_index(order.trader_index as u32),
+                        order_id.price_in_ticks.as_u64(),
+                        order_id.order_sequence_number,
+                        order.num_base_lots.as_u64(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            // Only the cancelled resting order unlocks the trader's funds
+            let cancelled_base_lots = if self_trade_behavior == SelfTradeBehavior::CancelBoth {
+                5
+            } else {
+                0
+            };
+            assert_eq!(
+                market.get_trader_state(&trader).unwrap().base_lots_locked,
+                trader_base_lots_locked - BaseLots::new(cancelled_base_lots)
+            );
+        });
+        let fills = events
+            .iter()
+            .filter_map(|e| match e {
+                MarketEvent::Fill {
+                    order_sequence_number,
+                    base_lots_filled,
+                    ..
+                } => Some((*order_sequence_number, base_lots_filled.as_u64())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let self_trades = events
+            .iter()
+            .filter(|e| matches!(e, MarketEvent::SelfTrade { .. }))
+            .count();
+        let reductions = events
+            .iter()
+            .filter_map(|e| match e {
+                MarketEvent::Reduce {
+                    order_sequence_number,
+                    base_lots_remaining,
+                    ..
+                } => Some((*order_sequence_number, base_lots_remaining.as_u64())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        (book, fills, self_trades, reductions)
+    };
+
+    // The trader's asks are skipped and keep their place in the queue and their size
+    let (book, fills, self_trades, reductions) = run(SelfTradeBehavior::SkipProvide);
+    assert_eq!(fills, vec![(1, 7), (3, 2), (4, 4)]);
+    assert_eq!(self_trades, 2);
+    assert!(reductions.is_empty());
+    assert_eq!(book, vec![(trader, 100, 2, 5), (trader, 102, 5, 6)]);
+
+    // Matching stops at the first ask of the trader, which is left untouched
+    let (book, fills, self_trades, reductions) = run(SelfTradeBehavior::CancelTake);
+    assert_eq!(fills, vec![(1, 7)]);
+    assert_eq!(self_trades, 1);
+    assert!(reductions.is_empty());
+    assert_eq!(
+        book,
+        vec![
+            (trader, 100, 2, 5),
+            (other, 100, 3, 2),
+            (other, 101, 4, 4),
+            (trader, 102, 5, 6)
+        ]
+    );
+
+    // The first ask of the trader is cancelled and matching stops
+    let (book, fills, self_trades, reductions) = run(SelfTradeBehavior::CancelBoth);
+    assert_eq!(fills, vec![(1, 7)]);
+    assert_eq!(self_trades, 1);
+    assert_eq!(reductions, vec![(2, 0)]);
+    assert_eq!(
+        book,
+        vec![(other, 100, 3, 2), (other, 101, 4, 4), (trader, 102, 5, 6)]
+    );
+}
+
+#[test]
+fn test_self_trade_prevention_group() {
+    use crate::quantities::WrapperU64;
+    use crate::state::{markets::MarketEvent, SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let group = Pubkey::new_unique();
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    with_test_market(market_size_params, 10, 1, |market| {
+        for trader in [maker, taker] {
+            market.get_or_register_trader(&trader).unwrap();
+            market
+                .get_trader_state_mut(&trader)
+                .unwrap()
+                .self_trade_prevention_group = group;
+        }
+        for (trader, num_base_lots) in [(maker, 5), (other, 3)] {
+            market
+                .place_order(
+                    &trader,
+                    OrderPacket::new_post_only_default(Side::Ask, 100, num_base_lots),
+                    &mut |_| {},
+                    &mut || (0, 0),
+                )
+                .unwrap();
+        }
+        let mut events = vec![];
+        market
+            .place_order(
+                &taker,
+                OrderPacket::new_ioc_by_lots(
+                    Side::Bid,
+                    100,
+                    8,
+                    SelfTradeBehavior::SkipProvide,
+                    None,
+                    0,
+                    false,
+                ),
+                &mut |e| events.push(e),
+                &mut || (0, 0),
+            )
+            .unwrap();
+        // The order of the other group member is skipped, only the order of `other` is filled
+        assert!(matches!(
+            events.as_slice(),
+            [
+                MarketEvent::SelfTrade { .. },
+                MarketEvent::Fill {
+                    maker_id,
+                    base_lots_filled,
+                    ..
+                },
+                MarketEvent::FillSummary { .. },
+            ] if *maker_id == other && base_lots_filled.as_u64() == 3
+        ));
+        let book = market
+            .get_book(Side::Ask)
+            .iter()
+            .map(|(_, order)| {
+                (
+                    market.get_trader_id_from_index(order.trader_index as u32),
+                    order.num_base_lots.as_u64(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(book, vec![(maker, 5)]);
+
+        // A trader without a group trades against the group members
+        let mut events = vec![];
+        market
+            .place_order(
+                &other,
+                OrderPacket::new_ioc_by_lots(
+                    Side::Bid,
+                    100,
+                    5,
+                    SelfTradeBehavior::Abort,
+                    None,
+                    0,
+                    false,
+                ),
+                &mut |e| events.push(e),
+                &mut || (0, 0),
+            )
+            .unwrap();
+        assert!(matches!(
+            events.as_slice(),
+            [MarketEvent::Fill { maker_id, .. }, MarketEvent::FillSummary { .. }]
+                if *maker_id == maker
+        ));
+
+        // A trader with a group and no funds is still evicted when claiming funds
+        let taker_state = market.get_trader_state(&taker).unwrap();
+        assert_eq!(taker_state.self_trade_prevention_group, group);
+        assert!(taker_state.is_empty());
+        market.claim_funds(&taker, None, None, true).unwrap();
+        assert!(market.get_trader_state(&taker).is_none());
+    });
+}
+
+#[test]
+fn test_limit_order_fill_requirements() {
+    use crate::quantities::{BaseLots, WrapperU64};
+    use crate::state::{SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let trader = Pubkey::new_unique();
+    let maker = Pubkey::new_unique();
+    // Places a limit bid for 15 base lots against asks of 5 base lots at 100 and 101 ticks. Returns
+    // the matched base lots and the bids on the book, or None if the order is voided
+    let run = |price_in_ticks: u64,
+               match_limit: Option<u64>,
+               min_fill: Option<u64>,
+               all_or_none: bool| {
+        let mut result = None;
+        with_test_market(market_size_params, 10, 1, |market| {
+            for ask_price_in_ticks in [100, 101] {
+                market
+                    .place_order(
+                        &maker,
+                        OrderPacket::new_post_only_default(Side::Ask, ask_price_in_ticks, 5),
+                        &mut |_| {},
+                        &mut || (0, 0),
+                    )
+                    .unwrap();
+            }
+            market.get_or_register_trader(&trader).unwrap();
+            let mut order_packet = OrderPacket::new_limit_order(
+                Side::Bid,
+                price_in_ticks,
+                15,
+                SelfTradeBehavior::Abort,
+                match_limit,
+                0,
+                false,
+            );
+            if let OrderPacket::Limit {
+                min_base_lots_to_fill,
+                all_or_none_on_cross,
+                ..
+            } = &mut order_packet
+            {
+                *min_base_lots_to_fill = min_fill.map(BaseLots::new);
+                *all_or_none_on_cross = all_or_none;
+            }
+            let asks_before = market.get_book(Side::Ask).iter().count();
+            let trader_state_before = *market.get_trader_state(&trader).unwrap();
+            result = market
+                .place_order(&trader, order_packet, &mut |_| {}, &mut || (0, 0))
+                .map(|(_, matching_engine_response)| {
+                    let bids = market
+                        .get_book(Side::Bid)
+                        .iter()
+                        .map(|(order_id, order)| {
+                            (
+                                order_id.price_in_ticks.as_u64(),
+                                order.num_base_lots.as_u64(),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    (matching_engine_response.num_base_lots().as_u64(), bids)
+                });
+            // An order that is voided without crossing the book leaves the market untouched
+            if result.is_none() && price_in_ticks < 100 {
+                assert_eq!(market.get_book(Side::Ask).iter().count(), asks_before);
+                assert_eq!(market.get_book(Side::Bid).iter().count(), 0);
+                assert_eq!(
+                    *market.get_trader_state(&trader).unwrap(),
+                    trader_state_before
+                );
+            }
+        });
+        result
+    };
+
+    // The order is voided unless the minimum number of base lots is matched. The engine returns None
+    // after matching, which the processor turns into an error so that the fills are discarded.
+    assert_eq!(run(101, None, Some(11), false), None);
+    assert_eq!(run(101, None, Some(10), false), Some((10, vec![(101, 5)])));
+    assert_eq!(run(101, Some(1), Some(5), false), Some((5, vec![])));
+    assert_eq!(run(99, None, Some(1), false), None);
+    assert_eq!(run(99, None, Some(0), false), Some((0, vec![(99, 15)])));
+
+    // The order is voided if part of it still crosses the book after matching
+    assert_eq!(run(101, Some(1), None, true), None);
+    assert_eq!(run(101, None, None, true), Some((10, vec![(101, 5)])));
+    assert_eq!(run(99, None, None, true), Some((0, vec![(99, 15)])));
+    // Without the flag, the crossing remainder is not posted
+    assert_eq!(run(101, Some(1), None, false), Some((5, vec![])));
+}
+
+#[test]
+fn test_market_to_limit_order() {
+    use crate::quantities::WrapperU64;
+    use crate::state::{SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let trader = Pubkey::new_unique();
+    let maker = Pubkey::new_unique();
+    // Places a market-to-limit order against resting orders of 5 base lots at each of the given
+    // prices. Returns the matched base lots and the orders left on the book on the side of the
+    // market-to-limit order, or None if the order is voided
+    let run = |side: Side, resting_prices_in_ticks: &[u64], order_packet: OrderPacket| {
+        let mut result = None;
+        with_test_market(market_size_params, 10, 1, |market| {
+            for price_in_ticks in resting_prices_in_ticks {
+                market
+                    .place_order(
+                        &maker,
+                        OrderPacket::new_post_only_default(side.opposite(), *price_in_ticks, 5),
+                        &mut |_| {},
+                        &mut || (0, 0),
+                    )
+                    .unwrap();
+            }
+            result = market
+                .place_order(&trader, order_packet, &mut |_| {}, &mut || (0, 0))
+                .map(|(order_id, matching_engine_response)| {
+                    let book = market
+                        .get_book(side)
+                        .iter()
+                        .map(|(order_id, order)| {
+                            (
+                                order_id.price_in_ticks.as_u64(),
+                                order.num_base_lots.as_u64(),
+                            )
+                        })
+                        .collect::<Vec<_>>();
+                    assert_eq!(order_id.is_some(), !book.is_empty());
+                    (matching_engine_response.num_base_lots().as_u64(), book)
+                });
+        });
+        result
+    };
+
+    // The remainder is posted at the price of the last fill
+    assert_eq!(
+        run(
+            Side::Bid,
+            &[100, 102],
+            OrderPacket::new_market_to_limit_order_default(Side::Bid, 12)
+        ),
+        Some((10, vec![(102, 2)]))
+    );
+    assert_eq!(
+        run(
+            Side::Bid,
+            &[100, 102],
+            OrderPacket::new_market_to_limit_order_default(Side::Bid, 8)
+        ),
+        Some((8, vec![]))
+    );
+    // The limit price and match limit stop the sweep, and the remainder does not cross the book
+    assert_eq!(
+        run(
+            Side::Bid,
+            &[100, 102],
+            OrderPacket::new_market_to_limit_order(
+                Side::Bid,
+                Some(101),
+                8,
+                SelfTradeBehavior::CancelProvide,
+                None,
+                0,
+                false
+            )
+        ),
+        Some((5, vec![(100, 3)]))
+    );
+    assert_eq!(
+        run(
+            Side::Bid,
+            &[100, 102],
+            OrderPacket::new_market_to_limit_order(
+                Side::Bid,
+                None,
+                8,
+                SelfTradeBehavior::CancelProvide,
+                Some(1),
+                0,
+                false
+            )
+        ),
+        Some((5, vec![(100, 3)]))
+    );
+    // Asks without a limit price are matched down to the lowest valid price
+    assert_eq!(
+        run(
+            Side::Ask,
+            &[1, 3],
+            OrderPacket::new_market_to_limit_order_default(Side::Ask, 12)
+        ),
+        Some((10, vec![(1, 2)]))
+    );
+    // The order is voided if nothing is matched
+    assert_eq!(
+        run(
+            Side::Bid,
+            &[],
+            OrderPacket::new_market_to_limit_order_default(Side::Bid, 5)
+        ),
+        None
+    );
+    assert_eq!(
+        run(
+            Side::Ask,
+            &[100],
+            OrderPacket::new_market_to_limit_order(
+                Side::Ask,
+                Some(101),
+                5,
+                SelfTradeBehavior::CancelProvide,
+                None,
+                0,
+                false
+            )
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_linked_orders() {
+    use crate::quantities::{BaseLots, WrapperU64};
+    use crate::state::{markets::MarketEvent, SelfTradeBehavior, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let trader = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    let post_linked = |side: Side, price_in_ticks: u64, num_base_lots: u64, link: u32| {
+        let mut order_packet =
+            OrderPacket::new_post_only_default(side, price_in_ticks, num_base_lots);
+        if let OrderPacket::PostOnly { link_id, .. } = &mut order_packet {
+            *link_id = Some(link);
+        }
+        order_packet
+    };
+    type TestMarket<'a> = dyn crate::state::markets::WritableMarket<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>
+        + 'a;
+    let place = |market: &mut TestMarket, trader: &Pubkey, order_packet: OrderPacket| {
+        let mut events = vec![];
+        let (order_id, _) = market
+            .place_order(trader, order_packet, &mut |e| events.push(e), &mut || {
+                (0, 0)
+            })
+            .unwrap();
+        (order_id, events)
+    };
+    let get_size = |market: &TestMarket, side: Side, order_id: &FIFOOrderId| {
+        market
+            .get_book(side)
+            .get(order_id)
+            .map(|order| order.num_base_lots.as_u64())
+    };
+    let reductions = |events: &[MarketEvent<Pubkey>]| {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                MarketEvent::Reduce {
+                    order_sequence_number,
+                    base_lots_remaining,
+                    ..
+                } => Some((*order_sequence_number, base_lots_remaining.as_u64())),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    // Filling either leg of a pair reduces the other leg by the same amount
+    with_test_market(market_size_params, 10, 1, |market| {
+        let ask = place(market, &trader, post_linked(Side::Ask, 100, 5, 7))
+            .0
+            .unwrap();
+        let bid = place(market, &trader, post_linked(Side::Bid, 90, 5, 7))
+            .0
+            .unwrap();
+        let (_, events) = place(
+            market,
+            &taker,
+            OrderPacket::new_ioc_by_lots(
+                Side::Bid,
+                100,
+                3,
+                SelfTradeBehavior::Abort,
+                None,
+                0,
+                false,
+            ),
+        );
+        assert_eq!(get_size(market, Side::Ask, &ask), Some(2));
+        assert_eq!(get_size(market, Side::Bid, &bid), Some(2));
+        assert_eq!(reductions(&events), vec![(bid.order_sequence_number, 2)]);
+        let trader_state = market.get_trader_state(&trader).unwrap();
+        assert_eq!(trader_state.base_lots_locked, BaseLots::new(2));
+
+        let (_, events) = place(
+            market,
+            &taker,
+            OrderPacket::new_ioc_by_lots(
+                Side::Ask,
+                90,
+                2,
+                SelfTradeBehavior::Abort,
+                None,
+                0,
+                false,
+            ),
+        );
+        assert_eq!(get_size(market, Side::Ask, &ask), None);
+        assert_eq!(get_size(market, Side::Bid, &bid), None);
+        assert_eq!(reductions(&events), vec![(ask.order_sequence_number, 0)]);
+        let trader_state = market.get_trader_state(&trader).unwrap();
+        assert_eq!(trader_state.base_lots_locked, BaseLots::ZERO);
+        assert_eq!(trader_state.quote_lots_locked.as_u64(), 0);
+    });
+
+    // Only the trader's most recent unlinked order can be linked to, and a link id can be reused
+    // once its pair is complete
+    with_test_market(market_size_params, 10, 1, |market| {
+        let ioc_bid = |price_in_ticks: u64, num_base_lots: u64| {
+            OrderPacket::new_ioc_by_lots(
+                Side::Bid,
+                price_in_ticks,
+                num_base_lots,
+                SelfTradeBehavior::Abort,
+                None,
+                0,
+                false,
+            )
+        };
+        let mut orders = vec![];
+        for (price_in_ticks, link) in [(100, 1), (101, 2), (102, 1), (103, 1), (104, 1), (105, 1)] {
+            orders.push(
+                place(
+                    market,
+                    &trader,
+                    post_linked(Side::Ask, price_in_ticks, 5, link),
+                )
+                .0
+                .unwrap(),
+            );
+        }
+        // The first order is replaced as the pending order by the second, so it is never linked
+        let (_, events) = place(market, &taker, ioc_bid(101, 10));
+        assert!(reductions(&events).is_empty());
+        // The third and fourth orders form a pair
+        let (_, events) = place(market, &taker, ioc_bid(102, 2));
+        assert_eq!(
+            reductions(&events),
+            vec![(orders[3].order_sequence_number, 3)]
+        );
+        assert_eq!(get_size(market, Side::Ask, &orders[2]), Some(3));
+        assert_eq!(get_size(market, Side::Ask, &orders[3]), Some(3));
+        // The fifth and sixth orders form a new pair with the same link id
+        let (_, events) = place(market, &taker, ioc_bid(105, 10));
+        assert_eq!(
+            reductions(&events),
+            vec![
+                (orders[3].order_sequence_number, 0),
+                (orders[5].order_sequence_number, 0)
+            ]
+        );
+        assert_eq!(market.get_book(Side::Ask).iter().count(), 0);
+        assert_eq!(
+            market.get_trader_state(&trader).unwrap().base_lots_locked,
+            BaseLots::ZERO
+        );
+    });
+
+    // Links survive orders being skipped by a self trade and increased
+    with_test_market(market_size_params, 10, 1, |market| {
+        let ask = place(market, &trader, post_linked(Side::Ask, 100, 5, 3))
+            .0
+            .unwrap();
+        let other_ask = place(market, &trader, post_linked(Side::Ask, 101, 5, 3))
+            .0
+            .unwrap();
+        let (_, events) = place(
+            market,
+            &trader,
+            OrderPacket::new_ioc_by_lots(
+                Side::Bid,
+                101,
+                5,
+                SelfTradeBehavior::SkipProvide,
+                None,
+                0,
+                false,
+            ),
+        );
+        assert!(reductions(&events).is_empty());
+        let (new_ask, _) = market
+            .increase_order(
+                &trader,
+                &ask,
+                Side::Ask,
+                BaseLots::new(2),
+                0,
+                false,
+                &mut |_| {},
+                &mut || (0, 0),
+            )
+            .unwrap();
+        let (_, events) = place(
+            market,
+            &taker,
+            OrderPacket::new_ioc_by_lots(
+                Side::Bid,
+                100,
+                4,
+                SelfTradeBehavior::Abort,
+                None,
+                0,
+                false,
+            ),
+        );
+        assert_eq!(get_size(market, Side::Ask, &new_ask), Some(3));
+        assert_eq!(get_size(market, Side::Ask, &other_ask), Some(1));
+        assert_eq!(
+            reductions(&events),
+            vec![(other_ask.order_sequence_number, 1)]
+        );
+    });
+}
+
+#[test]
+fn test_increase_order() {
+    use crate::quantities::{BaseLots, QuoteLots, WrapperU64};
+    use crate::state::{markets::MarketEvent, Side};
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let trader = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    with_test_market(market_size_params, 10, 1, |market| {
+        let mut place = |trader: &Pubkey, side: Side, num_base_lots: u64| {
+            market
+                .place_order(
+                    trader,
+                    OrderPacket::new_post_only_default(
+                        side,
+                        if side == Side::Bid { 100 } else { 101 },
+                        num_base_lots,
+                    ),
+                    &mut |_| {},
+                    &mut || (0, 0),
+                )
+                .unwrap()
+                .0
+                .unwrap()
+        };
+        let bid = place(&trader, Side::Bid, 5);
+        let other_bid = place(&other, Side::Bid, 5);
+        let ask = place(&trader, Side::Ask, 5);
+        let trader_state = *market.get_trader_state(&trader).unwrap();
+        assert_eq!(trader_state.quote_lots_locked, QuoteLots::new(5000));
+        assert_eq!(trader_state.base_lots_locked, BaseLots::new(5));
+
+        // Part of the added size is paid for with free funds
+        market
+            .get_trader_state_mut(&trader)
+            .unwrap()
+            .quote_lots_free = QuoteLots::new(1000);
+        let mut events = vec![];
+        let (new_bid, matching_engine_response) = market
+            .increase_order(
+                &trader,
+                &bid,
+                Side::Bid,
+                BaseLots::new(3),
+                7,
+                false,
+                &mut |e| events.push(e),
+                &mut || (0, 0),
+            )
+            .unwrap();
+        // The order moves to the back of the queue at the same price
+        assert_eq!(new_bid.price_in_ticks, bid.price_in_ticks);
+        assert!(new_bid > other_bid);
+        assert_eq!(
+            market
+                .get_book(Side::Bid)
+                .iter()
+                .map(|(order_id, order)| (*order_id, order.num_base_lots.as_u64()))
+                .collect::<Vec<_>>(),
+            vec![(other_bid, 5), (new_bid, 8)]
+        );
+        assert!(matches!(
+            events.as_slice(),
+            [
+                MarketEvent::Reduce {
+                    order_sequence_number,
+                    base_lots_removed,
+                    base_lots_remaining,
+                    ..
+                },
+                MarketEvent::Place {
+                    order_sequence_number: new_order_sequence_number,
+                    base_lots_placed,
+                    client_order_id: 7,
+                    ..
+                },
+            ] if *order_sequence_number == bid.order_sequence_number
+                && base_lots_removed.as_u64() == 5
+                && *base_lots_remaining == BaseLots::ZERO
+                && *new_order_sequence_number == new_bid.order_sequence_number
+                && base_lots_placed.as_u64() == 8
+        ));
+        // Only the added size is locked
+        assert_eq!(
+            matching_engine_response.num_quote_lots_posted,
+            QuoteLots::new(3000)
+        );
+        assert_eq!(
+            matching_engine_response.num_free_quote_lots_used,
+            QuoteLots::new(1000)
+        );
+        assert_eq!(
+            matching_engine_response.get_deposit_amount_bid_in_quote_lots(),
+            QuoteLots::new(2000)
+        );
+        let trader_state = *market.get_trader_state(&trader).unwrap();
+        assert_eq!(trader_state.quote_lots_locked, QuoteLots::new(8000));
+        assert_eq!(trader_state.quote_lots_free, QuoteLots::ZERO);
+
+        let (_, matching_engine_response) = market
+            .increase_order(
+                &trader,
+                &ask,
+                Side::Ask,
+                BaseLots::new(2),
+                0,
+                false,
+                &mut |_| {},
+                &mut || (0, 0),
+            )
+            .unwrap();
+        assert_eq!(
+            matching_engine_response.num_base_lots_posted,
+            BaseLots::new(2)
+        );
+        assert_eq!(
+            market.get_trader_state(&trader).unwrap().base_lots_locked,
+            BaseLots::new(7)
+        );
+
+        // Orders of other traders, unknown orders and orders that need a deposit when only
+        // deposited funds may be used cannot be increased
+        for (trader_id, order_id, use_only_deposited_funds) in [
+            (&trader, &other_bid, false),
+            (&trader, &bid, false),
+            (&trader, &new_bid, true),
+        ] {
+            assert!(market
+                .increase_order(
+                    trader_id,
+                    order_id,
+                    Side::Bid,
+                    BaseLots::new(1),
+                    0,
+                    use_only_deposited_funds,
+                    &mut |_| {},
+                    &mut || (0, 0),
+                )
+                .is_none());
+        }
+    });
 }
@@ -57,6 +57,14 @@ pub enum PhoenixError {
     FailedToSerializeEvent = 24,
     #[error("Failed to flush buffer")]
     FailedToFlushBuffer = 25,
+    #[error("Increase order error")]
+    IncreaseOrderError = 26,
+    #[error("Governance action must be proposed")]
+    GovernanceTimelocked = 27,
+    #[error("Governance proposal is not executable yet")]
+    ProposalNotExecutable = 28,
+    #[error("Invalid book full policy")]
+    InvalidBookFullPolicy = 29,
 }
 
 impl From<PhoenixError> for ProgramError {
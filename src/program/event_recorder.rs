@@ -35,7 +35,7 @@ const LOG_IX_ACCOUNT_META_SIZE: usize = 34;
 /// 32              market pubkey                Pubkey
 /// 32              signer pubkey                Pubkey
 /// 2               number of events in batch    u16
-const HEADER_LEN: usize = 93;
+pub(crate) const HEADER_LEN: usize = 93;
 
 /// The largest event is a fill event
 /// It contains the following metadata:
@@ -49,7 +49,11 @@ const HEADER_LEN: usize = 93;
 /// 8               price_in_ticks               u64,
 /// 8               base_lots_filled             u64,
 /// 8               base_lots_remaining          u64,
-const MAX_EVENT_SIZE: usize = 67;
+/// 32              taker_id                     Pubkey,
+/// 1               taker_side                   Side,
+/// 8               quote_lots_filled            u64,
+/// 8               fee_in_quote_lots            u64,
+const MAX_EVENT_SIZE: usize = 116;
 
 /// This struct manages in internal state of market events. It is used to
 /// track the current state of the event buffer and to serialize the
@@ -187,9 +191,9 @@ impl<'info> EventRecorder<'info> {
         }
         if market_info.data_is_empty() {
             assert_with_msg(
-                self.phoenix_instruction == PhoenixInstruction::ChangeMarketStatus,
+                self.phoenix_instruction == PhoenixInstruction::CloseMarket,
                 ProgramError::InvalidInstructionData,
-                "The only instruction that can be used to delete a market is ChangeMarketStatus",
+                "The only instruction that can be used to delete a market is CloseMarket",
             )?;
         } else {
             market_info.get_header_mut()?.increment_sequence_number();
@@ -0,0 +1,224 @@
+//! Decodes the market events that are recorded in the inner instructions of a transaction.
+//!
+//! Every instruction that touches a market records its events by CPI-ing into the `Log`
+//! instruction. The data of each `Log` instruction is laid out as follows:
+//!
+//! size (bytes)    description
+//! -----------------------------------------------------
+//! 1               `PhoenixInstruction::Log` tag
+//! 92              `PhoenixMarketEvent::Header`, where `total_events` is the number of events in the batch
+//! variable        `total_events` Borsh serialized `PhoenixMarketEvent`s
+//!
+//! If the events of an instruction do not fit in a single CPI, they are split across several
+//! batches with identical headers. The events are indexed from zero across all batches of the
+//! instruction, and every batch of an instruction shares the market sequence number of the header.
+
+use borsh::BorshDeserialize;
+
+use crate::program::{event_recorder::HEADER_LEN, PhoenixInstruction};
+
+use super::{AuditLogHeader, PhoenixMarketEvent};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventDecodeError {
+    /// The data does not start with the `Log` instruction tag
+    NotLogInstruction,
+    /// The data does not contain a valid audit log header
+    InvalidHeader,
+    /// An event could not be deserialized, or there is data left after the last event
+    InvalidEvent,
+    /// Batches with the same market and sequence number have different headers
+    MismatchedHeaders,
+    /// The event indices of an instruction are not contiguous from zero
+    InvalidEventIndex,
+}
+
+/// The events recorded by a single instruction on a market. The `total_events` of the header is the
+/// number of events across all batches of the instruction.
+#[derive(Debug, Clone)]
+pub struct DecodedMarketEvents {
+    pub header: AuditLogHeader,
+    pub events: Vec<PhoenixMarketEvent>,
+}
+
+/// Decodes the header and events of a single `Log` instruction
+pub fn decode_log_instruction(
+    data: &[u8],
+) -> Result<(AuditLogHeader, Vec<PhoenixMarketEvent>), EventDecodeError> {
+    let (tag, mut data) = data
+        .split_first()
+        .ok_or(EventDecodeError::NotLogInstruction)?;
+    if *tag != PhoenixInstruction::Log as u8 {
+        return Err(EventDecodeError::NotLogInstruction);
+    }
+    let header = match data
+        .get(..HEADER_LEN - 1)
+        .map(PhoenixMarketEvent::try_from_slice)
+    {
+        Some(Ok(PhoenixMarketEvent::Header(header))) => header,
+        _ => return Err(EventDecodeError::InvalidHeader),
+    };
+    data = &data[HEADER_LEN - 1..];
+    let mut events = Vec::with_capacity(header.total_events as usize);
+    for _ in 0..header.total_events {
+        match PhoenixMarketEvent::deserialize(&mut data) {
+            Ok(PhoenixMarketEvent::Uninitialized | PhoenixMarketEvent::Header(_)) | Err(_) => {
+                return Err(EventDecodeError::InvalidEvent)
+            }
+            Ok(event) => events.push(event),
+        }
+    }
+    if !data.is_empty() {
+        return Err(EventDecodeError::InvalidEvent);
+    }
+    Ok((header, events))
+}
+
+/// Decodes the inner instruction data of the Phoenix program in a transaction, in the order the
+/// inner instructions were executed. Instructions other than `Log` are ignored.
+///
+/// The batches of each instruction are reassembled into a single list of events ordered by index.
+/// The result contains one entry per instruction, in the order in which they were first logged.
+pub fn decode_log_instructions<'a>(
+    inner_instructions: impl IntoIterator<Item = &'a [u8]>,
+) -> Result<Vec<DecodedMarketEvents>, EventDecodeError> {
+    let mut decoded: Vec<DecodedMarketEvents> = vec![];
+    for data in inner_instructions {
+        if data.first() != Some(&(PhoenixInstruction::Log as u8)) {
+            continue;
+        }
+        let (header, events) = decode_log_instruction(data)?;
+        match decoded.iter_mut().find(|d| {
+            d.header.market == header.market && d.header.sequence_number == header.sequence_number
+        }) {
+            Some(instruction_events) => {
+                if !is_same_instruction(&instruction_events.header, &header) {
+                    return Err(EventDecodeError::MismatchedHeaders);
+                }
+                instruction_events.events.extend(events);
+            }
+            None => decoded.push(DecodedMarketEvents { header, events }),
+        }
+    }
+    for DecodedMarketEvents { header, events } in decoded.iter_mut() {
+        events.sort_by_key(PhoenixMarketEvent::get_index);
+        if events
+            .iter()
+            .enumerate()
+            .any(|(i, event)| event.get_index() != Some(i as u16))
+        {
+            return Err(EventDecodeError::InvalidEventIndex);
+        }
+        header.total_events =
+            u16::try_from(events.len()).map_err(|_| EventDecodeError::InvalidEventIndex)?;
+    }
+    Ok(decoded)
+}
+
+fn is_same_instruction(a: &AuditLogHeader, b: &AuditLogHeader) -> bool {
+    a.instruction == b.instruction
+        && a.timestamp == b.timestamp
+        && a.slot == b.slot
+        && a.signer == b.signer
+}
+
+#[test]
+fn test_decode_log_instructions() {
+    use super::{FeeEvent, SeatRequestEvent};
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+
+    let header = AuditLogHeader {
+        instruction: PhoenixInstruction::CollectFees as u8,
+        sequence_number: 7,
+        timestamp: 1_700_000_000,
+        slot: 100,
+        market: Pubkey::new_unique(),
+        signer: Pubkey::new_unique(),
+        total_events: 0,
+    };
+    let encode = |header: AuditLogHeader, events: &[PhoenixMarketEvent]| {
+        let mut data = vec![PhoenixInstruction::Log as u8];
+        PhoenixMarketEvent::Header(AuditLogHeader {
+            total_events: events.len() as u16,
+            ..header
+        })
+        .serialize(&mut data)
+        .unwrap();
+        for event in events {
+            event.serialize(&mut data).unwrap();
+        }
+        data
+    };
+    let events = (0..5)
+        .map(|index| {
+            PhoenixMarketEvent::Fee(FeeEvent {
+                index,
+                fees_collected_in_quote_lots: index as u64,
+            })
+        })
+        .collect::<Vec<_>>();
+    let first_batch = encode(header, &events[..3]);
+    let second_batch = encode(header, &events[3..]);
+    assert_eq!(first_batch.len(), HEADER_LEN + 3 * 11);
+    let other_header = AuditLogHeader {
+        sequence_number: 8,
+        ..header
+    };
+    let header_only = encode(other_header, &[]);
+    let not_log = [PhoenixInstruction::CollectFees as u8, 1, 2, 3];
+
+    let decoded = decode_log_instructions([
+        second_batch.as_slice(),
+        &not_log,
+        &first_batch,
+        &header_only,
+    ])
+    .unwrap();
+    assert_eq!(decoded.len(), 2);
+    assert_eq!(decoded[0].header.sequence_number, 7);
+    assert_eq!(decoded[0].header.total_events, 5);
+    assert_eq!(
+        decoded[0]
+            .events
+            .iter()
+            .map(|e| e.get_index().unwrap())
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4]
+    );
+    assert_eq!(decoded[1].header.sequence_number, 8);
+    assert!(decoded[1].events.is_empty());
+
+    // Missing batches and inconsistent headers are rejected
+    assert_eq!(
+        decode_log_instructions([second_batch.as_slice()]).unwrap_err(),
+        EventDecodeError::InvalidEventIndex
+    );
+    let other_signer = encode(
+        AuditLogHeader {
+            signer: Pubkey::new_unique(),
+            ..header
+        },
+        &[PhoenixMarketEvent::SeatRequest(SeatRequestEvent {
+            index: 3,
+            trader: Pubkey::new_unique(),
+        })],
+    );
+    assert_eq!(
+        decode_log_instructions([first_batch.as_slice(), &other_signer]).unwrap_err(),
+        EventDecodeError::MismatchedHeaders
+    );
+    // Truncated data is rejected
+    assert_eq!(
+        decode_log_instruction(&first_batch[..first_batch.len() - 1]).unwrap_err(),
+        EventDecodeError::InvalidEvent
+    );
+    assert_eq!(
+        decode_log_instruction(&first_batch[..HEADER_LEN - 1]).unwrap_err(),
+        EventDecodeError::InvalidHeader
+    );
+    assert_eq!(
+        decode_log_instruction(&not_log).unwrap_err(),
+        EventDecodeError::NotLogInstruction
+    );
+}
@@ -0,0 +1,524 @@
+use crate::{
+    program::status::{MarketStatus, SeatApprovalStatus},
+    state::{markets::MarketEvent, SelfTradeBehavior, Side},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::pubkey::Pubkey;
+
+pub mod decode;
+pub mod replay;
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct AuditLogHeader {
+    pub instruction: u8,
+    pub sequence_number: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+    pub market: Pubkey,
+    pub signer: Pubkey,
+    pub total_events: u16,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct FillEvent {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_filled: u64,
+    pub base_lots_remaining: u64,
+}
+
+/// Version 2 of the fill event. It also describes the taker side of the fill and the fee charged to
+/// the taker for this fill. `FillEvent` is no longer emitted, but remains decodable.
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct FillEventV2 {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_filled: u64,
+    pub base_lots_remaining: u64,
+    pub taker_id: Pubkey,
+    pub taker_side: Side,
+    pub quote_lots_filled: u64,
+    pub fee_in_quote_lots: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct ReduceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_removed: u64,
+    pub base_lots_remaining: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct PlaceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub client_order_id: u128,
+    pub price_in_ticks: u64,
+    pub base_lots_placed: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct EvictEvent {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_evicted: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct FillSummaryEvent {
+    pub index: u16,
+    pub client_order_id: u128,
+    pub total_base_lots_filled: u64,
+    pub total_quote_lots_filled: u64,
+    pub total_fee_in_quote_lots: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct FeeEvent {
+    pub index: u16,
+    pub fees_collected_in_quote_lots: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct TimeInForceEvent {
+    pub index: u16,
+    pub order_sequence_number: u64,
+    pub last_valid_slot: u64,
+    pub last_valid_unix_timestamp_in_seconds: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct ExpiredOrderEvent {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub base_lots_removed: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SelfTradeEvent {
+    pub index: u16,
+    pub maker_id: Pubkey,
+    pub order_sequence_number: u64,
+    pub price_in_ticks: u64,
+    pub self_trade_behavior: SelfTradeBehavior,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SeatAllowlistRootUpdateEvent {
+    pub index: u16,
+    pub previous_root: [u8; 32],
+    pub new_root: [u8; 32],
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SuccessorNominationEvent {
+    pub index: u16,
+    /// The default pubkey if no successor was named
+    pub previous_successor: Pubkey,
+    pub successor: Pubkey,
+    /// The nomination does not expire if this is zero
+    pub expiry_unix_timestamp: i64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SuccessorCancellationEvent {
+    pub index: u16,
+    pub successor: Pubkey,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct AuthorityClaimEvent {
+    pub index: u16,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct MarketStatusChangeEvent {
+    pub index: u16,
+    pub previous_status: MarketStatus,
+    pub new_status: MarketStatus,
+}
+
+/// Also recorded for the funds that an order or swap takes from the trader's token accounts.
+/// The free balances of the trader are the balances after the deposit, and are zero for traders
+/// without a seat on the market.
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct DepositEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+    pub base_lots_deposited: u64,
+    pub quote_lots_deposited: u64,
+    pub base_lots_free: u64,
+    pub quote_lots_free: u64,
+}
+
+/// Also recorded for the funds that an order or swap sends to the trader's token accounts.
+/// The free balances of the trader are the balances after the withdrawal. They are zero if the
+/// trader was removed from the market. When funds are withdrawn from the seat of an evicted
+/// trader, they are the evicted balances that remain on the seat.
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct WithdrawEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+    pub base_lots_withdrawn: u64,
+    pub quote_lots_withdrawn: u64,
+    pub base_lots_free: u64,
+    pub quote_lots_free: u64,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct FeeRecipientChangeEvent {
+    pub index: u16,
+    pub previous_fee_recipient: Pubkey,
+    pub new_fee_recipient: Pubkey,
+}
+
+/// A seat request is followed by a seat status change event if the seat is approved right away
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SeatRequestEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SeatStatusChangeEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+    pub previous_status: SeatApprovalStatus,
+    pub new_status: SeatApprovalStatus,
+}
+
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SeatClosureEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+}
+
+/// The free funds of an evicted trader are moved to its seat, from which they can be withdrawn
+#[derive(Debug, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub struct TraderEvictionEvent {
+    pub index: u16,
+    pub trader: Pubkey,
+    pub base_lots_evicted: u64,
+    pub quote_lots_evicted: u64,
+}
+
+#[derive(Debug, Default, Copy, Clone, BorshDeserialize, BorshSerialize)]
+pub enum PhoenixMarketEvent {
+    #[default]
+    Uninitialized,
+    Header(AuditLogHeader),
+    Fill(FillEvent),
+    Place(PlaceEvent),
+    Reduce(ReduceEvent),
+    Evict(EvictEvent),
+    FillSummary(FillSummaryEvent),
+    Fee(FeeEvent),
+    TimeInForce(TimeInForceEvent),
+    ExpiredOrder(ExpiredOrderEvent),
+    SelfTrade(SelfTradeEvent),
+    SeatAllowlistRootUpdate(SeatAllowlistRootUpdateEvent),
+    SuccessorNomination(SuccessorNominationEvent),
+    SuccessorCancellation(SuccessorCancellationEvent),
+    AuthorityClaim(AuthorityClaimEvent),
+    MarketStatusChange(MarketStatusChangeEvent),
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    FeeRecipientChange(FeeRecipientChangeEvent),
+    SeatRequest(SeatRequestEvent),
+    SeatStatusChange(SeatStatusChangeEvent),
+    FillV2(FillEventV2),
+    SeatClosure(SeatClosureEvent),
+    TraderEviction(TraderEvictionEvent),
+}
+
+impl PhoenixMarketEvent {
+    pub fn set_index(&mut self, i: u16) {
+        match self {
+            Self::Fill(FillEvent { index, .. }) => *index = i,
+            Self::FillV2(FillEventV2 { index, .. }) => *index = i,
+            Self::Place(PlaceEvent { index, .. }) => *index = i,
+            Self::Reduce(ReduceEvent { index, .. }) => *index = i,
+            Self::FillSummary(FillSummaryEvent { index, .. }) => *index = i,
+            Self::Evict(EvictEvent { index, .. }) => *index = i,
+            Self::Fee(FeeEvent { index, .. }) => *index = i,
+            Self::TimeInForce(TimeInForceEvent { index, .. }) => *index = i,
+            Self::ExpiredOrder(ExpiredOrderEvent { index, .. }) => *index = i,
+            Self::SelfTrade(SelfTradeEvent { index, .. }) => *index = i,
+            Self::SeatAllowlistRootUpdate(SeatAllowlistRootUpdateEvent { index, .. }) => *index = i,
+            Self::SuccessorNomination(SuccessorNominationEvent { index, .. }) => *index = i,
+            Self::SuccessorCancellation(SuccessorCancellationEvent { index, .. }) => *index = i,
+            Self::AuthorityClaim(AuthorityClaimEvent { index, .. }) => *index = i,
+            Self::MarketStatusChange(MarketStatusChangeEvent { index, .. }) => *index = i,
+            Self::Deposit(DepositEvent { index, .. }) => *index = i,
+            Self::Withdraw(WithdrawEvent { index, .. }) => *index = i,
+            Self::FeeRecipientChange(FeeRecipientChangeEvent { index, .. }) => *index = i,
+            Self::SeatRequest(SeatRequestEvent { index, .. }) => *index = i,
+            Self::SeatStatusChange(SeatStatusChangeEvent { index, .. }) => *index = i,
+            Self::SeatClosure(SeatClosureEvent { index, .. }) => *index = i,
+            Self::TraderEviction(TraderEvictionEvent { index, .. }) => *index = i,
+            _ => panic!("Cannot set index on uninitialized or header event"),
+        }
+    }
+
+    /// Returns the index of the event within its instruction, or None for the header
+    pub fn get_index(&self) -> Option<u16> {
+        match self {
+            Self::Fill(FillEvent { index, .. }) => Some(*index),
+            Self::FillV2(FillEventV2 { index, .. }) => Some(*index),
+            Self::Place(PlaceEvent { index, .. }) => Some(*index),
+            Self::Reduce(ReduceEvent { index, .. }) => Some(*index),
+            Self::FillSummary(FillSummaryEvent { index, .. }) => Some(*index),
+            Self::Evict(EvictEvent { index, .. }) => Some(*index),
+            Self::Fee(FeeEvent { index, .. }) => Some(*index),
+            Self::TimeInForce(TimeInForceEvent { index, .. }) => Some(*index),
+            Self::ExpiredOrder(ExpiredOrderEvent { index, .. }) => Some(*index),
+            Self::SelfTrade(SelfTradeEvent { index, .. }) => Some(*index),
+            Self::SeatAllowlistRootUpdate(SeatAllowlistRootUpdateEvent { index, .. }) => {
+                Some(*index)
+            }
+            Self::SuccessorNomination(SuccessorNominationEvent { index, .. }) => Some(*index),
+            Self::SuccessorCancellation(SuccessorCancellationEvent { index, .. }) => Some(*index),
+            Self::AuthorityClaim(AuthorityClaimEvent { index, .. }) => Some(*index),
+            Self::MarketStatusChange(MarketStatusChangeEvent { index, .. }) => Some(*index),
+            Self::Deposit(DepositEvent { index, .. }) => Some(*index),
+            Self::Withdraw(WithdrawEvent { index, .. }) => Some(*index),
+            Self::FeeRecipientChange(FeeRecipientChangeEvent { index, .. }) => Some(*index),
+            Self::SeatRequest(SeatRequestEvent { index, .. }) => Some(*index),
+            Self::SeatStatusChange(SeatStatusChangeEvent { index, .. }) => Some(*index),
+            Self::SeatClosure(SeatClosureEvent { index, .. }) => Some(*index),
+            Self::TraderEviction(TraderEvictionEvent { index, .. }) => Some(*index),
+            Self::Uninitialized | Self::Header(_) => None,
+        }
+    }
+}
+
+impl From<MarketEvent<Pubkey>> for PhoenixMarketEvent {
+    fn from(e: MarketEvent<Pubkey>) -> Self {
+        match e {
+            MarketEvent::<Pubkey>::Fill {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_filled,
+                base_lots_remaining,
+                taker_id,
+                taker_side,
+                quote_lots_filled,
+                fee_in_quote_lots,
+            } => Self::FillV2(FillEventV2 {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks: price_in_ticks.into(),
+                base_lots_filled: base_lots_filled.into(),
+                base_lots_remaining: base_lots_remaining.into(),
+                taker_id,
+                taker_side,
+                quote_lots_filled: quote_lots_filled.into(),
+                fee_in_quote_lots: fee_in_quote_lots.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Place {
+                order_sequence_number,
+                client_order_id,
+                price_in_ticks,
+                base_lots_placed,
+            } => Self::Place(PlaceEvent {
+                order_sequence_number,
+                client_order_id,
+                price_in_ticks: price_in_ticks.into(),
+                base_lots_placed: base_lots_placed.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Reduce {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_removed,
+                base_lots_remaining,
+            } => Self::Reduce(ReduceEvent {
+                order_sequence_number,
+                price_in_ticks: price_in_ticks.into(),
+                base_lots_removed: base_lots_removed.into(),
+                base_lots_remaining: base_lots_remaining.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Evict {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_evicted,
+            } => Self::Evict(EvictEvent {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks: price_in_ticks.into(),
+                base_lots_evicted: base_lots_evicted.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::FillSummary {
+                client_order_id,
+                total_base_lots_filled,
+                total_quote_lots_filled,
+                total_fee_in_quote_lots,
+            } => Self::FillSummary(FillSummaryEvent {
+                client_order_id,
+                total_base_lots_filled: total_base_lots_filled.into(),
+                total_quote_lots_filled: total_quote_lots_filled.into(),
+                total_fee_in_quote_lots: total_fee_in_quote_lots.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Fee {
+                fees_collected_in_quote_lots,
+            } => Self::Fee(FeeEvent {
+                fees_collected_in_quote_lots: fees_collected_in_quote_lots.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::TimeInForce {
+                order_sequence_number,
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+            } => Self::TimeInForce(TimeInForceEvent {
+                order_sequence_number,
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::ExpiredOrder {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_removed,
+            } => Self::ExpiredOrder(ExpiredOrderEvent {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks: price_in_ticks.into(),
+                base_lots_removed: base_lots_removed.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SelfTrade {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks,
+                self_trade_behavior,
+            } => Self::SelfTrade(SelfTradeEvent {
+                maker_id,
+                order_sequence_number,
+                price_in_ticks: price_in_ticks.into(),
+                self_trade_behavior,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SeatAllowlistRootUpdate {
+                previous_root,
+                new_root,
+            } => Self::SeatAllowlistRootUpdate(SeatAllowlistRootUpdateEvent {
+                previous_root,
+                new_root,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SuccessorNomination {
+                previous_successor,
+                successor,
+                expiry_unix_timestamp,
+            } => Self::SuccessorNomination(SuccessorNominationEvent {
+                previous_successor,
+                successor,
+                expiry_unix_timestamp,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SuccessorCancellation { successor } => {
+                Self::SuccessorCancellation(SuccessorCancellationEvent {
+                    successor,
+                    index: 0,
+                })
+            }
+            MarketEvent::<Pubkey>::AuthorityClaim {
+                previous_authority,
+                new_authority,
+            } => Self::AuthorityClaim(AuthorityClaimEvent {
+                previous_authority,
+                new_authority,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::MarketStatusChange {
+                previous_status,
+                new_status,
+            } => Self::MarketStatusChange(MarketStatusChangeEvent {
+                previous_status,
+                new_status,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Deposit {
+                trader,
+                base_lots_deposited,
+                quote_lots_deposited,
+                base_lots_free,
+                quote_lots_free,
+            } => Self::Deposit(DepositEvent {
+                trader,
+                base_lots_deposited: base_lots_deposited.into(),
+                quote_lots_deposited: quote_lots_deposited.into(),
+                base_lots_free: base_lots_free.into(),
+                quote_lots_free: quote_lots_free.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::Withdraw {
+                trader,
+                base_lots_withdrawn,
+                quote_lots_withdrawn,
+                base_lots_free,
+                quote_lots_free,
+            } => Self::Withdraw(WithdrawEvent {
+                trader,
+                base_lots_withdrawn: base_lots_withdrawn.into(),
+                quote_lots_withdrawn: quote_lots_withdrawn.into(),
+                base_lots_free: base_lots_free.into(),
+                quote_lots_free: quote_lots_free.into(),
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::FeeRecipientChange {
+                previous_fee_recipient,
+                new_fee_recipient,
+            } => Self::FeeRecipientChange(FeeRecipientChangeEvent {
+                previous_fee_recipient,
+                new_fee_recipient,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SeatRequest { trader } => {
+                Self::SeatRequest(SeatRequestEvent { trader, index: 0 })
+            }
+            MarketEvent::<Pubkey>::SeatStatusChange {
+                trader,
+                previous_status,
+                new_status,
+            } => Self::SeatStatusChange(SeatStatusChangeEvent {
+                trader,
+                previous_status,
+                new_status,
+                index: 0,
+            }),
+            MarketEvent::<Pubkey>::SeatClosure { trader } => {
+                Self::SeatClosure(SeatClosureEvent { trader, index: 0 })
+            }
+            MarketEvent::<Pubkey>::TraderEviction {
+                trader,
+                base_lots_evicted,
+                quote_lots_evicted,
+            } => Self::TraderEviction(TraderEvictionEvent {
+                trader,
+                base_lots_evicted: base_lots_evicted.into(),
+                quote_lots_evicted: quote_lots_evicted.into(),
+                index: 0,
+            }),
+        }
+    }
+}
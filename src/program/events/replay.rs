@@ -0,0 +1,627 @@
+//! Rebuilds the order book, locked trader balances and uncollected fees of a market from its event
+//! history. Free trader balances are not replayed.
+//!
+//! The replay starts from a snapshot of the market and applies the events of each instruction in
+//! market sequence number order. Locked balances are updated with the same rounding as the matching
+//! engine, so the replayed state can be checked exactly against a later snapshot of the market.
+//!
+//! Free balances are out of scope, and are neither tracked nor verified. Whether a taker settles
+//! against its token accounts or its free funds, and how much of a new order is funded from free
+//! funds, is not recorded in the event log, and traders can be registered or removed without an
+//! event. Deposit and Withdraw events carry the free balances of the trader after the transfer,
+//! which can be used as checkpoints instead.
+
+use std::collections::BTreeMap;
+
+use solana_program::pubkey::Pubkey;
+
+use crate::{
+    quantities::{
+        BaseLots, BaseLotsPerBaseUnit, QuoteLots, QuoteLotsPerBaseUnitPerTick, WrapperU64,
+    },
+    state::{
+        markets::{FIFOOrderId, FIFORestingOrder, Market},
+        OrderPacket, Side,
+    },
+};
+
+use super::{
+    decode::DecodedMarketEvents, EvictEvent, ExpiredOrderEvent, FeeEvent, FillEvent, FillEventV2,
+    FillSummaryEvent, PhoenixMarketEvent, PlaceEvent, ReduceEvent, TimeInForceEvent,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The events were recorded for a different market
+    MarketMismatch { expected: Pubkey, found: Pubkey },
+    /// The events do not follow the last applied instruction
+    SequenceGap { expected: u64, found: u64 },
+    /// An event refers to an order that is not on the replayed book
+    UnknownOrder(FIFOOrderId),
+    /// An event removes more base lots than the order has
+    InvalidOrderSize(FIFOOrderId),
+    /// The replayed book does not match the snapshot at this order
+    OrderMismatch(FIFOOrderId),
+    /// The locked balances of the trader do not match the snapshot
+    TraderMismatch(Pubkey),
+    /// The uncollected fees do not match the snapshot
+    FeeMismatch {
+        expected: QuoteLots,
+        found: QuoteLots,
+    },
+}
+
+/// A resting order rebuilt from the event log
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayedOrder {
+    pub trader: Pubkey,
+    pub num_base_lots: BaseLots,
+    pub last_valid_slot: u64,
+    pub last_valid_unix_timestamp_in_seconds: u64,
+}
+
+/// The balances of a trader that are locked in resting orders
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ReplayedTraderState {
+    pub base_lots_locked: BaseLots,
+    pub quote_lots_locked: QuoteLots,
+}
+
+pub struct MarketReplay {
+    pub market: Pubkey,
+    /// The market sequence number of the next instruction to apply
+    pub next_sequence_number: u64,
+    pub tick_size_in_quote_lots_per_base_unit: QuoteLotsPerBaseUnitPerTick,
+    pub base_lots_per_base_unit: BaseLotsPerBaseUnit,
+    /// Bids, from the best price to the worst
+    pub bids: BTreeMap<FIFOOrderId, ReplayedOrder>,
+    /// Asks, from the best price to the worst
+    pub asks: BTreeMap<FIFOOrderId, ReplayedOrder>,
+    pub traders: BTreeMap<Pubkey, ReplayedTraderState>,
+    pub uncollected_fees: QuoteLots,
+    /// The ids of the orders on both sides of the book, keyed by order sequence number, for the
+    /// events that only refer to an order by its sequence number
+    order_ids: BTreeMap<u64, FIFOOrderId>,
+}
+
+impl MarketReplay {
+    /// Starts a replay from a snapshot of the market. `market_sequence_number` is the sequence
+    /// number in the market header of the snapshot, which is the sequence number of the next
+    /// instruction on the market.
+    pub fn new(
+        market_key: Pubkey,
+        market_sequence_number: u64,
+        market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    ) -> Self {
+        let load_book = |side: Side| {
+            market
+                .get_book(side)
+                .iter()
+                .map(|(order_id, order)| {
+                    (
+                        *order_id,
+                        ReplayedOrder {
+                            trader: market.get_trader_id_from_index(order.trader_index as u32),
+                            num_base_lots: order.num_base_lots,
+                            last_valid_slot: order.last_valid_slot,
+                            last_valid_unix_timestamp_in_seconds: order
+                                .last_valid_unix_timestamp_in_seconds,
+                        },
+                    )
+                })
+                .collect()
+        };
+        let traders = market
+            .get_registered_traders()
+            .iter()
+            .map(|(trader, state)| {
+                (
+                    *trader,
+                    ReplayedTraderState {
+                        base_lots_locked: state.base_lots_locked,
+                        quote_lots_locked: state.quote_lots_locked,
+                    },
+                )
+            })
+            .collect();
+        let bids: BTreeMap<FIFOOrderId, ReplayedOrder> = load_book(Side::Bid);
+        let asks: BTreeMap<FIFOOrderId, ReplayedOrder> = load_book(Side::Ask);
+        let order_ids = bids
+            .keys()
+            .chain(asks.keys())
+            .map(|order_id| (order_id.order_sequence_number, *order_id))
+            .collect();
+        Self {
+            market: market_key,
+            next_sequence_number: market_sequence_number,
+            tick_size_in_quote_lots_per_base_unit: market.get_tick_size(),
+            base_lots_per_base_unit: market.get_base_lots_per_base_unit(),
+            bids,
+            asks,
+            traders,
+            uncollected_fees: market.get_uncollected_fee_amount(),
+            order_ids,
+        }
+    }
+
+    /// Returns the orders of one side of the book, from the best price to the worst
+    pub fn get_book(&self, side: Side) -> &BTreeMap<FIFOOrderId, ReplayedOrder> {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn get_book_mut(&mut self, side: Side) -> &mut BTreeMap<FIFOOrderId, ReplayedOrder> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    /// Applies the events of the next instruction on the market. The state is left unchanged if
+    /// the instruction is not the next one in sequence.
+    pub fn apply(&mut self, instruction_events: &DecodedMarketEvents) -> Result<(), ReplayError> {
+        let DecodedMarketEvents { header, events } = instruction_events;
+        if header.market != self.market {
+            return Err(ReplayError::MarketMismatch {
+                expected: self.market,
+                found: header.market,
+            });
+        }
+        if header.sequence_number != self.next_sequence_number {
+            return Err(ReplayError::SequenceGap {
+                expected: self.next_sequence_number,
+                found: header.sequence_number,
+            });
+        }
+        for event in events {
+            self.apply_event(&header.signer, event)?;
+        }
+        self.next_sequence_number += 1;
+        Ok(())
+    }
+
+    fn apply_event(
+        &mut self,
+        signer: &Pubkey,
+        event: &PhoenixMarketEvent,
+    ) -> Result<(), ReplayError> {
+        match *event {
+            // Orders are always placed by the signer of the instruction
+            PhoenixMarketEvent::Place(PlaceEvent {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_placed,
+                ..
+            }) => {
+                let order_id = FIFOOrderId::new_from_untyped(price_in_ticks, order_sequence_number);
+                let num_base_lots = BaseLots::new(base_lots_placed);
+                self.get_book_mut(Side::from_order_sequence_number(order_sequence_number))
+                    .insert(
+                        order_id,
+                        ReplayedOrder {
+                            trader: *signer,
+                            num_base_lots,
+                            ..Default::default()
+                        },
+                    );
+                self.order_ids.insert(order_sequence_number, order_id);
+                let locked = self.get_locked_amounts(&order_id, num_base_lots);
+                let trader_state = self.traders.entry(*signer).or_default();
+                trader_state.base_lots_locked += locked.base_lots_locked;
+                trader_state.quote_lots_locked += locked.quote_lots_locked;
+            }
+            PhoenixMarketEvent::TimeInForce(TimeInForceEvent {
+                order_sequence_number,
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                ..
+            }) => {
+                let order_id = self.order_ids.get(&order_sequence_number).copied().ok_or(
+                    ReplayError::UnknownOrder(FIFOOrderId::new_from_untyped(
+                        0,
+                        order_sequence_number,
+                    )),
+                )?;
+                let order = self
+                    .get_book_mut(Side::from_order_sequence_number(order_sequence_number))
+                    .get_mut(&order_id)
+                    .ok_or(ReplayError::UnknownOrder(order_id))?;
+                order.last_valid_slot = last_valid_slot;
+                order.last_valid_unix_timestamp_in_seconds = last_valid_unix_timestamp_in_seconds;
+            }
+            PhoenixMarketEvent::Fill(FillEvent {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_filled,
+                ..
+            })
+            | PhoenixMarketEvent::FillV2(FillEventV2 {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_filled,
+                ..
+            })
+            | PhoenixMarketEvent::Reduce(ReduceEvent {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_removed: base_lots_filled,
+                ..
+            })
+            | PhoenixMarketEvent::Evict(EvictEvent {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_evicted: base_lots_filled,
+                ..
+            })
+            | PhoenixMarketEvent::ExpiredOrder(ExpiredOrderEvent {
+                order_sequence_number,
+                price_in_ticks,
+                base_lots_removed: base_lots_filled,
+                ..
+            }) => {
+                let order_id = FIFOOrderId::new_from_untyped(price_in_ticks, order_sequence_number);
+                self.remove_base_lots(&order_id, BaseLots::new(base_lots_filled))?;
+            }
+            PhoenixMarketEvent::FillSummary(FillSummaryEvent {
+                total_fee_in_quote_lots,
+                ..
+            }) => {
+                self.uncollected_fees += QuoteLots::new(total_fee_in_quote_lots);
+            }
+            PhoenixMarketEvent::Fee(FeeEvent {
+                fees_collected_in_quote_lots,
+                ..
+            }) => {
+                self.uncollected_fees -= QuoteLots::new(fees_collected_in_quote_lots);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Removes base lots from a resting order and unlocks the funds of the maker
+    fn remove_base_lots(
+        &mut self,
+        order_id: &FIFOOrderId,
+        base_lots_removed: BaseLots,
+    ) -> Result<(), ReplayError> {
+        let book = self.get_book_mut(Side::from_order_sequence_number(
+            order_id.order_sequence_number,
+        ));
+        let order = book
+            .get_mut(order_id)
+            .ok_or(ReplayError::UnknownOrder(*order_id))?;
+        if base_lots_removed > order.num_base_lots {
+            return Err(ReplayError::InvalidOrderSize(*order_id));
+        }
+        order.num_base_lots -= base_lots_removed;
+        let trader = order.trader;
+        if order.num_base_lots == BaseLots::ZERO {
+            book.remove(order_id);
+            self.order_ids.remove(&order_id.order_sequence_number);
+        }
+        let unlocked = self.get_locked_amounts(order_id, base_lots_removed);
+        let trader_state = self.traders.entry(trader).or_default();
+        trader_state.base_lots_locked -= unlocked.base_lots_locked;
+        trader_state.quote_lots_locked -= unlocked.quote_lots_locked;
+        Ok(())
+    }
+
+    /// The funds that the matching engine locks for the given size of an order
+    fn get_locked_amounts(
+        &self,
+        order_id: &FIFOOrderId,
+        num_base_lots: BaseLots,
+    ) -> ReplayedTraderState {
+        match Side::from_order_sequence_number(order_id.order_sequence_number) {
+            Side::Bid => ReplayedTraderState {
+                base_lots_locked: BaseLots::ZERO,
+                quote_lots_locked: (self.tick_size_in_quote_lots_per_base_unit
+                    * order_id.price_in_ticks
+                    * num_base_lots)
+                    / self.base_lots_per_base_unit,
+            },
+            Side::Ask => ReplayedTraderState {
+                base_lots_locked: num_base_lots,
+                quote_lots_locked: QuoteLots::ZERO,
+            },
+        }
+    }
+
+    /// Checks the replayed book, locked balances and uncollected fees against a snapshot of the
+    /// market taken after the last applied instruction
+    pub fn verify(
+        &self,
+        market: &dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    ) -> Result<(), ReplayError> {
+        let snapshot = Self::new(self.market, self.next_sequence_number, market);
+        for side in [Side::Bid, Side::Ask] {
+            let (book, snapshot_book) = (self.get_book(side), snapshot.get_book(side));
+            for (order_id, order) in book.iter() {
+                if snapshot_book.get(order_id) != Some(order) {
+                    return Err(ReplayError::OrderMismatch(*order_id));
+                }
+            }
+            if let Some(order_id) = snapshot_book
+                .keys()
+                .find(|order_id| !book.contains_key(order_id))
+            {
+                return Err(ReplayError::OrderMismatch(*order_id));
+            }
+        }
+        // Traders without locked funds may have been registered or removed without an event
+        for trader in self.traders.keys().chain(snapshot.traders.keys()) {
+            let replayed = self.traders.get(trader).copied().unwrap_or_default();
+            let expected = snapshot.traders.get(trader).copied().unwrap_or_default();
+            if replayed != expected {
+                return Err(ReplayError::TraderMismatch(*trader));
+            }
+        }
+        if self.uncollected_fees != snapshot.uncollected_fees {
+            return Err(ReplayError::FeeMismatch {
+                expected: snapshot.uncollected_fees,
+                found: self.uncollected_fees,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+type MarketInstructionFn<'a> =
+    dyn FnMut(&mut dyn FnMut(crate::state::markets::MarketEvent<Pubkey>)) + 'a;
+
+/// Runs an instruction against a market and returns its events as they would be decoded from the
+/// event log
+#[cfg(test)]
+fn record_instruction(
+    market_key: Pubkey,
+    sequence_number: u64,
+    signer: Pubkey,
+    instruction: &mut MarketInstructionFn,
+) -> DecodedMarketEvents {
+    let mut events = vec![];
+    instruction(&mut |e| {
+        let mut event = PhoenixMarketEvent::from(e);
+        event.set_index(events.len() as u16);
+        events.push(event);
+    });
+    let header = crate::program::AuditLogHeader {
+        instruction: 0,
+        sequence_number,
+        timestamp: 0,
+        slot: 0,
+        market: market_key,
+        signer,
+        total_events: events.len() as u16,
+    };
+    DecodedMarketEvents { header, events }
+}
+
+#[test]
+fn test_market_replay() {
+    use crate::program::{get_market_size, load_with_dispatch_init, MarketSizeParams};
+    use crate::state::SelfTradeBehavior;
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 8,
+        asks_size: 8,
+        num_seats: 4,
+    };
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    let mut market = load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner;
+    market.initialize_with_params(
+        QuoteLotsPerBaseUnitPerTick::new(30),
+        BaseLotsPerBaseUnit::new(10),
+    );
+    market.set_fee(5);
+    let market_key = Pubkey::new_unique();
+    let maker = Pubkey::new_unique();
+    let taker = Pubkey::new_unique();
+    // The snapshot already has a resting order
+    market
+        .place_order(
+            &maker,
+            OrderPacket::new_post_only_default(Side::Ask, 120, 9),
+            &mut |_| {},
+            &mut || (0, 0),
+        )
+        .unwrap();
+    let mut replay = MarketReplay::new(market_key, 10, market.as_ref());
+
+    let mut sequence_number = 10;
+    let mut run_instruction = |signer: Pubkey, instruction: &mut MarketInstructionFn| {
+        sequence_number += 1;
+        record_instruction(market_key, sequence_number - 1, signer, instruction)
+    };
+
+    let mut instructions = vec![];
+    instructions.push(run_instruction(maker, &mut |record| {
+        for (price_in_ticks, num_base_lots) in [(100, 5), (98, 13)] {
+            let mut order_packet =
+                OrderPacket::new_post_only_default(Side::Bid, price_in_ticks, num_base_lots);
+            if let OrderPacket::PostOnly {
+                last_valid_slot, ..
+            } = &mut order_packet
+            {
+                *last_valid_slot = Some(1000);
+            }
+            market
+                .place_order(&maker, order_packet, record, &mut || (0, 0))
+                .unwrap();
+        }
+    }));
+    instructions.push(run_instruction(taker, &mut |record| {
+        market
+            .place_order(
+                &taker,
+                OrderPacket::new_limit_order(
+                    Side::Ask,
+                    98,
+                    8,
+                    SelfTradeBehavior::Abort,
+                    None,
+                    0,
+                    false,
+                ),
+                record,
+                &mut || (0, 0),
+            )
+            .unwrap();
+    }));
+    instructions.push(run_instruction(maker, &mut |record| {
+        let (order_id, _) = market
+            .get_book(Side::Bid)
+            .iter()
+            .next()
+            .map(|(order_id, order)| (*order_id, *order))
+            .unwrap();
+        market
+            .increase_order(
+                &maker,
+                &order_id,
+                Side::Bid,
+                BaseLots::new(4),
+                0,
+                false,
+                record,
+                &mut || (0, 0),
+            )
+            .unwrap();
+        let (order_id, _) = market.get_book(Side::Ask).iter().next().unwrap();
+        let order_id = *order_id;
+        market
+            .reduce_order(
+                &maker,
+                &order_id,
+                Side::Ask,
+                Some(BaseLots::new(4)),
+                false,
+                record,
+            )
+            .unwrap();
+    }));
+    instructions.push(run_instruction(maker, &mut |record| {
+        market.collect_fees(record);
+    }));
+
+    // Instructions must be applied in sequence
+    assert_eq!(
+        replay.apply(&instructions[1]).unwrap_err(),
+        ReplayError::SequenceGap {
+            expected: 10,
+            found: 11
+        }
+    );
+    for instruction in instructions.iter() {
+        replay.apply(instruction).unwrap();
+    }
+    assert_eq!(replay.next_sequence_number, 14);
+    assert!(replay
+        .get_book(Side::Bid)
+        .values()
+        .all(|order| order.last_valid_slot == 1000));
+    assert_eq!(replay.get_book(Side::Ask).len(), 1);
+    replay.verify(market.as_ref()).unwrap();
+
+    // Missing an instruction is detected when verifying against the snapshot
+    let mut partial_replay = MarketReplay::new(market_key, 10, market.as_ref());
+    partial_replay.bids.clear();
+    assert!(matches!(
+        partial_replay.verify(market.as_ref()).unwrap_err(),
+        ReplayError::OrderMismatch(_)
+    ));
+}
+
+#[test]
+fn test_market_replay_with_deep_book() {
+    use crate::program::{get_market_size, load_with_dispatch_init, MarketSizeParams};
+    use crate::state::SelfTradeBehavior;
+
+    let market_size_params = MarketSizeParams {
+        bids_size: 32,
+        asks_size: 32,
+        num_seats: 4,
+    };
+    let mut data = vec![0_u64; get_market_size(&market_size_params).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut data);
+    let mut market = load_with_dispatch_init(&market_size_params, bytes)
+        .unwrap()
+        .inner;
+    market.initialize_with_params(
+        QuoteLotsPerBaseUnitPerTick::new(30),
+        BaseLotsPerBaseUnit::new(10),
+    );
+    let market_key = Pubkey::new_unique();
+    let makers = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let taker = Pubkey::new_unique();
+    let mut replay = MarketReplay::new(market_key, 0, market.as_ref());
+
+    // Both sides hold 16 orders, with several orders at each price
+    let mut instructions = vec![];
+    for (i, maker) in makers.iter().cycle().take(16).enumerate() {
+        let sequence_number = instructions.len() as u64;
+        instructions.push(record_instruction(
+            market_key,
+            sequence_number,
+            *maker,
+            &mut |record| {
+                for (side, price_in_ticks) in [
+                    (Side::Bid, 100 - i as u64 % 5),
+                    (Side::Ask, 110 + i as u64 % 5),
+                ] {
+                    market
+                        .place_order(
+                            maker,
+                            OrderPacket::new_post_only_default(side, price_in_ticks, 1 + i as u64),
+                            record,
+                            &mut || (0, 0),
+                        )
+                        .unwrap();
+                }
+            },
+        ));
+    }
+    let sequence_number = instructions.len() as u64;
+    instructions.push(record_instruction(
+        market_key,
+        sequence_number,
+        taker,
+        &mut |record| {
+            market
+                .place_order(
+                    &taker,
+                    OrderPacket::new_limit_order(
+                        Side::Ask,
+                        98,
+                        10,
+                        SelfTradeBehavior::Abort,
+                        None,
+                        0,
+                        false,
+                    ),
+                    record,
+                    &mut || (0, 0),
+                )
+                .unwrap();
+        },
+    ));
+    for instruction in instructions.iter() {
+        replay.apply(instruction).unwrap();
+    }
+
+    assert!(replay.get_book(Side::Bid).len() > 11);
+    assert_eq!(replay.get_book(Side::Ask).len(), 16);
+    for side in [Side::Bid, Side::Ask] {
+        assert!(replay
+            .get_book(side)
+            .keys()
+            .eq(market.get_book(side).iter().map(|(order_id, _)| order_id)));
+    }
+    replay.verify(market.as_ref()).unwrap();
+}
@@ -154,6 +154,10 @@ pub enum PhoenixInstruction {
     #[account(3, writable, signer, name = "payer")]
     #[account(4, writable, name = "seat")]
     #[account(5, name = "system_program", desc = "System program")]
+    #[account(6, optional, writable, name = "fee_recipient", desc = "Required if the market charges a lamport fee for permissionless seats")]
+    #[account(7, optional, writable, name = "quote_account", desc = "Required if the market charges a quote token fee for permissionless seats")]
+    #[account(8, optional, writable, name = "fee_recipient_quote_account", desc = "Required if the market charges a quote token fee for permissionless seats")]
+    #[account(9, optional, name = "token_program", desc = "Required if the market charges a quote token fee for permissionless seats")]
     RequestSeat = 14,
 
     #[account(0, signer, name = "log_authority", desc = "Log authority")]
@@ -182,9 +186,72 @@ pub enum PhoenixInstruction {
     #[account(4, name = "seat")]
     PlaceMultiplePostOnlyOrdersWithFreeFunds = 17,
 
+    /// Join or leave a self trade prevention group. Orders from traders in the same group will not match
+    /// against each other. The group key must sign to join a group.
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "trader")]
+    #[account(4, name = "seat")]
+    #[account(5, optional, signer, name = "group", desc = "The self trade prevention group key, only required to join a group")]
+    ChangeSelfTradePreventionGroup = 18,
+
+    /// Add size to an existing order on the book. The order is moved to the back of the queue at its price
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "trader")]
+    #[account(4, name = "seat")]
+    #[account(5, writable, name = "base_account", desc = "Trader base token account")]
+    #[account(6, writable, name = "quote_account", desc = "Trader quote token account")]
+    #[account(7, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(8, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    IncreaseOrder = 19,
+
+    /// Add size to an existing order on the book using only deposited funds
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "trader")]
+    #[account(4, name = "seat")]
+    IncreaseOrderWithFreeFunds = 20,
+
+    /// Request a seat for a trader on the market's seat allowlist. The seat is approved if the inclusion proof is valid
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, writable, signer, name = "payer")]
+    #[account(4, writable, name = "seat")]
+    #[account(5, name = "system_program", desc = "System program")]
+    RequestSeatWithProof = 21,
+
+    /// Evict the least recently active trader without open orders from a market whose seats are all taken.
+    /// The free funds of the evicted trader are moved to its seat
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "signer")]
+    #[account(4, writable, name = "seat", desc = "The evicted trader's PDA seat account, seeds are [b'seat', market_address, trader_address]")]
+    EvictInactiveTrader = 22,
+
+    /// Withdraw the funds that were moved to a trader's seat when the trader was evicted for inactivity.
+    /// Once the market is Closed, the market authority can also send the funds to the trader's token accounts
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "trader", desc = "The evicted trader, or the market authority if the market is Closed")]
+    #[account(4, writable, name = "seat")]
+    #[account(5, writable, name = "base_account", desc = "Trader base token account")]
+    #[account(6, writable, name = "quote_account", desc = "Trader quote token account")]
+    #[account(7, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(8, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(9, name = "token_program", desc = "Token program")]
+    WithdrawEvictedFunds = 23,
+
 
     // Admin instructions
-    /// Create a market 
+    /// Create a market. The capacities in the market size params must be powers of two from 4 to 65536 (see `MarketSizeParams`)
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
@@ -209,23 +276,25 @@ pub enum PhoenixInstruction {
     #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to name successor")]
     NameSuccessor = 102,
 
+    /// Change the status of a market. Tombstoning a market does not close the market account, the vaults and the
+    /// market account are closed with CloseMarket once the market is Tombstoned.
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change market status")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or pause guardian account must sign to change market status")]
     ChangeMarketStatus = 103,
 
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change seat status")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to change seat status")]
     #[account(4, writable, name = "seat")]
     ChangeSeatStatus = 104,
 
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to request a seat on behalf of a trader")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to request a seat on behalf of a trader")]
     #[account(4, writable, signer, name = "payer")]
     #[account(5, name = "trader")]
     #[account(6, writable, name = "seat")]
@@ -235,7 +304,7 @@ pub enum PhoenixInstruction {
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to evict a seat")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to evict a seat")]
     #[account(4, name = "trader")]
     #[account(5, name = "seat", desc = "The trader's PDA seat account, seeds are [b'seat', market_address, trader_address]")]
     #[account(6, writable, name = "base_account")]
@@ -248,7 +317,7 @@ pub enum PhoenixInstruction {
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to claim authority")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to force cancel orders")]
     #[account(4, name = "trader")]
     #[account(5, name = "seat", desc = "The trader's PDA seat account, seeds are [b'seat', market_address, trader_address]")]
     #[account(6, writable, name = "base_account", desc = "Trader base token account")]
@@ -270,9 +339,161 @@ pub enum PhoenixInstruction {
     #[account(0, name = "phoenix_program", desc = "Phoenix program")]
     #[account(1, name = "log_authority", desc = "Phoenix log authority")]
     #[account(2, writable, name = "market", desc = "This account holds the market state")]
-    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the free recipient")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or fee manager account must sign to change the free recipient")]
     #[account(4, name = "new_fee_recipient", desc = "New fee recipient")]
     ChangeFeeRecipient = 109,
+
+    /// Change how the market makes room for new orders when a side of the book is full
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the book full policy")]
+    ChangeBookFullPolicy = 110,
+
+    /// Set the self trade prevention group of a trader
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the self trade prevention group of a trader")]
+    #[account(4, name = "trader")]
+    #[account(5, name = "seat", desc = "The trader's PDA seat account, seeds are [b'seat', market_address, trader_address]")]
+    ChangeSelfTradePreventionGroupAuthorized = 111,
+
+    /// Increase the capacity of the order book and the number of seats of a market. The market account can only grow by
+    /// a limited number of bytes per instruction, so this may need to be sent multiple times to reach the new size.
+    /// The new capacities must be powers of two from 4 to 65536 (see `MarketSizeParams`).
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to expand the market")]
+    #[account(4, writable, signer, name = "payer", desc = "Pays for the rent of the additional market space")]
+    #[account(5, name = "system_program", desc = "System program")]
+    ExpandMarket = 112,
+
+    /// Cancel all orders and withdraw the funds of the next registered traders of a Closed market to their associated
+    /// token accounts. Traders are processed in the order of the trader map and removed from the market, so all traders
+    /// are processed in batches. The accounts of each trader are passed as a (seat, base_account, quote_account) triple
+    /// after the token program. Funds for a missing associated token account are moved to the trader's seat.
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to withdraw funds")]
+    #[account(4, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(5, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    ForceWithdrawFunds = 113,
+
+    /// Close the vaults and the market account of a Tombstoned market. The vaults must be empty.
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to close the market")]
+    #[account(4, writable, name = "receiver", desc = "Receives the lamports of the closed accounts")]
+    #[account(5, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(6, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(7, name = "token_program", desc = "Token program")]
+    CloseMarket = 114,
+
+    /// Close the retired seat of a trader that is no longer registered on the market
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to close a seat")]
+    #[account(4, writable, name = "seat", desc = "The trader's PDA seat account, seeds are [b'seat', market_address, trader_address]")]
+    #[account(5, writable, name = "receiver", desc = "Receives the lamports of the seat")]
+    CloseSeat = 115,
+
+    /// Change whether seats requested by traders are approved without the market authority, and the fee charged for such seats
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the seat approval params")]
+    ChangeSeatApprovalParams = 116,
+
+    /// Replace the root of the Merkle tree of traders that can approve their own seat. A zero root disables the allowlist
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the seat allowlist root")]
+    ChangeSeatAllowlistRoot = 117,
+
+    /// Change whether inactive traders can be evicted from the market when all of its seats are taken
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change the trader eviction policy")]
+    ChangeTraderEvictionPolicy = 118,
+
+    /// Assign or revoke the pause guardian, seat manager or fee manager role
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to change a market role")]
+    ChangeMarketRole = 119,
+
+    /// Propose a governance action. While the market has a governance delay, fee, fee recipient, successor
+    /// and Closed status changes can only be made by executing a proposal once the delay has passed
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, writable, signer, name = "market_authority", desc = "The market_authority account must sign to propose a governance action and pays for the proposal account")]
+    #[account(4, writable, name = "proposal", desc = "The proposal PDA, seeds are [b'proposal', market_address, proposal_id]")]
+    #[account(5, name = "system_program", desc = "System program")]
+    ProposeGovernanceAction = 120,
+
+    /// Execute a proposed governance action once its delay has passed and close the proposal
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, writable, signer, name = "market_authority", desc = "The market_authority account must sign to execute a governance action and receives the lamports of the proposal")]
+    #[account(4, writable, name = "proposal", desc = "The proposal PDA, seeds are [b'proposal', market_address, proposal_id]")]
+    #[account(5, optional, signer, name = "previous_fee_recipient", desc = "The current fee recipient, only required to change the fee recipient if there are uncollected fees")]
+    ExecuteGovernanceAction = 121,
+
+    /// Cancel a proposed governance action and close the proposal
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, writable, signer, name = "market_authority", desc = "The market_authority account must sign to cancel a governance action and receives the lamports of the proposal")]
+    #[account(4, writable, name = "proposal", desc = "The proposal PDA, seeds are [b'proposal', market_address, proposal_id]")]
+    CancelGovernanceAction = 122,
+
+    /// Withdraw the pending successor nomination
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority account must sign to cancel the successor")]
+    CancelSuccessor = 123,
+
+    /// Evict the seats of many traders. Each trader is given as a group of 4 remaining accounts
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to evict seats")]
+    #[account(4, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(5, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    // Remaining accounts: (trader, seat, writable base_account, writable quote_account) for each trader
+    BatchEvictSeats = 124,
+
+    /// Force cancel the orders of many traders. Each trader is given as a group of 4 remaining accounts
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or seat manager account must sign to force cancel orders")]
+    #[account(4, writable, name = "base_vault", desc = "Base vault PDA, seeds are [b'vault', market_address, base_mint_address]")]
+    #[account(5, writable, name = "quote_vault", desc = "Quote vault PDA, seeds are [b'vault', market_address, quote_mint_address]")]
+    #[account(6, name = "token_program", desc = "Token program")]
+    // Remaining accounts: (trader, seat, writable base_account, writable quote_account) for each trader
+    BatchForceCancelOrders = 125,
+
+    /// Schedule a market status transition that is applied by the first instruction on the market after the given
+    /// unix timestamp, or cancel the scheduled transition
+    #[account(0, name = "phoenix_program", desc = "Phoenix program")]
+    #[account(1, name = "log_authority", desc = "Phoenix log authority")]
+    #[account(2, writable, name = "market", desc = "This account holds the market state")]
+    #[account(3, signer, name = "market_authority", desc = "The market_authority or pause guardian account must sign to schedule a market status change")]
+    ScheduleMarketStatus = 126,
 }
 
 impl PhoenixInstruction {
@@ -283,13 +504,13 @@ impl PhoenixInstruction {
 
 #[test]
 fn test_instruction_serialization() {
-    for i in 0..=108 {
+    for i in 0..=126 {
         let instruction = match PhoenixInstruction::try_from(i) {
             Ok(j) => j,
             Err(_) => {
                 assert!(i < 100);
                 // This needs to be changed if new instructions are added
-                assert!(i > 17);
+                assert!(i > 23);
                 continue;
             }
         };
@@ -1,11 +1,14 @@
 use crate::phoenix_log_authority;
 use crate::program::status::{MarketStatus, SeatApprovalStatus};
 use crate::program::{
-    get_market_size, processor::*, MarketHeader, MarketSizeParams, PhoenixInstruction,
+    create_withdraw_evicted_funds_instruction, get_governance_proposal_address, get_market_size,
+    processor::*, GovernanceAction, MarketHeader, MarketRole, MarketSizeParams, PhoenixInstruction,
+    SeatApprovalParams,
 };
-use crate::state::Side;
+use crate::state::{BookFullPolicy, Side};
 use borsh::BorshSerialize;
 use solana_program::{
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     pubkey::Pubkey,
@@ -134,6 +137,7 @@ pub fn create_initialize_market_instruction(
                 taker_fee_bps,
                 fee_collector: *fee_collector,
                 raw_base_units_per_base_unit,
+                seat_approval_params: None,
             }
             .try_to_vec()
             .unwrap(),
@@ -173,6 +177,102 @@ pub fn create_evict_seat_instruction(
     }
 }
 
+pub fn create_batch_evict_seats_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    traders: &[Pubkey],
+    base: &Pubkey,
+    quote: &Pubkey,
+) -> Instruction {
+    _batch_authorized_action_template(
+        authority,
+        market,
+        traders,
+        base,
+        quote,
+        PhoenixInstruction::BatchEvictSeats.to_vec(),
+    )
+}
+
+pub fn create_batch_force_cancel_orders_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    traders: &[Pubkey],
+    base: &Pubkey,
+    quote: &Pubkey,
+    params: &CancelUpToParams,
+) -> Instruction {
+    _batch_authorized_action_template(
+        authority,
+        market,
+        traders,
+        base,
+        quote,
+        [
+            PhoenixInstruction::BatchForceCancelOrders.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    )
+}
+
+fn _batch_authorized_action_template(
+    authority: &Pubkey,
+    market: &Pubkey,
+    traders: &[Pubkey],
+    base: &Pubkey,
+    quote: &Pubkey,
+    data: Vec<u8>,
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base);
+    let (quote_vault, _) = get_vault_address(market, quote);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(crate::id(), false),
+        AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(base_vault, false),
+        AccountMeta::new(quote_vault, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for trader in traders {
+        let (seat, _) = get_seat_address(market, trader);
+        accounts.extend([
+            AccountMeta::new_readonly(*trader, false),
+            AccountMeta::new_readonly(seat, false),
+            AccountMeta::new(get_associated_token_address(trader, base), false),
+            AccountMeta::new(get_associated_token_address(trader, quote), false),
+        ]);
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data,
+    }
+}
+
+/// Passing `None` cancels the scheduled status transition
+pub fn create_schedule_market_status_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    params: Option<governance::ScheduledMarketStatusParams>,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ScheduleMarketStatus.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
 pub fn create_claim_authority_instruction(authority: &Pubkey, market: &Pubkey) -> Instruction {
     Instruction {
         program_id: crate::id(),
@@ -207,6 +307,46 @@ pub fn create_name_successor_instruction(
     }
 }
 
+pub fn create_name_successor_with_expiry_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    successor: &Pubkey,
+    expiry_unix_timestamp: i64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::NameSuccessor.to_vec(),
+            governance::NameSuccessorParams {
+                successor: *successor,
+                expiry_unix_timestamp: Some(expiry_unix_timestamp),
+            }
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_cancel_successor_instruction(authority: &Pubkey, market: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: PhoenixInstruction::CancelSuccessor.to_vec(),
+    }
+}
+
 pub fn create_change_market_status_instruction(
     authority: &Pubkey,
     market: &Pubkey,
@@ -228,6 +368,105 @@ pub fn create_change_market_status_instruction(
     }
 }
 
+pub fn create_change_book_full_policy_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    book_full_policy: BookFullPolicy,
+    min_eviction_improvement_in_ticks: u64,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ChangeBookFullPolicy.to_vec(),
+            governance::ChangeBookFullPolicyParams {
+                book_full_policy,
+                min_eviction_improvement_in_ticks,
+            }
+            .try_to_vec()
+            .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_expand_market_instruction(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    market: &Pubkey,
+    market_size_params: MarketSizeParams,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            PhoenixInstruction::ExpandMarket.to_vec(),
+            ExpandMarketParams { market_size_params }
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// Returns enough expand market instructions to grow the market account from its current size
+/// params to the new ones. The instructions can be split across multiple transactions, but they
+/// must be executed in order.
+pub fn create_expand_market_instructions(
+    authority: &Pubkey,
+    payer: &Pubkey,
+    market: &Pubkey,
+    current_market_size_params: MarketSizeParams,
+    new_market_size_params: MarketSizeParams,
+) -> Result<Vec<Instruction>, ProgramError> {
+    let current_space = get_market_size(&current_market_size_params)?;
+    let new_space = get_market_size(&new_market_size_params)?;
+    let num_instructions = new_space
+        .saturating_sub(current_space)
+        .div_ceil(MAX_PERMITTED_DATA_INCREASE)
+        .max(1);
+    Ok((0..num_instructions)
+        .map(|_| create_expand_market_instruction(authority, payer, market, new_market_size_params))
+        .collect())
+}
+
+pub fn create_change_self_trade_prevention_group_authorized_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    trader: &Pubkey,
+    group: &Pubkey,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*trader, false),
+            AccountMeta::new_readonly(seat, false),
+        ],
+        data: [
+            PhoenixInstruction::ChangeSelfTradePreventionGroupAuthorized.to_vec(),
+            group.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
 pub fn create_request_seat_authorized_instruction(
     authority: &Pubkey,
     payer: &Pubkey,
@@ -251,6 +490,162 @@ pub fn create_request_seat_authorized_instruction(
     }
 }
 
+pub fn create_change_seat_approval_params_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    seat_approval_params: SeatApprovalParams,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ChangeSeatApprovalParams.to_vec(),
+            seat_approval_params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_change_seat_allowlist_root_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    root: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ChangeSeatAllowlistRoot.to_vec(),
+            root.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_change_trader_eviction_policy_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    evict_inactive_traders: bool,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ChangeTraderEvictionPolicy.to_vec(),
+            evict_inactive_traders.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_change_market_role_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    role: MarketRole,
+    key: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: [
+            PhoenixInstruction::ChangeMarketRole.to_vec(),
+            governance::ChangeMarketRoleParams { role, key: *key }
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+/// The proposal id must be the current `governance_proposal_count` of the market
+pub fn create_propose_governance_action_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    proposal_id: u64,
+    action: &GovernanceAction,
+) -> Instruction {
+    let (proposal, _) = get_governance_proposal_address(market, proposal_id);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            PhoenixInstruction::ProposeGovernanceAction.to_vec(),
+            action.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_execute_governance_action_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    proposal_id: u64,
+    previous_fee_recipient: Option<&Pubkey>,
+) -> Instruction {
+    let (proposal, _) = get_governance_proposal_address(market, proposal_id);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(crate::id(), false),
+        AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new(*authority, true),
+        AccountMeta::new(proposal, false),
+    ];
+    if let Some(previous_fee_recipient) = previous_fee_recipient {
+        accounts.push(AccountMeta::new_readonly(*previous_fee_recipient, true));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: PhoenixInstruction::ExecuteGovernanceAction.to_vec(),
+    }
+}
+
+pub fn create_cancel_governance_action_instruction(
+    authority: &Pubkey,
+    market: &Pubkey,
+    proposal_id: u64,
+) -> Instruction {
+    let (proposal, _) = get_governance_proposal_address(market, proposal_id);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(proposal, false),
+        ],
+        data: PhoenixInstruction::CancelGovernanceAction.to_vec(),
+    }
+}
+
 pub fn create_change_seat_status_instruction(
     authority: &Pubkey,
     market: &Pubkey,
@@ -414,3 +809,121 @@ fn create_force_cancel_orders_instruction(
         .concat(),
     }
 }
+
+/// Creates the instructions for the market authority to cancel all orders and withdraw the funds
+/// of the given traders of a Closed market to their associated token accounts,
+/// `traders_per_instruction` traders at a time. `traders` must contain all registered traders,
+/// and they are sorted into the order in which `ForceWithdrawFunds` processes them.
+pub fn create_force_withdraw_funds_instructions(
+    market: &Pubkey,
+    market_authority: &Pubkey,
+    traders: &[Pubkey],
+    base: &Pubkey,
+    quote: &Pubkey,
+    traders_per_instruction: usize,
+) -> Vec<Instruction> {
+    let mut traders = traders.to_vec();
+    traders.sort();
+    traders
+        .chunks(traders_per_instruction.max(1))
+        .map(|batch| {
+            create_force_withdraw_funds_instruction(market, market_authority, batch, base, quote)
+        })
+        .collect()
+}
+
+/// `traders` must be the next registered traders of the market, in the order of the trader map.
+pub fn create_force_withdraw_funds_instruction(
+    market: &Pubkey,
+    market_authority: &Pubkey,
+    traders: &[Pubkey],
+    base: &Pubkey,
+    quote: &Pubkey,
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base);
+    let (quote_vault, _) = get_vault_address(market, quote);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(crate::id(), false),
+        AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*market_authority, true),
+        AccountMeta::new(base_vault, false),
+        AccountMeta::new(quote_vault, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    for trader in traders {
+        accounts.push(AccountMeta::new(get_seat_address(market, trader).0, false));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(trader, base),
+            false,
+        ));
+        accounts.push(AccountMeta::new(
+            get_associated_token_address(trader, quote),
+            false,
+        ));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: PhoenixInstruction::ForceWithdrawFunds.to_vec(),
+    }
+}
+
+pub fn create_close_market_instruction(
+    market: &Pubkey,
+    market_authority: &Pubkey,
+    receiver: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base);
+    let (quote_vault, _) = get_vault_address(market, quote);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(*receiver, false),
+            AccountMeta::new(base_vault, false),
+            AccountMeta::new(quote_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: PhoenixInstruction::CloseMarket.to_vec(),
+    }
+}
+
+/// Sends the evicted funds of a trader to its associated token accounts. The market must be Closed.
+pub fn create_sweep_evicted_funds_instruction(
+    market: &Pubkey,
+    market_authority: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+) -> Instruction {
+    let mut ix = create_withdraw_evicted_funds_instruction(market, trader, base, quote);
+    ix.accounts[3] = AccountMeta::new_readonly(*market_authority, true);
+    ix
+}
+
+pub fn create_close_seat_instruction(
+    market: &Pubkey,
+    market_authority: &Pubkey,
+    trader: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*market_authority, true),
+            AccountMeta::new(seat, false),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: PhoenixInstruction::CloseSeat.to_vec(),
+    }
+}
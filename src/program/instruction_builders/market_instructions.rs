@@ -1,7 +1,7 @@
 use crate::phoenix_log_authority;
 use crate::program::new_order::MultipleOrderPacket;
 use crate::program::withdraw::WithdrawParams;
-use crate::program::{processor::*, PhoenixInstruction};
+use crate::program::{processor::*, PhoenixInstruction, SeatApprovalParams};
 use crate::state::{OrderPacket, OrderPacketMetadata};
 use borsh::BorshSerialize;
 use solana_program::{
@@ -274,6 +274,83 @@ pub fn create_reduce_order_with_free_funds_instruction(
     }
 }
 
+pub fn create_increase_order_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+    params: &IncreaseOrderParams,
+) -> Instruction {
+    let base_account = get_associated_token_address(trader, base);
+    let quote_account = get_associated_token_address(trader, quote);
+    create_increase_order_instruction_with_custom_token_accounts(
+        market,
+        trader,
+        &base_account,
+        &quote_account,
+        base,
+        quote,
+        params,
+    )
+}
+
+pub fn create_increase_order_instruction_with_custom_token_accounts(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base_account: &Pubkey,
+    quote_account: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+    params: &IncreaseOrderParams,
+) -> Instruction {
+    let (base_vault, _) = get_vault_address(market, base);
+    let (quote_vault, _) = get_vault_address(market, quote);
+    let (seat, _) = get_seat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new_readonly(seat, false),
+            AccountMeta::new(*base_account, false),
+            AccountMeta::new(*quote_account, false),
+            AccountMeta::new(base_vault, false),
+            AccountMeta::new(quote_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: [
+            PhoenixInstruction::IncreaseOrder.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_increase_order_with_free_funds_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    params: &IncreaseOrderParams,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new_readonly(seat, false),
+        ],
+        data: [
+            PhoenixInstruction::IncreaseOrderWithFreeFunds.to_vec(),
+            params.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
+
 pub fn create_deposit_funds_instruction(
     market: &Pubkey,
     trader: &Pubkey,
@@ -651,3 +728,126 @@ pub fn create_request_seat_instruction(payer: &Pubkey, market: &Pubkey) -> Instr
         data: PhoenixInstruction::RequestSeat.to_vec(),
     }
 }
+
+/// Creates a request seat instruction for a market that approves seats without the market authority
+/// and charges a seat fee. The fee accounts are only included for the fees that the market charges.
+pub fn create_request_seat_with_fee_instruction(
+    payer: &Pubkey,
+    market: &Pubkey,
+    quote: &Pubkey,
+    fee_recipient: &Pubkey,
+    seat_approval_params: &SeatApprovalParams,
+) -> Instruction {
+    let mut ix = create_request_seat_instruction(payer, market);
+    if seat_approval_params.seat_fee_in_lamports > 0 {
+        ix.accounts.push(AccountMeta::new(*fee_recipient, false));
+    }
+    if seat_approval_params.seat_fee_in_quote_lots > 0 {
+        ix.accounts.extend_from_slice(&[
+            AccountMeta::new(get_associated_token_address(payer, quote), false),
+            AccountMeta::new(get_associated_token_address(fee_recipient, quote), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ]);
+    }
+    ix
+}
+
+pub fn create_request_seat_with_proof_instruction(
+    payer: &Pubkey,
+    market: &Pubkey,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, payer);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(seat, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            PhoenixInstruction::RequestSeatWithProof.to_vec(),
+            manage_seat::RequestSeatWithProofParams { proof }
+                .try_to_vec()
+                .unwrap(),
+        ]
+        .concat(),
+    }
+}
+
+pub fn create_evict_inactive_trader_instruction(
+    market: &Pubkey,
+    signer: &Pubkey,
+    evicted_trader: &Pubkey,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, evicted_trader);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(seat, false),
+        ],
+        data: PhoenixInstruction::EvictInactiveTrader.to_vec(),
+    }
+}
+
+pub fn create_withdraw_evicted_funds_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    base: &Pubkey,
+    quote: &Pubkey,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, trader);
+    let (base_vault, _) = get_vault_address(market, base);
+    let (quote_vault, _) = get_vault_address(market, quote);
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(crate::id(), false),
+            AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+            AccountMeta::new(*market, false),
+            AccountMeta::new_readonly(*trader, true),
+            AccountMeta::new(seat, false),
+            AccountMeta::new(get_associated_token_address(trader, base), false),
+            AccountMeta::new(get_associated_token_address(trader, quote), false),
+            AccountMeta::new(base_vault, false),
+            AccountMeta::new(quote_vault, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: PhoenixInstruction::WithdrawEvictedFunds.to_vec(),
+    }
+}
+
+pub fn create_change_self_trade_prevention_group_instruction(
+    market: &Pubkey,
+    trader: &Pubkey,
+    group: &Pubkey,
+) -> Instruction {
+    let (seat, _) = get_seat_address(market, trader);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(crate::id(), false),
+        AccountMeta::new_readonly(phoenix_log_authority::id(), false),
+        AccountMeta::new(*market, false),
+        AccountMeta::new_readonly(*trader, true),
+        AccountMeta::new_readonly(seat, false),
+    ];
+    // The group key only needs to sign when joining a group
+    if group != &Pubkey::default() {
+        accounts.push(AccountMeta::new_readonly(*group, true));
+    }
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: [
+            PhoenixInstruction::ChangeSelfTradePreventionGroup.to_vec(),
+            group.try_to_vec().unwrap(),
+        ]
+        .concat(),
+    }
+}
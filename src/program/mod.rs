@@ -1,9 +1,12 @@
 pub(crate) mod event_recorder;
 pub(crate) mod processor;
+#[cfg(test)]
+pub(crate) mod test_utils;
 pub(crate) mod token_utils;
 pub(crate) mod validation;
 
 pub mod accounts;
+pub mod allowlist;
 pub mod dispatch_market;
 pub mod error;
 pub mod events;
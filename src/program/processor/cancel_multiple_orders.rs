@@ -18,6 +18,11 @@ use solana_program::{
 };
 use std::mem::size_of;
 
+use super::{
+    evict_trader::{get_activity_slot, record_trader_activity},
+    withdraw::record_withdraw_event,
+};
+
 use super::CancelOrderParams;
 
 #[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
@@ -54,13 +59,15 @@ pub(crate) fn process_cancel_all_orders<'a, 'info>(
     } = market_context;
 
     let claim_funds = vault_context_option.is_some();
+    let activity_slot = get_activity_slot(market_info)?;
     let MatchingEngineResponse {
         num_base_lots_out,
         num_quote_lots_out,
         ..
     } = {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader.key, activity_slot);
         sol_log_compute_units();
         market
             .cancel_all_orders(trader.key, claim_funds, record_event_fn)
@@ -78,6 +85,13 @@ pub(crate) fn process_cancel_all_orders<'a, 'info>(
         token_program,
     }) = vault_context_option
     {
+        record_withdraw_event(
+            market_info,
+            trader.key,
+            num_base_lots_out,
+            num_quote_lots_out,
+            record_event_fn,
+        )?;
         try_withdraw(
             market_info.key,
             &header.base_params,
@@ -135,6 +149,7 @@ pub(crate) fn process_cancel_up_to<'a, 'info>(
         trader.key,
         vault_context_option,
         params,
+        get_activity_slot(market_info)?,
         record_event_fn,
     )
 }
@@ -163,6 +178,7 @@ pub(crate) fn process_cancel_multiple_orders_by_id<'a, 'info>(
     if cancel_params.orders.is_empty() {
         return Ok(());
     }
+    let activity_slot = get_activity_slot(market_info)?;
 
     let MatchingEngineResponse {
         num_quote_lots_out,
@@ -171,7 +187,8 @@ pub(crate) fn process_cancel_multiple_orders_by_id<'a, 'info>(
     } = {
         sol_log_compute_units();
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader.key, activity_slot);
         let orders_to_cancel = cancel_params
             .orders
             .iter()
@@ -214,6 +231,13 @@ pub(crate) fn process_cancel_multiple_orders_by_id<'a, 'info>(
         token_program,
     }) = vault_context_option
     {
+        record_withdraw_event(
+            market_info,
+            trader.key,
+            num_base_lots_out,
+            num_quote_lots_out,
+            record_event_fn,
+        )?;
         try_withdraw(
             market_info.key,
             &header.base_params,
@@ -250,6 +274,7 @@ pub(crate) fn process_cancel_orders<'a, 'info>(
     trader_key: &Pubkey,
     vault_context_option: Option<PhoenixVaultContext<'a, 'info>>,
     cancel_params: CancelUpToParams,
+    activity_slot: Option<u64>,
     record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let CancelUpToParams {
@@ -262,7 +287,8 @@ pub(crate) fn process_cancel_orders<'a, 'info>(
     let claim_funds = vault_context_option.is_some();
     let released = {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader_key, activity_slot);
         sol_log_compute_units();
         market
             .cancel_up_to(
@@ -293,6 +319,13 @@ pub(crate) fn process_cancel_orders<'a, 'info>(
         token_program,
     }) = vault_context_option
     {
+        record_withdraw_event(
+            market_info,
+            trader_key,
+            num_base_lots_out,
+            num_quote_lots_out,
+            record_event_fn,
+        )?;
         try_withdraw(
             market_info.key,
             &header.base_params,
@@ -4,11 +4,14 @@ use crate::{
         MarketHeader, PhoenixError, PhoenixMarketContext, PhoenixVaultContext,
     },
     quantities::{BaseLots, QuoteLots, WrapperU64},
+    state::markets::MarketEvent,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
 use std::mem::size_of;
 
+use super::evict_trader::{get_activity_slot, record_trader_activity};
+
 #[derive(BorshDeserialize, BorshSerialize, Clone)]
 pub struct DepositParams {
     pub quote_lots_to_deposit: u64,
@@ -20,6 +23,7 @@ pub(crate) fn process_deposit_funds<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let DepositContext {
         vault_context:
@@ -45,9 +49,11 @@ pub(crate) fn process_deposit_funds<'a, 'info>(
         signer: trader,
     } = market_context;
 
+    let activity_slot = get_activity_slot(market_info)?;
     {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader.key, activity_slot);
         market
             .get_or_register_trader(trader.key)
             .ok_or(PhoenixError::TraderNotFound)?;
@@ -56,6 +62,13 @@ pub(crate) fn process_deposit_funds<'a, 'info>(
             .ok_or(PhoenixError::TraderNotFound)?;
         trader_state.deposit_free_base_lots(base_lots);
         trader_state.deposit_free_quote_lots(quote_lots);
+        record_event_fn(MarketEvent::Deposit {
+            trader: *trader.key,
+            base_lots_deposited: base_lots,
+            quote_lots_deposited: quote_lots,
+            base_lots_free: trader_state.base_lots_free,
+            quote_lots_free: trader_state.quote_lots_free,
+        });
     }
 
     let header = market_info.get_header()?;
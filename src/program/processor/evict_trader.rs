@@ -0,0 +1,274 @@
+use crate::{
+    program::{
+        dispatch_market::load_with_dispatch_mut, error::PhoenixError, token_utils::try_withdraw,
+        validation::checkers::phoenix_checkers::MarketAccountInfo, EvictInactiveTraderContext,
+        MarketHeader, PhoenixMarketContext, PhoenixVaultContext, WithdrawEvictedFundsContext,
+    },
+    quantities::{BaseLots, QuoteLots, WrapperU64},
+    state::{
+        markets::{FIFOOrderId, FIFORestingOrder, MarketEvent, WritableMarket},
+        OrderPacket,
+    },
+};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult,
+    program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
+};
+use std::mem::size_of;
+
+/// This instruction can be sent by anyone to evict the least recently active trader without open
+/// orders from a market whose seats are all taken, so that a new trader can be registered.
+///
+/// The free funds of the evicted trader are moved to its seat, and can be withdrawn with the
+/// WithdrawEvictedFunds instruction.
+///
+/// Eviction is a separate instruction rather than part of seat approval because the seat of the
+/// evicted trader must be passed in as a writable account to receive its funds. The approval
+/// instructions have a fixed account layout, and the least recently active trader can only be
+/// found by scanning the market off chain.
+pub(crate) fn process_evict_inactive_trader<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let EvictInactiveTraderContext { seat } =
+        EvictInactiveTraderContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let trader = seat.load_mut()?.trader;
+    let trader_state = {
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        market
+            .evict_inactive_trader(&trader)
+            .ok_or(PhoenixError::EvictionError)?
+    };
+    record_event_fn(MarketEvent::TraderEviction {
+        trader,
+        base_lots_evicted: trader_state.base_lots_free,
+        quote_lots_evicted: trader_state.quote_lots_free,
+    });
+    let mut seat = seat.load_mut()?;
+    seat.evicted_base_lots += trader_state.base_lots_free.as_u64();
+    seat.evicted_quote_lots += trader_state.quote_lots_free.as_u64();
+    Ok(())
+}
+
+/// This instruction is used by a trader that was evicted from the market for inactivity to
+/// withdraw the free funds that it held at the time of its eviction. Once the market is Closed,
+/// the market authority can also send these funds to the trader's token accounts.
+pub(crate) fn process_withdraw_evicted_funds<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let WithdrawEvictedFundsContext {
+        seat,
+        vault_context,
+    } = WithdrawEvictedFundsContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let (base_lots, quote_lots) = {
+        let mut seat = seat.load_mut()?;
+        let base_lots = BaseLots::new(seat.evicted_base_lots);
+        let quote_lots = QuoteLots::new(seat.evicted_quote_lots);
+        seat.evicted_base_lots = 0;
+        seat.evicted_quote_lots = 0;
+        // The funds come from the seat rather than the market, so the free balances reported
+        // are what remains on the seat.
+        if base_lots != BaseLots::ZERO || quote_lots != QuoteLots::ZERO {
+            record_event_fn(MarketEvent::Withdraw {
+                trader: seat.trader,
+                base_lots_withdrawn: base_lots,
+                quote_lots_withdrawn: quote_lots,
+                base_lots_free: BaseLots::new(seat.evicted_base_lots),
+                quote_lots_free: QuoteLots::new(seat.evicted_quote_lots),
+            });
+        }
+        (base_lots, quote_lots)
+    };
+    let PhoenixVaultContext {
+        base_account,
+        quote_account,
+        base_vault,
+        quote_vault,
+        token_program,
+    } = vault_context;
+    let header = market_info.get_header()?;
+    try_withdraw(
+        market_info.key,
+        &header.base_params,
+        &header.quote_params,
+        token_program.as_ref(),
+        quote_account.as_ref(),
+        quote_vault,
+        base_account.as_ref(),
+        base_vault,
+        quote_lots * header.get_quote_lot_size(),
+        base_lots * header.get_base_lot_size(),
+    )
+}
+
+/// This function can only be called by the current market authority to change whether inactive
+/// traders can be evicted from the market when all of its seats are taken
+///
+/// Activity is only recorded while the policy is on, so when it is switched on, the current slot
+/// is recorded as the last activity of every registered trader.
+pub(crate) fn process_change_trader_eviction_policy<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    let evict_inactive_traders = bool::try_from_slice(data)?;
+    let was_evicting_inactive_traders = {
+        let mut header = market_info.get_header_mut()?;
+        let previous_policy = header.evict_inactive_traders != 0;
+        header.evict_inactive_traders = evict_inactive_traders as u64;
+        previous_policy
+    };
+    if evict_inactive_traders && !was_evicting_inactive_traders {
+        let slot = Clock::get()?.slot;
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        for (_, trader_state) in market.get_registered_traders_mut().iter_mut() {
+            trader_state.last_activity_slot = slot;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the slot to record as the last activity of a trader that signs a market instruction, or
+/// None if the market does not evict inactive traders. This must be called before the market is
+/// borrowed, and the slot is recorded with `record_trader_activity` within that borrow.
+pub(crate) fn get_activity_slot(
+    market_info: &MarketAccountInfo,
+) -> Result<Option<u64>, ProgramError> {
+    if market_info.get_header()?.evict_inactive_traders == 0 {
+        return Ok(None);
+    }
+    Ok(Some(Clock::get()?.slot))
+}
+
+/// Records `activity_slot` as the last activity of the trader, if the trader is registered
+pub(crate) fn record_trader_activity(
+    market: &mut dyn WritableMarket<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    trader: &Pubkey,
+    activity_slot: Option<u64>,
+) {
+    if let Some(slot) = activity_slot {
+        if let Some(trader_state) = market.get_trader_state_mut(trader) {
+            trader_state.last_activity_slot = slot;
+        }
+    }
+}
+
+#[test]
+fn test_sweep_evicted_funds() {
+    use crate::program::{
+        create_evict_inactive_trader_instruction, create_sweep_evicted_funds_instruction,
+        status::MarketStatus, test_utils::TestMarket,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let (base, quote) = (test_market.base_mint, test_market.quote_mint);
+    test_market.update_header(|header| header.evict_inactive_traders = 1);
+    let traders = (0..8)
+        .map(|i| test_market.add_trader(i + 1, 0))
+        .collect::<Vec<_>>();
+    test_market
+        .process(&create_evict_inactive_trader_instruction(
+            &market,
+            &authority,
+            &traders[0],
+        ))
+        .unwrap();
+    assert_eq!(test_market.seat(&traders[0]).unwrap().evicted_base_lots, 1);
+
+    let sweep =
+        create_sweep_evicted_funds_instruction(&market, &authority, &traders[0], &base, &quote);
+    // The authority can only sweep the evicted funds once the market is closed
+    assert!(test_market.process(&sweep).is_err());
+    test_market.update_header(|header| header.status = MarketStatus::Closed as u64);
+    // The funds can only be sent to token accounts owned by the trader
+    let mut sweep_to_other_trader = sweep.clone();
+    sweep_to_other_trader.accounts[5].pubkey = get_associated_token_address(&traders[1], &base);
+    assert!(test_market.process(&sweep_to_other_trader).is_err());
+    assert!(test_market
+        .process(&create_sweep_evicted_funds_instruction(
+            &market,
+            &Pubkey::new_unique(),
+            &traders[0],
+            &base,
+            &quote
+        ))
+        .is_err());
+    test_market.process(&sweep).unwrap();
+    assert_eq!(test_market.seat(&traders[0]).unwrap().evicted_base_lots, 0);
+}
+
+#[test]
+fn test_trader_activity() {
+    use crate::program::{
+        create_change_trader_eviction_policy_instruction, create_deposit_funds_instruction,
+        create_evict_inactive_trader_instruction, deposit::DepositParams, test_utils::TestMarket,
+    };
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let (base, quote) = (test_market.base_mint, test_market.quote_mint);
+    let traders = (0..7)
+        .map(|_| test_market.add_trader(0, 0))
+        .collect::<Vec<_>>();
+    let last_activity_slot = |test_market: &TestMarket, trader: &Pubkey| {
+        test_market
+            .market_data()
+            .get_trader_state(trader)
+            .unwrap()
+            .last_activity_slot
+    };
+
+    // Switching the policy on records the current slot for every registered trader
+    test_market.clock.slot = 5;
+    test_market
+        .process(&create_change_trader_eviction_policy_instruction(
+            &authority, &market, true,
+        ))
+        .unwrap();
+    for trader in traders.iter() {
+        assert_eq!(last_activity_slot(&test_market, trader), 5);
+    }
+
+    // Traders cannot be evicted while there are free seats
+    let evict =
+        |trader: &Pubkey| create_evict_inactive_trader_instruction(&market, &authority, trader);
+    assert!(test_market.process(&evict(&traders[0])).is_err());
+    let new_trader = test_market.add_trader(0, 0);
+
+    // Market instructions signed by a trader are recorded as activity
+    test_market.clock.slot = 10;
+    test_market
+        .process(&create_deposit_funds_instruction(
+            &market,
+            &new_trader,
+            &base,
+            &quote,
+            &DepositParams {
+                quote_lots_to_deposit: 0,
+                base_lots_to_deposit: 0,
+            },
+        ))
+        .unwrap();
+    assert_eq!(last_activity_slot(&test_market, &new_trader), 10);
+    assert!(test_market.process(&evict(&new_trader)).is_err());
+    test_market.process(&evict(&traders[0])).unwrap();
+}
@@ -0,0 +1,80 @@
+use crate::program::{
+    assert_with_msg, dispatch_market::expand_market_with_dispatch, error::PhoenixError,
+    get_market_size, ExpandMarketContext, MarketHeader, MarketSizeParams, PhoenixMarketContext,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    program::invoke, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
+};
+use std::mem::size_of;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy)]
+pub struct ExpandMarketParams {
+    /// The new size params of the market. Each capacity must be at least as large as the current one
+    /// and must be one of the capacities supported by `MarketSizeParams`
+    pub market_size_params: MarketSizeParams,
+}
+
+/// This function can only be called by the current market authority to increase the capacity of
+/// the order book and the number of seats of a market.
+///
+/// The market account can only grow by `MAX_PERMITTED_DATA_INCREASE` bytes per instruction, so
+/// this instruction may need to be sent multiple times. Each call grows the account, and the call
+/// that brings the account to its final size moves the market data into the new layout and
+/// updates the size params in the market header. All orders and seats are preserved.
+pub(crate) fn process_expand_market<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let ExpandMarketContext {
+        payer,
+        system_program,
+    } = ExpandMarketContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let ExpandMarketParams {
+        market_size_params: new_market_size_params,
+    } = ExpandMarketParams::try_from_slice(data)?;
+    let market_size_params = market_info.size_params;
+    assert_with_msg(
+        new_market_size_params.bids_size >= market_size_params.bids_size
+            && new_market_size_params.asks_size >= market_size_params.asks_size
+            && new_market_size_params.num_seats >= market_size_params.num_seats,
+        PhoenixError::InvalidMarketParameters,
+        "The market can only be expanded",
+    )?;
+
+    let target_len = size_of::<MarketHeader>() + get_market_size(&new_market_size_params)?;
+    let current_len = market_info.data_len();
+    let new_len = target_len.min(current_len + MAX_PERMITTED_DATA_INCREASE);
+    if new_len > current_len {
+        // Fund the account for rent exemption before growing it
+        let required_lamports = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(market_info.lamports());
+        if required_lamports > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, market_info.key, required_lamports),
+                &[
+                    payer.as_ref().clone(),
+                    market_info.as_ref().clone(),
+                    system_program.as_ref().clone(),
+                ],
+            )?;
+        }
+        market_info.realloc(new_len, true)?;
+    }
+    if new_len < target_len {
+        // The account needs to grow further before the market data can be moved
+        return Ok(());
+    }
+
+    {
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        expand_market_with_dispatch(&market_size_params, &new_market_size_params, market_bytes)?;
+    }
+    market_info.get_header_mut()?.market_size_params = new_market_size_params;
+    Ok(())
+}
@@ -3,7 +3,8 @@ use std::mem::size_of;
 use crate::{
     program::{
         assert_with_msg, load_with_dispatch_mut, token_utils::maybe_invoke_withdraw,
-        ChangeFeeRecipientContext, CollectFeesContext, MarketHeader, PhoenixMarketContext,
+        validation::checkers::phoenix_checkers::MarketAccountInfo, ChangeFeeRecipientContext,
+        CollectFeesContext, MarketHeader, PhoenixMarketContext,
     },
     quantities::{QuoteLots, WrapperU64},
     state::markets::MarketEvent,
@@ -33,7 +34,7 @@ pub(crate) fn process_collect_fees<'a, 'info>(
 
     let num_quote_lots_out = {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
         market.collect_fees(record_event_fn)
     };
 
@@ -57,28 +58,47 @@ pub(crate) fn process_change_fee_recipient<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let ChangeFeeRecipientContext {
         new_fee_recipient,
         previous_fee_recipient,
     } = ChangeFeeRecipientContext::load(market_context, accounts)?;
     let PhoenixMarketContext { market_info, .. } = market_context;
+    market_info.assert_not_governance_timelocked()?;
+    change_fee_recipient(
+        market_info,
+        new_fee_recipient.key,
+        previous_fee_recipient.is_some(),
+        record_event_fn,
+    )
+}
 
+/// The previous fee recipient must have signed if there are uncollected fees
+pub(crate) fn change_fee_recipient(
+    market_info: &MarketAccountInfo,
+    new_fee_recipient: &Pubkey,
+    previous_fee_recipient_signed: bool,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
     let uncollected_fees = {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        load_with_dispatch_mut(&market_info.size_params, market_bytes)?
-            .inner
-            .get_uncollected_fee_amount()
+        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        market.get_uncollected_fee_amount()
     };
 
     let mut header = market_info.get_header_mut()?;
     if uncollected_fees > QuoteLots::ZERO {
         assert_with_msg(
-            previous_fee_recipient.is_some(),
+            previous_fee_recipient_signed,
             ProgramError::MissingRequiredSignature,
             "Previous fee recipient must sign if there are uncollected fees",
         )?;
     }
-    header.fee_recipient = *new_fee_recipient.key;
+    record_event_fn(MarketEvent::FeeRecipientChange {
+        previous_fee_recipient: header.fee_recipient,
+        new_fee_recipient: *new_fee_recipient,
+    });
+    header.fee_recipient = *new_fee_recipient;
     Ok(())
 }
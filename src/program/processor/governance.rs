@@ -2,23 +2,34 @@ use std::mem::size_of;
 
 use super::{
     cancel_multiple_orders::{process_cancel_orders, CancelUpToParams},
-    withdraw::process_withdraw,
+    withdraw::{process_withdraw, record_withdraw_event},
 };
 use crate::{
     program::{
-        error::assert_with_msg, load_with_dispatch_mut, status::MarketStatus,
-        AuthorizedActionContext, ChangeMarketStatusContext, MarketHeader, PhoenixMarketContext,
+        error::{assert_with_msg, PhoenixError},
+        load_with_dispatch_mut,
+        status::MarketStatus,
+        system_utils::close_account,
+        token_utils::try_withdraw,
+        validation::checkers::{
+            phoenix_checkers::{MarketAccountInfo, SeatAccountInfo},
+            TokenAccountInfo,
+        },
+        AuthorizedActionContext, AuthorizedBatchActionContext, CloseMarketContext,
+        CloseSeatContext, ForceWithdrawFundsContext, MarketHeader, MarketRole,
+        PhoenixMarketContext,
     },
-    quantities::QuoteLots,
-    state::{markets::MarketEvent, Side},
+    quantities::{BaseLots, QuoteLots, Ticks, WrapperU64},
+    state::{markets::MarketEvent, BookFullPolicy, MatchingEngineResponse, Side},
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
-    pubkey::Pubkey, system_program,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, msg,
+    program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, sysvar::Sysvar,
 };
+use spl_associated_token_account::get_associated_token_address;
 
-/// This action can be taken by the market authority to remove the seat (on the Market account) of a
+/// This action can be taken by the market authority or seat manager to remove the seat (on the Market account) of a
 /// trader whose Seat account is no longer approved
 ///
 /// It will also withdraw all funds to token accounts owned by the trader, but it will fail
@@ -28,6 +39,7 @@ pub(crate) fn process_evict_seat<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let AuthorizedActionContext {
         trader,
@@ -42,10 +54,11 @@ pub(crate) fn process_evict_seat<'a, 'info>(
         None,
         None,
         true,
+        record_event_fn,
     )
 }
 
-/// This action can be taken by the market authority to cancel all orders of a
+/// This action can be taken by the market authority or seat manager to cancel all orders of a
 /// trader whose seat is no longer approved
 pub(crate) fn process_force_cancel_orders<'a, 'info>(
     _program_id: &Pubkey,
@@ -64,111 +77,961 @@ pub(crate) fn process_force_cancel_orders<'a, 'info>(
         trader.key,
         Some(vault_context),
         CancelUpToParams::try_from_slice(data)?,
+        None,
         record_event_fn,
     )
 }
 
-/// This function can only be called by the active successor of the current authority.
+/// Batched version of EvictSeat. Every (trader, seat, base_account, quote_account) group in the
+/// remaining accounts is processed in order, and the instruction fails if any of them cannot be
+/// evicted.
+pub(crate) fn process_batch_evict_seats<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let ctx = AuthorizedBatchActionContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    for trader_accounts in ctx
+        .trader_accounts
+        .chunks_exact(AuthorizedBatchActionContext::ACCOUNTS_PER_TRADER)
+    {
+        let (trader, vault_context) = ctx.load_trader(market_info, trader_accounts)?;
+        process_withdraw(
+            market_info,
+            trader.clone(),
+            vault_context,
+            None,
+            None,
+            true,
+            record_event_fn,
+        )?;
+    }
+    Ok(())
+}
+
+/// Batched version of ForceCancelOrders. The same cancel params are applied to every
+/// (trader, seat, base_account, quote_account) group in the remaining accounts.
+pub(crate) fn process_batch_force_cancel_orders<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let ctx = AuthorizedBatchActionContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let params = CancelUpToParams::try_from_slice(data)?;
+    for trader_accounts in ctx
+        .trader_accounts
+        .chunks_exact(AuthorizedBatchActionContext::ACCOUNTS_PER_TRADER)
+    {
+        let (trader, vault_context) = ctx.load_trader(market_info, trader_accounts)?;
+        process_cancel_orders(
+            market_info,
+            trader.key,
+            Some(vault_context),
+            params,
+            None,
+            record_event_fn,
+        )?;
+    }
+    Ok(())
+}
+
+/// This action can be taken by the market authority once the market is Closed to cancel all
+/// orders of the registered traders and withdraw their funds to their associated token accounts
+///
+/// Traders are processed in the order of the trader map, starting from the first registered
+/// trader, and each processed trader is removed from the market. The remaining trader map acts as
+/// the cursor for the next instruction, so all traders are processed in batches across multiple
+/// transactions and none can be skipped. The accounts must contain a (seat, base_account,
+/// quote_account) triple for each trader in the batch, in order, where the token accounts are the
+/// associated token accounts of the trader. If an associated token account does not exist, the
+/// funds are moved to the trader's seat instead, and can be withdrawn with WithdrawEvictedFunds.
+pub(crate) fn process_force_withdraw_funds<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let ForceWithdrawFundsContext {
+        base_vault,
+        quote_vault,
+        token_program,
+        trader_accounts,
+    } = ForceWithdrawFundsContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let (base_params, quote_params) = {
+        let header = market_info.get_header()?;
+        (header.base_params, header.quote_params)
+    };
+
+    let withdrawals = {
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut withdrawals = Vec::with_capacity(trader_accounts.len() / 3);
+        for accounts in trader_accounts.chunks_exact(3) {
+            let trader = market
+                .get_registered_traders()
+                .iter()
+                .next()
+                .map(|(trader, _)| *trader)
+                .ok_or(PhoenixError::TraderNotFound)?;
+            let seat = SeatAccountInfo::new(&accounts[0], market_info.key)?;
+            assert_with_msg(
+                seat.load_mut()?.trader == trader,
+                ProgramError::InvalidAccountData,
+                &format!("Expected the seat of the next registered trader {}", trader),
+            )?;
+            for (account, mint) in [
+                (&accounts[1], &base_params.mint_key),
+                (&accounts[2], &quote_params.mint_key),
+            ] {
+                assert_with_msg(
+                    account.key == &get_associated_token_address(&trader, mint),
+                    ProgramError::InvalidInstructionData,
+                    "Token accounts must be the associated token accounts of the trader",
+                )?;
+            }
+            market
+                .cancel_all_orders(&trader, false, record_event_fn)
+                .ok_or(PhoenixError::CancelMultipleOrdersError)?;
+            let MatchingEngineResponse {
+                num_quote_lots_out,
+                num_base_lots_out,
+                ..
+            } = market
+                .claim_funds(&trader, None, None, true)
+                .ok_or(PhoenixError::WithdrawFundsError)?;
+            assert_with_msg(
+                market.get_trader_index(&trader).is_none(),
+                PhoenixError::EvictionError,
+                "Trader was not evicted, there are still locked funds",
+            )?;
+            withdrawals.push((
+                trader,
+                seat,
+                &accounts[1],
+                &accounts[2],
+                num_base_lots_out,
+                num_quote_lots_out,
+            ));
+        }
+        withdrawals
+    };
+
+    let header = market_info.get_header()?;
+    for (trader, seat, base_account, quote_account, num_base_lots_out, num_quote_lots_out) in
+        withdrawals
+    {
+        // Funds that can't be sent to a missing associated token account are kept on the seat
+        let (base_lots_withdrawn, base_lots_evicted) =
+            match TokenAccountInfo::new_with_owner(base_account, &base_params.mint_key, &trader) {
+                Ok(_) => (num_base_lots_out, BaseLots::ZERO),
+                Err(_) => (BaseLots::ZERO, num_base_lots_out),
+            };
+        let (quote_lots_withdrawn, quote_lots_evicted) = match TokenAccountInfo::new_with_owner(
+            quote_account,
+            &quote_params.mint_key,
+            &trader,
+        ) {
+            Ok(_) => (num_quote_lots_out, QuoteLots::ZERO),
+            Err(_) => (QuoteLots::ZERO, num_quote_lots_out),
+        };
+        if base_lots_evicted > BaseLots::ZERO || quote_lots_evicted > QuoteLots::ZERO {
+            let mut seat = seat.load_mut()?;
+            seat.evicted_base_lots += base_lots_evicted.as_u64();
+            seat.evicted_quote_lots += quote_lots_evicted.as_u64();
+            record_event_fn(MarketEvent::TraderEviction {
+                trader,
+                base_lots_evicted,
+                quote_lots_evicted,
+            });
+        }
+        record_withdraw_event(
+            market_info,
+            &trader,
+            base_lots_withdrawn,
+            quote_lots_withdrawn,
+            record_event_fn,
+        )?;
+        try_withdraw(
+            market_info.key,
+            &base_params,
+            &quote_params,
+            token_program.as_ref(),
+            quote_account,
+            quote_vault.clone(),
+            base_account,
+            base_vault.clone(),
+            quote_lots_withdrawn * header.get_quote_lot_size(),
+            base_lots_withdrawn * header.get_base_lot_size(),
+        )?;
+    }
+    Ok(())
+}
+
+/// This function can only be called by the active successor of the current authority, before
+/// the nomination expires. The nomination is consumed once the authority is claimed.
 pub(crate) fn process_claim_authority<'a, 'info>(
     _program_id: &Pubkey,
     market_context: &PhoenixMarketContext<'a, 'info>,
     _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let PhoenixMarketContext {
         market_info,
         signer: successor,
     } = market_context;
     market_info.assert_valid_successor(successor.key)?;
-    market_info.get_header_mut()?.authority = *successor.key;
+    let mut header = market_info.get_header_mut()?;
+    if header.successor_expiry_unix_timestamp != 0 {
+        assert_with_msg(
+            Clock::get()?.unix_timestamp <= header.successor_expiry_unix_timestamp,
+            PhoenixError::InvalidMarketAuthority,
+            "Successor nomination has expired",
+        )?;
+    }
+    record_event_fn(MarketEvent::AuthorityClaim {
+        previous_authority: header.authority,
+        new_authority: *successor.key,
+    });
+    header.authority = *successor.key;
+    header.successor = Pubkey::default();
+    header.successor_expiry_unix_timestamp = 0;
     Ok(())
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NameSuccessorParams {
+    pub successor: Pubkey,
+    /// Unix timestamp after which the successor can no longer claim the authority
+    pub expiry_unix_timestamp: Option<i64>,
+}
+
+/// Decodes the name successor params, allowing the optional expiry to be omitted.
+pub fn decode_name_successor_params(bytes: &[u8]) -> Result<NameSuccessorParams, ProgramError> {
+    NameSuccessorParams::try_from_slice(bytes).or_else(|_| {
+        // Params encoded before `expiry_unix_timestamp` existed only contain the successor
+        NameSuccessorParams::try_from_slice(&[bytes, &[0_u8 /* expiry_unix_timestamp */]].concat())
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    })
+}
+
 /// The authority can be changed to a successor, but the successor must explicitly claim the
 /// authority from the previous market authority
 pub(crate) fn process_name_successor<'a, 'info>(
     _program_id: &Pubkey,
     market_context: &PhoenixMarketContext<'a, 'info>,
     data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    market_info.assert_not_governance_timelocked()?;
+    let NameSuccessorParams {
+        successor,
+        expiry_unix_timestamp,
+    } = decode_name_successor_params(data)?;
+    name_successor(
+        market_info,
+        successor,
+        expiry_unix_timestamp,
+        record_event_fn,
+    )
+}
+
+pub(crate) fn name_successor(
+    market_info: &MarketAccountInfo,
+    successor: Pubkey,
+    expiry_unix_timestamp: Option<i64>,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let expiry_unix_timestamp = expiry_unix_timestamp.unwrap_or(0);
+    let mut header = market_info.get_header_mut()?;
+    record_event_fn(MarketEvent::SuccessorNomination {
+        previous_successor: header.successor,
+        successor,
+        expiry_unix_timestamp,
+    });
+    header.successor = successor;
+    header.successor_expiry_unix_timestamp = expiry_unix_timestamp;
+    Ok(())
+}
+
+/// This function can only be called by the current market authority to withdraw a pending
+/// successor nomination
+pub(crate) fn process_cancel_successor<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let PhoenixMarketContext {
         market_info,
         signer: authority,
     } = market_context;
     market_info.assert_valid_authority(authority.key)?;
-    let successor = Pubkey::try_from_slice(data)?;
-    market_info.get_header_mut()?.successor = successor;
+    let mut header = market_info.get_header_mut()?;
+    assert_with_msg(
+        header.successor != Pubkey::default(),
+        PhoenixError::InvalidMarketAuthority,
+        "No successor has been named",
+    )?;
+    record_event_fn(MarketEvent::SuccessorCancellation {
+        successor: header.successor,
+    });
+    header.successor = Pubkey::default();
+    header.successor_expiry_unix_timestamp = 0;
     Ok(())
 }
 
 /// This function can only be called by the current market authority to
-/// modify the current market status (based on valid transitions)
+/// modify the current market status (based on valid transitions). The pause guardian
+/// can also move the market to PostOnly or Paused.
 pub(crate) fn process_change_market_status<'a, 'info>(
     _program_id: &Pubkey,
     market_context: &PhoenixMarketContext<'a, 'info>,
-    accounts: &'a [AccountInfo<'info>],
+    _accounts: &'a [AccountInfo<'info>],
     data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
-    let ChangeMarketStatusContext {
-        receiver: receiver_option,
-    } = ChangeMarketStatusContext::load(accounts)?;
     let PhoenixMarketContext {
         market_info,
         signer: authority,
     } = market_context;
-    market_info.assert_valid_authority(authority.key)?;
     let next_state = MarketStatus::try_from_slice(data)?;
-    let status = market_info.get_header()?.status;
+    assert_can_change_market_status(market_info, authority.key, &next_state)?;
+    // Closing the market must go through a governance proposal while the market is timelocked
+    if next_state == MarketStatus::Closed {
+        market_info.assert_not_governance_timelocked()?;
+    }
+    // A Tombstoned market is no longer closed here, because its vaults would be left open. The
+    // market account stays open until CloseMarket closes it together with the vaults.
+    change_market_status(market_info, next_state, record_event_fn)
+}
+
+/// The market authority can make any status transition, while the pause guardian can only
+/// restrict trading on a market that is Active or PostOnly
+fn assert_can_change_market_status(
+    market_info: &MarketAccountInfo,
+    signer: &Pubkey,
+    next_state: &MarketStatus,
+) -> ProgramResult {
+    let header = market_info.get_header()?;
+    if &header.authority == signer {
+        return Ok(());
+    }
+    market_info.assert_valid_role(signer, MarketRole::PauseGuardian)?;
+    let status = MarketStatus::from(header.status);
+    assert_with_msg(
+        status.pause_guardian_can_transition(next_state),
+        PhoenixError::InvalidMarketAuthority,
+        &format!(
+            "The pause guardian cannot change the market status from {} to {}",
+            status, next_state
+        ),
+    )
+}
+
+pub(crate) fn change_market_status(
+    market_info: &MarketAccountInfo,
+    next_state: MarketStatus,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let status = MarketStatus::from(market_info.get_header()?.status);
     // Ensure that the state transition is allowed
-    MarketStatus::from(status).assert_valid_state_transition(&next_state)?;
-    // Modify the state of the market
-    match next_state {
-        // When the market is tombstoned, its data is fully removed.
-        MarketStatus::Tombstoned => {
-            // The book must be empty
-            {
-                let market_bytes =
-                    &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-                let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
-                assert_with_msg(
-                    market.get_book(Side::Bid).is_empty() && market.get_book(Side::Ask).is_empty(),
-                    ProgramError::InvalidAccountData,
-                    &format!(
-                        "Invalid market status, must have no open orders, found {} bids and {} asks",
-                        market.get_book(Side::Bid).len(),
-                        market.get_book(Side::Ask).len()
-                    ),
-                )?;
-                assert_with_msg(
-                    market.get_uncollected_fee_amount() == QuoteLots::ZERO,
-                    ProgramError::InvalidAccountData,
-                    "Invalid market status, must have no uncollected fees",
-                )?;
-                // All traders should be removed (all funds withdrawn)
-                assert_with_msg(
-                    market.get_registered_traders().is_empty(),
-                    ProgramError::InvalidAccountData,
-                    &format!(
-                        "Invalid market status, must have no traders, found {}",
-                        market.get_registered_traders().len()
-                    ),
-                )?;
+    status.assert_valid_state_transition(&next_state)?;
+    // A market can only be tombstoned once it is empty. Its accounts can then be closed with the
+    // CloseMarket instruction, unless ChangeMarketStatus was passed a receiver.
+    if next_state == MarketStatus::Tombstoned {
+        // The book must be empty
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        assert_with_msg(
+            market.get_book(Side::Bid).is_empty() && market.get_book(Side::Ask).is_empty(),
+            ProgramError::InvalidAccountData,
+            &format!(
+                "Invalid market status, must have no open orders, found {} bids and {} asks",
+                market.get_book(Side::Bid).len(),
+                market.get_book(Side::Ask).len()
+            ),
+        )?;
+        assert_with_msg(
+            market.get_uncollected_fee_amount() == QuoteLots::ZERO,
+            ProgramError::InvalidAccountData,
+            "Invalid market status, must have no uncollected fees",
+        )?;
+        // All traders should be removed (all funds withdrawn)
+        assert_with_msg(
+            market.get_registered_traders().is_empty(),
+            ProgramError::InvalidAccountData,
+            &format!(
+                "Invalid market status, must have no traders, found {}",
+                market.get_registered_traders().len()
+            ),
+        )?;
+    }
+    record_event_fn(MarketEvent::MarketStatusChange {
+        previous_status: status,
+        new_status: next_state,
+    });
+    let mut header = market_info.get_header_mut()?;
+    // A scheduled transition no longer applies once the status is changed directly
+    if next_state != status {
+        header.clear_scheduled_status();
+    }
+    header.status = next_state as u64;
+    Ok(())
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduledMarketStatusParams {
+    pub status: MarketStatus,
+    pub unix_timestamp: i64,
+}
+
+/// This function can be called by the same signers as ChangeMarketStatus to schedule a status
+/// transition, which is applied by the first instruction on the market once the timestamp has
+/// passed. Passing `None` cancels the scheduled transition. The pause guardian cannot replace a
+/// transition scheduled by the market authority.
+pub(crate) fn process_schedule_market_status<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    let ScheduledMarketStatusParams {
+        status,
+        unix_timestamp,
+    } = match Option::<ScheduledMarketStatusParams>::try_from_slice(data)? {
+        Some(params) => params,
+        None => {
+            market_info.assert_valid_authority(authority.key)?;
+            market_info.get_header_mut()?.clear_scheduled_status();
+            return Ok(());
+        }
+    };
+    assert_can_change_market_status(market_info, authority.key, &status)?;
+    let is_authority = {
+        let header = market_info.get_header()?;
+        let is_authority = header.authority == *authority.key;
+        assert_with_msg(
+            is_authority || !header.has_scheduled_status() || header.scheduled_by_authority == 0,
+            PhoenixError::InvalidMarketAuthority,
+            "The pause guardian cannot replace a transition scheduled by the market authority",
+        )?;
+        is_authority
+    };
+    if status == MarketStatus::Closed {
+        market_info.assert_not_governance_timelocked()?;
+    }
+    // Tombstoning requires the market to be empty, so it cannot be scheduled
+    assert_with_msg(
+        status != MarketStatus::Tombstoned,
+        ProgramError::InvalidInstructionData,
+        "Tombstoned transitions cannot be scheduled",
+    )?;
+    let mut header = market_info.get_header_mut()?;
+    MarketStatus::from(header.status).assert_valid_state_transition(&status)?;
+    header.scheduled_status = status as u64;
+    header.scheduled_status_unix_timestamp = unix_timestamp;
+    header.scheduled_by_authority = is_authority as u64;
+    Ok(())
+}
+
+/// Applies the scheduled status transition of the market if its timestamp has passed. A
+/// transition that is no longer valid from the current status is dropped, as is a Closed
+/// transition once the market is governance timelocked.
+pub(crate) fn apply_scheduled_market_status(
+    market_info: &MarketAccountInfo,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    if !market_info.get_header()?.has_scheduled_status() {
+        return Ok(());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let mut header = market_info.get_header_mut()?;
+    if now < header.scheduled_status_unix_timestamp {
+        return Ok(());
+    }
+    let previous_status = MarketStatus::from(header.status);
+    let new_status = MarketStatus::from(header.scheduled_status);
+    header.clear_scheduled_status();
+    if !previous_status.valid_state_transition(&new_status) {
+        msg!(
+            "Dropping scheduled transition from {} to {}",
+            previous_status,
+            new_status
+        );
+        return Ok(());
+    }
+    if new_status == MarketStatus::Closed && header.is_governance_timelocked() {
+        msg!("Dropping scheduled transition to Closed, the market is governance timelocked");
+        return Ok(());
+    }
+    header.status = new_status as u64;
+    record_event_fn(MarketEvent::MarketStatusChange {
+        previous_status,
+        new_status,
+    });
+    Ok(())
+}
+
+/// This function can only be called by the current market authority once the market is
+/// Tombstoned. It closes both vaults and the market account and sends their lamports to the
+/// receiver. The vaults must be empty.
+pub(crate) fn process_close_market<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+) -> ProgramResult {
+    let CloseMarketContext {
+        receiver,
+        base_vault,
+        quote_vault,
+        token_program,
+    } = CloseMarketContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let (base_params, quote_params) = {
+        let header = market_info.get_header()?;
+        (header.base_params, header.quote_params)
+    };
+    for (vault, params) in [(base_vault, base_params), (quote_vault, quote_params)] {
+        assert_with_msg(
+            vault.amount()? == 0,
+            ProgramError::InvalidAccountData,
+            "Vault must be empty to close the market",
+        )?;
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                token_program.key,
+                vault.key,
+                receiver.key,
+                vault.key,
+                &[],
+            )?,
+            &[
+                token_program.as_ref().clone(),
+                vault.as_ref().clone(),
+                receiver.clone(),
+            ],
+            &[&[
+                b"vault",
+                market_info.key.as_ref(),
+                params.mint_key.as_ref(),
+                &[params.vault_bump as u8],
+            ]],
+        )?;
+    }
+    close_account(market_info, receiver)
+}
+
+/// This function can only be called by the current market authority to close a retired seat
+/// of a trader that is no longer registered on the market. The seat lamports are sent to the
+/// receiver.
+pub(crate) fn process_close_seat<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let CloseSeatContext { seat, receiver } = CloseSeatContext::load(market_context, accounts)?;
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    {
+        let (trader, evicted_base_lots, evicted_quote_lots) = {
+            let seat = seat.load_mut()?;
+            (seat.trader, seat.evicted_base_lots, seat.evicted_quote_lots)
+        };
+        assert_with_msg(
+            evicted_base_lots == 0 && evicted_quote_lots == 0,
+            PhoenixError::InvalidSeatStatus,
+            "Evicted funds must be withdrawn before the seat is closed",
+        )?;
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        assert_with_msg(
+            market.get_trader_index(&trader).is_none(),
+            PhoenixError::InvalidSeatStatus,
+            "Trader must be removed from the market before the seat is closed",
+        )?;
+        record_event_fn(MarketEvent::SeatClosure { trader });
+    }
+    close_account(seat.as_ref(), receiver)
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeMarketRoleParams {
+    pub role: MarketRole,
+    /// The key that is assigned the role. The role is revoked if this is the default pubkey.
+    pub key: Pubkey,
+}
+
+/// This function can only be called by the current market authority to assign or
+/// revoke one of the delegated market roles
+pub(crate) fn process_change_market_role<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    let ChangeMarketRoleParams { role, key } = ChangeMarketRoleParams::try_from_slice(data)?;
+    *market_info.get_header_mut()?.get_role_mut(role) = key;
+    Ok(())
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangeBookFullPolicyParams {
+    pub book_full_policy: BookFullPolicy,
+    /// A resting order can only be evicted by an order that improves on its price by at least this many ticks
+    pub min_eviction_improvement_in_ticks: u64,
+}
+
+/// This function can only be called by the current market authority to change how the
+/// market makes room for new orders when a side of the book is full
+pub(crate) fn process_change_book_full_policy<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    let ChangeBookFullPolicyParams {
+        book_full_policy,
+        min_eviction_improvement_in_ticks,
+    } = ChangeBookFullPolicyParams::try_from_slice(data)?;
+    let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+    load_with_dispatch_mut(&market_info.size_params, market_bytes)?
+        .inner
+        .set_book_full_policy(
+            book_full_policy,
+            Ticks::new(min_eviction_improvement_in_ticks),
+        );
+    Ok(())
+}
+
+#[test]
+fn test_decode_name_successor_params() {
+    let successor = Pubkey::new_unique();
+    let params = decode_name_successor_params(&successor.try_to_vec().unwrap()).unwrap();
+    assert_eq!(params.successor, successor);
+    assert_eq!(params.expiry_unix_timestamp, None);
+
+    let expected = NameSuccessorParams {
+        successor,
+        expiry_unix_timestamp: Some(1_700_000_000),
+    };
+    let params = decode_name_successor_params(&expected.try_to_vec().unwrap()).unwrap();
+    assert_eq!(params, expected);
+}
+
+#[test]
+fn test_force_withdraw_funds() {
+    use crate::program::{create_force_withdraw_funds_instruction, test_utils::TestMarket};
+    use crate::state::OrderPacket;
+    use spl_associated_token_account::get_associated_token_address;
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let (base, quote) = (test_market.base_mint, test_market.quote_mint);
+    let mut traders = [(10, 0), (1, 20), (5, 5), (1, 1)]
+        .map(|(base_lots, quote_lots)| test_market.add_trader(base_lots, quote_lots));
+    // The first trader has an open order, which locks their funds
+    test_market.with_market(|market| {
+        market
+            .place_order(
+                &traders[0],
+                OrderPacket::new_post_only_default(Side::Ask, 100, 4),
+                &mut |_| {},
+                &mut || (0, 0),
+            )
+            .unwrap();
+    });
+    // Traders are processed in the order of the trader map
+    traders.sort();
+    let base_lots = {
+        let market = test_market.market_data();
+        let trader_state = market.get_trader_state(&traders[2]).unwrap();
+        trader_state.base_lots_free + trader_state.base_lots_locked
+    };
+    let withdraw_all = |traders: &[Pubkey]| {
+        create_force_withdraw_funds_instruction(&market, &authority, traders, &base, &quote)
+    };
+
+    assert!(test_market.process(&withdraw_all(&traders[..2])).is_err());
+    test_market.update_header(|header| header.status = MarketStatus::Closed as u64);
+    // Traders can't be skipped
+    assert!(test_market.process(&withdraw_all(&traders[1..3])).is_err());
+    test_market.process(&withdraw_all(&traders[..2])).unwrap();
+    {
+        let market = test_market.market_data();
+        assert_eq!(market.get_registered_traders().len(), 2);
+        assert_eq!(market.get_book(Side::Ask).len(), 0);
+    }
+
+    // The token accounts must be the associated token accounts of the trader
+    let custom_base_account = Pubkey::new_unique();
+    test_market.create_token_account(&custom_base_account, &base, &traders[2], 0);
+    let mut instruction = withdraw_all(&traders[2..3]);
+    instruction.accounts[8].pubkey = custom_base_account;
+    assert!(test_market.process(&instruction).is_err());
+
+    // Funds for a missing associated token account are moved to the seat
+    test_market
+        .accounts
+        .remove(&get_associated_token_address(&traders[2], &base));
+    test_market.process(&withdraw_all(&traders[2..3])).unwrap();
+    let seat = test_market.seat(&traders[2]).unwrap();
+    assert_eq!(seat.evicted_base_lots, base_lots.as_u64());
+    assert_eq!(seat.evicted_quote_lots, 0);
+
+    test_market.process(&withdraw_all(&traders[3..])).unwrap();
+    assert_eq!(test_market.market_data().get_registered_traders().len(), 0);
+    // There are no traders left to process
+    assert!(test_market.process(&withdraw_all(&traders[3..])).is_err());
+}
+
+#[test]
+fn test_close_market() {
+    use crate::program::{
+        create_change_market_status_instruction, create_close_market_instruction,
+        test_utils::TestMarket,
+    };
+    use solana_program::instruction::AccountMeta;
+
+    let receiver = Pubkey::new_unique();
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let market_lamports = test_market.accounts[&market].lamports;
+    let base_vault = test_market.header().base_params.vault_key;
+    test_market.update_header(|header| header.status = MarketStatus::Closed as u64);
+    // Tombstoning the market leaves the market account open for CloseMarket, even if a receiver
+    // is passed as it was before CloseMarket existed
+    let mut tombstone =
+        create_change_market_status_instruction(&authority, &market, MarketStatus::Tombstoned);
+    tombstone.accounts.push(AccountMeta::new(receiver, false));
+    test_market.process(&tombstone).unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Tombstoned as u64);
+    assert_eq!(test_market.accounts[&market].lamports, market_lamports);
+    assert_eq!(test_market.accounts[&receiver].lamports, 0);
+
+    let close_market = create_close_market_instruction(
+        &market,
+        &authority,
+        &receiver,
+        &test_market.base_mint,
+        &test_market.quote_mint,
+    );
+    // The vaults must be empty
+    test_market.add_token_balance(&base_vault, 1);
+    assert!(test_market.process(&close_market).is_err());
+    test_market.add_token_balance(&base_vault, -1);
+    test_market.process(&close_market).unwrap();
+    assert!(test_market.accounts[&market].data.is_empty());
+    assert_eq!(test_market.accounts[&market].lamports, 0);
+    assert_eq!(test_market.accounts[&receiver].lamports, market_lamports);
+}
+
+#[test]
+fn test_close_seat() {
+    use crate::program::{
+        create_close_seat_instruction, get_seat_address, status::SeatApprovalStatus,
+        test_utils::TestMarket,
+    };
+
+    let receiver = Pubkey::new_unique();
+    let mut test_market = TestMarket::new();
+    let trader = test_market.add_trader(0, 0);
+    let seat = get_seat_address(&test_market.market, &trader).0;
+    let seat_lamports = test_market.accounts[&seat].lamports;
+    let close_seat = create_close_seat_instruction(
+        &test_market.market,
+        &test_market.authority,
+        &trader,
+        &receiver,
+    );
+
+    // The seat must be retired
+    assert!(test_market.process(&close_seat).is_err());
+    test_market.create_seat(&trader, SeatApprovalStatus::Retired);
+    // The trader must no longer be registered
+    assert!(test_market.process(&close_seat).is_err());
+    test_market.with_market(|market| {
+        market.get_registered_traders_mut().remove(&trader);
+    });
+    test_market.process(&close_seat).unwrap();
+    assert!(test_market.accounts[&seat].data.is_empty());
+    assert_eq!(test_market.accounts[&receiver].lamports, seat_lamports);
+}
+
+#[test]
+fn test_pause_guardian_status_changes() {
+    use crate::program::{create_change_market_status_instruction, test_utils::TestMarket};
+
+    let statuses = [
+        MarketStatus::Active,
+        MarketStatus::PostOnly,
+        MarketStatus::Paused,
+        MarketStatus::Closed,
+    ];
+    let pause_guardian = Pubkey::new_unique();
+    for status in statuses {
+        for next_status in statuses {
+            if !status.valid_state_transition(&next_status) {
+                continue;
             }
-            // The market lamports are either transferred to the receiver or to the authority
-            let receiver = match receiver_option {
-                Some(r) => r,
-                None => authority.as_ref(),
+            let mut test_market = TestMarket::new();
+            test_market.update_header(|header| {
+                header.status = status as u64;
+                header.pause_guardian = pause_guardian;
+            });
+            let market = test_market.market;
+            let change_status = |signer: &Pubkey| {
+                create_change_market_status_instruction(signer, &market, next_status)
             };
-            let destination_starting_lamports = receiver.lamports();
-            **receiver.lamports.borrow_mut() =
-                destination_starting_lamports + market_info.lamports();
-            **market_info.lamports.borrow_mut() = 0;
-            market_info.assign(&system_program::id());
-            market_info.realloc(0, false)?;
-        }
-        // In all other cases, we simply update the status of the market
-        _ => {
-            market_info.get_header_mut()?.status = next_state as u64;
+            let (by_guardian, by_stranger) = (
+                change_status(&pause_guardian),
+                change_status(&Pubkey::new_unique()),
+            );
+            assert!(test_market.process(&by_stranger).is_err());
+            let allowed = matches!(
+                (status, next_status),
+                (MarketStatus::Active, MarketStatus::PostOnly)
+                    | (MarketStatus::Active, MarketStatus::Paused)
+                    | (MarketStatus::PostOnly, MarketStatus::Paused)
+            );
+            assert_eq!(
+                test_market.process(&by_guardian).is_ok(),
+                allowed,
+                "{} -> {}",
+                status,
+                next_status
+            );
+            let expected_status = if allowed { next_status } else { status };
+            assert_eq!(test_market.header().status, expected_status as u64);
+            // The market authority can make every valid transition
+            let authority = test_market.authority;
+            test_market.update_header(|header| header.status = status as u64);
+            test_market.process(&change_status(&authority)).unwrap();
+            assert_eq!(test_market.header().status, next_status as u64);
         }
     }
-    Ok(())
+}
+
+#[test]
+fn test_schedule_market_status() {
+    use crate::program::{
+        create_change_market_role_instruction, create_change_market_status_instruction,
+        create_schedule_market_status_instruction, test_utils::TestMarket,
+    };
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let pause_guardian = Pubkey::new_unique();
+    test_market.update_header(|header| header.pause_guardian = pause_guardian);
+    let schedule = |signer: &Pubkey, status: MarketStatus, unix_timestamp: i64| {
+        create_schedule_market_status_instruction(
+            signer,
+            &market,
+            Some(ScheduledMarketStatusParams {
+                status,
+                unix_timestamp,
+            }),
+        )
+    };
+    let cancel = |signer: &Pubkey| create_schedule_market_status_instruction(signer, &market, None);
+    // Any instruction on the market applies a scheduled transition that is due
+    let touch_market = create_change_market_role_instruction(
+        &authority,
+        &market,
+        MarketRole::PauseGuardian,
+        &pause_guardian,
+    );
+
+    // The pause guardian can only schedule transitions that restrict trading
+    assert!(test_market
+        .process(&schedule(&pause_guardian, MarketStatus::Closed, 100))
+        .is_err());
+    assert!(test_market
+        .process(&schedule(&Pubkey::new_unique(), MarketStatus::Paused, 100))
+        .is_err());
+    test_market
+        .process(&schedule(&pause_guardian, MarketStatus::PostOnly, 100))
+        .unwrap();
+    // The pause guardian cannot replace or cancel a transition scheduled by the authority
+    test_market
+        .process(&schedule(&authority, MarketStatus::Paused, 100))
+        .unwrap();
+    assert_eq!(test_market.header().scheduled_by_authority, 1);
+    assert!(test_market
+        .process(&schedule(&pause_guardian, MarketStatus::PostOnly, 100))
+        .is_err());
+    assert!(test_market.process(&cancel(&pause_guardian)).is_err());
+    test_market.process(&cancel(&authority)).unwrap();
+    assert!(!test_market.header().has_scheduled_status());
+
+    // The transition is applied once its timestamp has passed
+    test_market
+        .process(&schedule(&pause_guardian, MarketStatus::Paused, 100))
+        .unwrap();
+    test_market.clock.unix_timestamp = 99;
+    test_market.process(&touch_market).unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Active as u64);
+    test_market.clock.unix_timestamp = 100;
+    test_market.process(&touch_market).unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Paused as u64);
+    assert!(!test_market.header().has_scheduled_status());
+
+    // Changing the status directly clears the scheduled transition
+    test_market
+        .process(&schedule(&authority, MarketStatus::Closed, 200))
+        .unwrap();
+    test_market
+        .process(&create_change_market_status_instruction(
+            &authority,
+            &market,
+            MarketStatus::Active,
+        ))
+        .unwrap();
+    assert!(!test_market.header().has_scheduled_status());
+
+    // A transition that is no longer valid is dropped
+    test_market
+        .process(&schedule(&authority, MarketStatus::Active, 200))
+        .unwrap();
+    test_market.update_header(|header| header.status = MarketStatus::Closed as u64);
+    test_market.clock.unix_timestamp = 200;
+    test_market.process(&touch_market).unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Closed as u64);
+    assert!(!test_market.header().has_scheduled_status());
+
+    // A scheduled Closed transition is dropped once the market is governance timelocked
+    test_market.update_header(|header| header.status = MarketStatus::Paused as u64);
+    test_market
+        .process(&schedule(&authority, MarketStatus::Closed, 300))
+        .unwrap();
+    test_market.update_header(|header| header.governance_delay_in_slots = 1);
+    test_market.clock.unix_timestamp = 300;
+    test_market.process(&touch_market).unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Paused as u64);
+    assert!(!test_market.header().has_scheduled_status());
 }
@@ -0,0 +1,272 @@
+use crate::program::{
+    error::{assert_with_msg, PhoenixError},
+    get_governance_proposal_address, load_with_dispatch_mut,
+    system_utils::{close_account, create_account},
+    GovernanceAction, GovernanceProposal, GovernanceProposalContext, MarketHeader,
+    PhoenixMarketContext, ProposeGovernanceActionContext,
+};
+use crate::state::markets::MarketEvent;
+use borsh::BorshDeserialize;
+use sokoban::node_allocator::ZeroCopy;
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
+};
+use std::mem::size_of;
+
+use super::{
+    fees::change_fee_recipient,
+    governance::{change_market_status, name_successor},
+};
+
+/// This function can only be called by the current market authority to propose a governance
+/// action. The proposal is stored in its own account and can be executed once the governance delay
+/// of the market has passed. The delay in effect at execution time applies, so raising the delay
+/// also holds back proposals that are already pending.
+pub(crate) fn process_propose_governance_action<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let ProposeGovernanceActionContext {
+        proposal,
+        system_program,
+    } = ProposeGovernanceActionContext::load(market_context, accounts)?;
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    let action = GovernanceAction::try_from_slice(data)?;
+    if let GovernanceAction::ChangeTakerFee { taker_fee_bps } = action {
+        assert_with_msg(
+            taker_fee_bps <= 10000,
+            ProgramError::InvalidInstructionData,
+            "Taker fee must be less than or equal to 10000 basis points (100%)",
+        )?;
+    }
+
+    let proposal_id = {
+        let mut header = market_info.get_header_mut()?;
+        let proposal_id = header.governance_proposal_count;
+        header.governance_proposal_count += 1;
+        proposal_id
+    };
+    let (proposal_address, bump) = get_governance_proposal_address(market_info.key, proposal_id);
+    assert_with_msg(
+        &proposal_address == proposal.key,
+        ProgramError::InvalidAccountData,
+        "Invalid proposal address",
+    )?;
+    let seeds = vec![
+        b"proposal".to_vec(),
+        market_info.key.as_ref().to_vec(),
+        proposal_id.to_le_bytes().to_vec(),
+        vec![bump],
+    ];
+    create_account(
+        authority.as_ref(),
+        proposal.as_ref(),
+        system_program.as_ref(),
+        &crate::id(),
+        &Rent::get()?,
+        size_of::<GovernanceProposal>() as u64,
+        seeds,
+    )?;
+
+    let clock = Clock::get()?;
+    let mut proposal_bytes = proposal.try_borrow_mut_data()?;
+    *GovernanceProposal::load_mut_bytes(&mut proposal_bytes)
+        .ok_or(ProgramError::InvalidAccountData)? = GovernanceProposal::new_init(
+        *market_info.key,
+        proposal_id,
+        clock.slot,
+        clock.unix_timestamp,
+        &action,
+    )?;
+    Ok(())
+}
+
+/// This function can only be called by the current market authority to execute a proposed
+/// governance action once its delay has passed. The proposal account is closed and its lamports are
+/// returned to the authority.
+pub(crate) fn process_execute_governance_action<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let GovernanceProposalContext {
+        proposal,
+        previous_fee_recipient,
+    } = GovernanceProposalContext::load(market_context, accounts)?;
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    let (action, (executable_slot, executable_unix_timestamp)) = {
+        let proposal = proposal.load()?;
+        (
+            proposal.get_action()?,
+            proposal.get_executable_slot_and_unix_timestamp(&*market_info.get_header()?),
+        )
+    };
+    let clock = Clock::get()?;
+    assert_with_msg(
+        clock.slot >= executable_slot && clock.unix_timestamp >= executable_unix_timestamp,
+        PhoenixError::ProposalNotExecutable,
+        &format!(
+            "Proposal can be executed at slot {} and unix timestamp {}",
+            executable_slot, executable_unix_timestamp
+        ),
+    )?;
+
+    match action {
+        GovernanceAction::ChangeTakerFee { taker_fee_bps } => {
+            let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+            let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+            market.set_fee(taker_fee_bps as u64);
+        }
+        GovernanceAction::ChangeFeeRecipient { fee_recipient } => {
+            change_fee_recipient(
+                market_info,
+                &fee_recipient,
+                previous_fee_recipient.is_some(),
+                record_event_fn,
+            )?;
+        }
+        GovernanceAction::NameSuccessor {
+            successor,
+            expiry_unix_timestamp,
+        } => {
+            name_successor(
+                market_info,
+                successor,
+                expiry_unix_timestamp,
+                record_event_fn,
+            )?;
+        }
+        GovernanceAction::ChangeMarketStatus { status } => {
+            change_market_status(market_info, status, record_event_fn)?;
+        }
+        GovernanceAction::ChangeGovernanceDelay {
+            delay_in_slots,
+            delay_in_seconds,
+        } => {
+            let mut header = market_info.get_header_mut()?;
+            header.governance_delay_in_slots = delay_in_slots;
+            header.governance_delay_in_seconds = delay_in_seconds;
+        }
+    }
+    close_account(proposal.as_ref(), authority.as_ref())
+}
+
+/// This function can only be called by the current market authority to cancel a proposed
+/// governance action. The proposal account is closed and its lamports are returned to the authority.
+pub(crate) fn process_cancel_governance_action<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    _data: &[u8],
+) -> ProgramResult {
+    let GovernanceProposalContext { proposal, .. } =
+        GovernanceProposalContext::load(market_context, accounts)?;
+    let PhoenixMarketContext {
+        signer: authority, ..
+    } = market_context;
+    close_account(proposal.as_ref(), authority.as_ref())
+}
+
+#[test]
+fn test_execute_governance_action_with_current_delay() {
+    use crate::program::{
+        create_execute_governance_action_instruction,
+        test_utils::{TestAccount, TestMarket},
+    };
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    // A proposal made while the market had no delay
+    let proposal = GovernanceProposal::new_init(
+        market,
+        0,
+        0,
+        0,
+        &GovernanceAction::ChangeTakerFee { taker_fee_bps: 5 },
+    )
+    .unwrap();
+    test_market.accounts.insert(
+        get_governance_proposal_address(&market, 0).0,
+        TestAccount {
+            owner: crate::id(),
+            lamports: Rent::default().minimum_balance(size_of::<GovernanceProposal>()),
+            data: bytemuck::bytes_of(&proposal).to_vec(),
+        },
+    );
+    test_market.update_header(|header| {
+        header.governance_proposal_count = 1;
+        header.governance_delay_in_slots = 100;
+        header.governance_delay_in_seconds = 60;
+    });
+    let execute = create_execute_governance_action_instruction(&authority, &market, 0, None);
+
+    // The delay that was set after the proposal was made still applies to it
+    test_market.clock.slot = 99;
+    test_market.clock.unix_timestamp = 60;
+    assert!(test_market.process(&execute).is_err());
+    test_market.clock.slot = 100;
+    test_market.clock.unix_timestamp = 59;
+    assert!(test_market.process(&execute).is_err());
+    test_market.clock.unix_timestamp = 60;
+    test_market.process(&execute).unwrap();
+    assert_eq!(test_market.market_data().get_taker_fee_bps(), 5);
+}
+
+#[test]
+fn test_execute_fee_recipient_change() {
+    use crate::program::{
+        create_execute_governance_action_instruction,
+        test_utils::{TestAccount, TestMarket},
+    };
+
+    let mut test_market = TestMarket::new();
+    let (market, authority) = (test_market.market, test_market.authority);
+    let fee_recipient = Pubkey::new_unique();
+    let proposal = GovernanceProposal::new_init(
+        market,
+        0,
+        0,
+        0,
+        &GovernanceAction::ChangeFeeRecipient { fee_recipient },
+    )
+    .unwrap();
+    test_market.accounts.insert(
+        get_governance_proposal_address(&market, 0).0,
+        TestAccount {
+            owner: crate::id(),
+            lamports: Rent::default().minimum_balance(size_of::<GovernanceProposal>()),
+            data: bytemuck::bytes_of(&proposal).to_vec(),
+        },
+    );
+    test_market.update_header(|header| header.governance_proposal_count = 1);
+
+    // A previous fee recipient that is passed must be the current fee recipient
+    assert!(test_market
+        .process(&create_execute_governance_action_instruction(
+            &authority,
+            &market,
+            0,
+            Some(&Pubkey::new_unique()),
+        ))
+        .is_err());
+    test_market
+        .process(&create_execute_governance_action_instruction(
+            &authority,
+            &market,
+            0,
+            Some(&authority),
+        ))
+        .unwrap();
+    assert_eq!(test_market.header().fee_recipient, fee_recipient);
+}
@@ -0,0 +1,117 @@
+use crate::{
+    program::{
+        assert_with_msg, dispatch_market::load_with_dispatch_mut, error::PhoenixError,
+        loaders::NewOrderContext, token_utils::maybe_invoke_deposit, MarketHeader,
+        PhoenixMarketContext, PhoenixVaultContext,
+    },
+    quantities::{BaseLots, QuoteLots, Ticks, WrapperU64},
+    state::markets::{FIFOOrderId, MarketEvent},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use std::mem::size_of;
+
+use super::CancelOrderParams;
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
+pub struct IncreaseOrderParams {
+    pub base_params: CancelOrderParams,
+    /// Number of base lots to add to the order
+    pub size: u64,
+    /// Client order id used to identify the reinserted order in the response to the client
+    pub client_order_id: u128,
+}
+
+/// This function adds size to an existing order on the book. The order is moved to the back of the
+/// queue at its price and is assigned a new order id, which is returned to the client.
+/// Only users with a "seat" on the market are authorized to perform this action.
+pub(crate) fn process_increase_order<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+    only_free_funds: bool,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+    order_ids: &mut Vec<FIFOOrderId>,
+) -> ProgramResult {
+    let IncreaseOrderParams {
+        base_params,
+        size,
+        client_order_id,
+    } = IncreaseOrderParams::try_from_slice(data)?;
+    let CancelOrderParams {
+        side,
+        price_in_ticks,
+        order_sequence_number,
+    } = base_params;
+    let order_id = FIFOOrderId::new(Ticks::new(price_in_ticks), order_sequence_number);
+
+    let NewOrderContext { vault_context, .. } =
+        NewOrderContext::load_post_allowed(market_context, accounts, only_free_funds)?;
+
+    let PhoenixMarketContext {
+        market_info,
+        signer: trader,
+    } = market_context;
+
+    let (quote_lots_to_deposit, base_lots_to_deposit) = {
+        let clock = Clock::get()?;
+        let mut get_clock_fn = || (clock.slot, clock.unix_timestamp as u64);
+        let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let (new_order_id, matching_engine_response) = market
+            .increase_order(
+                trader.key,
+                &order_id,
+                side,
+                BaseLots::new(size),
+                client_order_id,
+                only_free_funds,
+                record_event_fn,
+                &mut get_clock_fn,
+            )
+            .ok_or(PhoenixError::IncreaseOrderError)?;
+        order_ids.push(new_order_id);
+        (
+            matching_engine_response.get_deposit_amount_bid_in_quote_lots(),
+            matching_engine_response.get_deposit_amount_ask_in_base_lots(),
+        )
+    };
+
+    if let Some(PhoenixVaultContext {
+        base_account,
+        quote_account,
+        base_vault,
+        quote_vault,
+        token_program,
+    }) = vault_context
+    {
+        let header = market_info.get_header()?;
+        maybe_invoke_deposit(
+            (quote_lots_to_deposit * header.get_quote_lot_size()).as_u64(),
+            &token_program,
+            &quote_account,
+            &quote_vault,
+            trader.as_ref(),
+        )?;
+        maybe_invoke_deposit(
+            (base_lots_to_deposit * header.get_base_lot_size()).as_u64(),
+            &token_program,
+            &base_account,
+            &base_vault,
+            trader.as_ref(),
+        )?;
+    } else {
+        // This case is only reached if the user is increasing orders with free funds
+        // In this case, the matching engine verifies that no deposit is needed
+        assert_with_msg(
+            quote_lots_to_deposit == QuoteLots::ZERO && base_lots_to_deposit == BaseLots::ZERO,
+            PhoenixError::IncreaseOrderError,
+            "WARNING: Expected no funds to be deposited",
+        )?;
+    }
+    Ok(())
+}
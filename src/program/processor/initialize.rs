@@ -4,7 +4,7 @@ use crate::{
         error::{assert_with_msg, PhoenixError},
         loaders::{get_vault_address, InitializeMarketContext},
         system_utils::create_account,
-        MarketHeader, MarketSizeParams, PhoenixMarketContext, TokenParams,
+        MarketHeader, MarketSizeParams, PhoenixMarketContext, SeatApprovalParams, TokenParams,
     },
     quantities::{
         BaseAtomsPerBaseUnit, BaseLotsPerBaseUnit, QuoteAtomsPerQuoteUnit,
@@ -22,6 +22,7 @@ use std::{mem::size_of, ops::DerefMut};
 pub struct InitializeParams {
     /// These parameters define the number of orders on each side of the market as well as the maximum
     /// number of supported traders. They are used to deserialize the market state (see `dispatch_market.rs`).
+    /// Only some capacities are supported, see `MarketSizeParams`.
     pub market_size_params: MarketSizeParams,
 
     /// Number of quote lots to make up a full quote unit. Quote lots are the smallest measurement for
@@ -63,6 +64,19 @@ pub struct InitializeParams {
     /// If this parameter is supplied, the market will treat the number of base atoms in a base unit as
     /// `(10^base_mint_decimals) * raw_base_units_per_base_unit`.
     pub raw_base_units_per_base_unit: Option<u32>,
+
+    /// Defines whether seats requested by traders are approved without the market authority, and the fee
+    /// charged for such seats. By default, seats must be approved by the market authority.
+    pub seat_approval_params: Option<SeatApprovalParams>,
+}
+
+/// Decodes the initialize params, allowing the optional trailing fields to be omitted.
+pub fn decode_initialize_params(bytes: &[u8]) -> Result<InitializeParams, ProgramError> {
+    InitializeParams::try_from_slice(bytes).or_else(|_| {
+        // Params encoded before `seat_approval_params` existed end after `raw_base_units_per_base_unit`
+        InitializeParams::try_from_slice(&[bytes, &[0_u8 /* seat_approval_params */]].concat())
+            .map_err(|_| ProgramError::InvalidInstructionData)
+    })
 }
 
 pub(crate) fn process_initialize_market<'a, 'info>(
@@ -93,7 +107,8 @@ pub(crate) fn process_initialize_market<'a, 'info>(
         taker_fee_bps,
         fee_collector,
         raw_base_units_per_base_unit,
-    } = InitializeParams::try_from_slice(data)?;
+        seat_approval_params,
+    } = decode_initialize_params(data)?;
 
     let tick_size_in_quote_lots_per_base_unit =
         QuoteLotsPerBaseUnitPerTick::new(tick_size_in_quote_lots_per_base_unit);
@@ -195,7 +210,7 @@ pub(crate) fn process_initialize_market<'a, 'info>(
     // Setup the initial market state
     {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_init(&market_size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_init(&market_size_params, market_bytes)?.inner;
         assert_with_msg(
             market.get_sequence_number() == 0,
             PhoenixError::MarketAlreadyInitialized,
@@ -234,7 +249,43 @@ pub(crate) fn process_initialize_market<'a, 'info>(
         fee_collector,
         raw_base_units_per_base_unit.unwrap_or(1),
     );
+    header.seat_approval_params = seat_approval_params.unwrap_or_default();
 
     drop(header);
     Ok(())
 }
+
+#[test]
+fn test_decode_initialize_params_without_optional_fields() {
+    let params = InitializeParams {
+        market_size_params: MarketSizeParams {
+            bids_size: 512,
+            asks_size: 512,
+            num_seats: 128,
+        },
+        num_quote_lots_per_quote_unit: 100_000,
+        tick_size_in_quote_lots_per_base_unit: 1000,
+        num_base_lots_per_base_unit: 1000,
+        taker_fee_bps: 5,
+        fee_collector: Pubkey::new_unique(),
+        raw_base_units_per_base_unit: None,
+        seat_approval_params: None,
+    };
+    let bytes = params.try_to_vec().unwrap();
+    // Params encoded before `seat_approval_params` existed lack its trailing byte
+    let decoded = decode_initialize_params(&bytes[..bytes.len() - 1]).unwrap();
+    assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+
+    let params = InitializeParams {
+        seat_approval_params: Some(SeatApprovalParams {
+            permissionless: 1,
+            seat_fee_in_lamports: 1_000_000,
+            seat_fee_in_quote_lots: 0,
+        }),
+        ..params
+    };
+    let bytes = params.try_to_vec().unwrap();
+    let decoded = decode_initialize_params(&bytes).unwrap();
+    assert_eq!(decoded.seat_approval_params, params.seat_approval_params);
+    assert!(decode_initialize_params(&bytes[..bytes.len() - 4]).is_err());
+}
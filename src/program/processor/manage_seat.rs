@@ -1,13 +1,24 @@
-use crate::program::{
-    dispatch_market::load_with_dispatch_mut, error::assert_with_msg, loaders::get_seat_address,
-    status::SeatApprovalStatus, system_utils::create_account, AuthorizedSeatRequestContext,
-    MarketHeader, ModifySeatContext, PhoenixMarketContext, RequestSeatContext, Seat,
+use crate::{
+    program::{
+        allowlist::verify_allowlist_proof,
+        dispatch_market::load_with_dispatch_mut,
+        error::{assert_with_msg, PhoenixError},
+        loaders::get_seat_address,
+        status::SeatApprovalStatus,
+        system_utils::create_account,
+        token_utils::maybe_invoke_deposit,
+        validation::checkers::phoenix_checkers::MarketAccountInfo,
+        AuthorizedSeatRequestContext, MarketHeader, ModifySeatContext, PhoenixMarketContext,
+        RequestSeatContext, RequestSeatWithProofContext, Seat, SeatApprovalParams, SeatFeeContext,
+    },
+    quantities::{QuoteLots, WrapperU64},
+    state::markets::MarketEvent,
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use sokoban::node_allocator::ZeroCopy;
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
-    pubkey::Pubkey, rent::Rent, sysvar::Sysvar,
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::invoke,
+    program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_instruction, sysvar::Sysvar,
 };
 use std::mem::size_of;
 
@@ -17,6 +28,7 @@ pub(crate) fn process_request_seat_authorized<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let AuthorizedSeatRequestContext {
         payer,
@@ -30,20 +42,26 @@ pub(crate) fn process_request_seat_authorized<'a, 'info>(
         seat.as_ref(),
         market_context.market_info.key,
         system_program.as_ref(),
+        record_event_fn,
     )
 }
 
 /// This instruction is used to request a seat on the market for a trader (by the trader)
+///
+/// If the market allows permissionless seats, the seat fee is charged and the seat is immediately
+/// approved, registering the trader on the market.
 pub(crate) fn process_request_seat<'a, 'info>(
     _program_id: &Pubkey,
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     _data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let RequestSeatContext {
         seat,
         system_program,
-        ..
+        fee_recipient,
+        seat_fee_context,
     } = RequestSeatContext::load(market_context, accounts)?;
     let PhoenixMarketContext {
         market_info,
@@ -55,7 +73,121 @@ pub(crate) fn process_request_seat<'a, 'info>(
         seat.as_ref(),
         market_info.key,
         system_program.as_ref(),
-    )
+        record_event_fn,
+    )?;
+
+    let (seat_approval_params, quote_lot_size) = {
+        let header = market_info.get_header()?;
+        (header.seat_approval_params, header.get_quote_lot_size())
+    };
+    if !seat_approval_params.is_permissionless() {
+        return Ok(());
+    }
+    if let Some(fee_recipient) = fee_recipient {
+        invoke(
+            &system_instruction::transfer(
+                trader.key,
+                fee_recipient.key,
+                seat_approval_params.seat_fee_in_lamports,
+            ),
+            &[
+                trader.as_ref().clone(),
+                fee_recipient.clone(),
+                system_program.as_ref().clone(),
+            ],
+        )?;
+    }
+    if let Some(SeatFeeContext {
+        quote_account,
+        fee_recipient_quote_account,
+        token_program,
+    }) = seat_fee_context
+    {
+        maybe_invoke_deposit(
+            (QuoteLots::new(seat_approval_params.seat_fee_in_quote_lots) * quote_lot_size).as_u64(),
+            &token_program,
+            &quote_account,
+            &fee_recipient_quote_account,
+            trader.as_ref(),
+        )?;
+    }
+
+    _approve_seat(market_info, trader.key, seat.as_ref(), record_event_fn)
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct RequestSeatWithProofParams {
+    /// Sibling hashes from the trader's leaf to the root of the market's seat allowlist
+    pub proof: Vec<[u8; 32]>,
+}
+
+/// This instruction is used by a trader on the market's seat allowlist to request a seat, which
+/// is immediately approved if the inclusion proof is valid
+pub(crate) fn process_request_seat_with_proof<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let RequestSeatWithProofContext {
+        seat,
+        system_program,
+    } = RequestSeatWithProofContext::load(market_context, accounts)?;
+    let PhoenixMarketContext {
+        market_info,
+        signer: trader,
+    } = market_context;
+    let RequestSeatWithProofParams { proof } = RequestSeatWithProofParams::try_from_slice(data)?;
+    let root = market_info.get_header()?.seat_allowlist_root;
+    assert_with_msg(
+        root != [0; 32],
+        PhoenixError::InvalidSeatStatus,
+        "Market does not have a seat allowlist",
+    )?;
+    assert_with_msg(
+        verify_allowlist_proof(&root, trader.key, &proof),
+        PhoenixError::InvalidSeatStatus,
+        "Invalid seat allowlist proof",
+    )?;
+    _create_seat(
+        trader.as_ref(),
+        trader.key,
+        seat.as_ref(),
+        market_info.key,
+        system_program.as_ref(),
+        record_event_fn,
+    )?;
+    _approve_seat(market_info, trader.key, seat.as_ref(), record_event_fn)
+}
+
+/// Approves a newly created seat and registers the trader on the market
+fn _approve_seat(
+    market_info: &MarketAccountInfo,
+    trader: &Pubkey,
+    seat: &AccountInfo,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let mut seat_bytes = seat.try_borrow_mut_data()?;
+    let seat = Seat::load_mut_bytes(&mut seat_bytes).ok_or(ProgramError::InvalidAccountData)?;
+    record_event_fn(MarketEvent::SeatStatusChange {
+        trader: *trader,
+        previous_status: SeatApprovalStatus::from(seat.approval_status),
+        new_status: SeatApprovalStatus::Approved,
+    });
+    seat.approval_status = SeatApprovalStatus::Approved as u64;
+    let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+    let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+    assert_with_msg(
+        market.get_or_register_trader(trader).is_some(),
+        ProgramError::InvalidArgument,
+        "Failed to register trader",
+    )?;
+    // Newly registered traders start out active so that they cannot be evicted right away
+    if let Some(trader_state) = market.get_trader_state_mut(trader) {
+        trader_state.last_activity_slot = Clock::get()?.slot;
+    }
+    Ok(())
 }
 
 fn _create_seat<'a, 'info>(
@@ -64,6 +196,7 @@ fn _create_seat<'a, 'info>(
     seat: &'a AccountInfo<'info>,
     market_key: &Pubkey,
     system_program: &'a AccountInfo<'info>,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let (seat_address, bump) = get_seat_address(market_key, trader);
     assert_with_msg(
@@ -90,6 +223,7 @@ fn _create_seat<'a, 'info>(
     let mut seat_bytes = seat.try_borrow_mut_data()?;
     *Seat::load_mut_bytes(&mut seat_bytes).ok_or(ProgramError::InvalidAccountData)? =
         Seat::new_init(*market_key, *trader)?;
+    record_event_fn(MarketEvent::SeatRequest { trader: *trader });
     Ok(())
 }
 
@@ -100,6 +234,7 @@ pub(crate) fn process_change_seat_status<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let ModifySeatContext { seat: seat_info } = ModifySeatContext::load(market_context, accounts)?;
     let PhoenixMarketContext {
@@ -117,12 +252,15 @@ pub(crate) fn process_change_seat_status<'a, 'info>(
             seat.approval_status = SeatApprovalStatus::Approved as u64;
             // Initialize a seat for the approved trader
             let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-            let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+            let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
             assert_with_msg(
                 market.get_or_register_trader(&seat.trader).is_some(),
                 ProgramError::InvalidArgument,
                 "Failed to register trader",
             )?;
+            if let Some(trader_state) = market.get_trader_state_mut(&seat.trader) {
+                trader_state.last_activity_slot = Clock::get()?.slot;
+            }
         }
         (SeatApprovalStatus::Approved, SeatApprovalStatus::NotApproved) => {
             seat.approval_status = SeatApprovalStatus::NotApproved as u64;
@@ -137,5 +275,55 @@ pub(crate) fn process_change_seat_status<'a, 'info>(
             return Err(ProgramError::InvalidInstructionData);
         }
     }
+    record_event_fn(MarketEvent::SeatStatusChange {
+        trader: seat.trader,
+        previous_status: current_status,
+        new_status,
+    });
+    Ok(())
+}
+
+/// This instruction is used by the market authority to change how seats requested by traders are
+/// approved, and the fee charged for permissionless seats
+pub(crate) fn process_change_seat_approval_params<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    let seat_approval_params = SeatApprovalParams::try_from_slice(data)?;
+    assert_with_msg(
+        seat_approval_params.permissionless <= 1,
+        ProgramError::InvalidInstructionData,
+        "Permissionless must be set to 0 or 1",
+    )?;
+    market_info.get_header_mut()?.seat_approval_params = seat_approval_params;
+    Ok(())
+}
+
+/// This instruction is used by the market authority to replace the root of the market's seat
+/// allowlist. Setting the root to zero disables the allowlist.
+pub(crate) fn process_change_seat_allowlist_root<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    let PhoenixMarketContext {
+        market_info,
+        signer: authority,
+    } = market_context;
+    market_info.assert_valid_authority(authority.key)?;
+    let new_root = <[u8; 32]>::try_from_slice(data)?;
+    let mut header = market_info.get_header_mut()?;
+    record_event_fn(MarketEvent::SeatAllowlistRootUpdate {
+        previous_root: header.seat_allowlist_root,
+        new_root,
+    });
+    header.seat_allowlist_root = new_root;
     Ok(())
 }
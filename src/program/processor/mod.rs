@@ -1,13 +1,20 @@
 pub mod cancel_multiple_orders;
 pub mod deposit;
+pub mod evict_trader;
+pub mod expand_market;
 pub mod fees;
 pub mod governance;
+pub mod governance_proposals;
+pub mod increase_order;
 pub mod initialize;
 pub mod manage_seat;
 pub mod new_order;
 pub mod reduce_order;
+pub mod self_trade_prevention;
 pub mod withdraw;
 
 pub use cancel_multiple_orders::*;
+pub use expand_market::*;
+pub use increase_order::*;
 pub use initialize::*;
 pub use reduce_order::*;
@@ -13,11 +13,11 @@ use crate::{
     },
     state::{
         decode_order_packet,
-        markets::{FIFOOrderId, FIFORestingOrder, MarketEvent, MarketWrapperMut},
+        markets::{FIFOOrderId, FIFORestingOrder, MarketEvent, MarketWrapperMut, WritableMarket},
         OrderPacket, OrderPacketMetadata, Side,
     },
 };
-use borsh::{BorshDeserialize, BorshSerialize};
+use borsh::{BorshDeserialize, BorshDeserialize as Deserialize, BorshSerialize};
 use itertools::Itertools;
 use solana_program::{
     account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, log::sol_log_compute_units,
@@ -25,6 +25,8 @@ use solana_program::{
 };
 use std::mem::size_of;
 
+use super::evict_trader::{get_activity_slot, record_trader_activity};
+
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub enum FailedMultipleLimitOrderBehavior {
     /// Orders will never cross the spread. Instead they will be amended to the closest non-crossing price.
@@ -64,6 +66,7 @@ impl FailedMultipleLimitOrderBehavior {
 }
 
 /// Struct to send a vector of bids and asks as PostOnly orders in a single packet.
+/// These orders can't be linked (see `link_id` in `OrderPacket`).
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
 pub struct MultipleOrderPacket {
     /// Bids and asks are in the format (price in ticks, size in base lots)
@@ -79,6 +82,10 @@ pub struct CondensedOrder {
     pub size_in_base_lots: u64,
     pub last_valid_slot: Option<u64>,
     pub last_valid_unix_timestamp_in_seconds: Option<u64>,
+    /// Relative expiry in slots from the time the order is placed
+    pub time_to_live_in_slots: Option<u64>,
+    /// Relative expiry in seconds from the time the order is placed
+    pub time_to_live_in_seconds: Option<u64>,
 }
 
 impl CondensedOrder {
@@ -88,6 +95,63 @@ impl CondensedOrder {
             size_in_base_lots,
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
+        }
+    }
+}
+
+// By using the aliased BorshDeserialize trait, we prevent Shank from writing the legacy structs to the IDL.
+
+/// Layout of `CondensedOrder` before the relative time-to-live fields were added
+#[derive(Deserialize)]
+struct LegacyCondensedOrder {
+    price_in_ticks: u64,
+    size_in_base_lots: u64,
+    last_valid_slot: Option<u64>,
+    last_valid_unix_timestamp_in_seconds: Option<u64>,
+}
+
+/// Layout of `MultipleOrderPacket` before the relative time-to-live fields were added
+#[derive(Deserialize)]
+struct LegacyMultipleOrderPacket {
+    bids: Vec<LegacyCondensedOrder>,
+    asks: Vec<LegacyCondensedOrder>,
+    client_order_id: Option<u128>,
+    failed_multiple_limit_order_behavior: FailedMultipleLimitOrderBehavior,
+}
+
+impl From<LegacyCondensedOrder> for CondensedOrder {
+    fn from(order: LegacyCondensedOrder) -> Self {
+        CondensedOrder {
+            price_in_ticks: order.price_in_ticks,
+            size_in_base_lots: order.size_in_base_lots,
+            last_valid_slot: order.last_valid_slot,
+            last_valid_unix_timestamp_in_seconds: order.last_valid_unix_timestamp_in_seconds,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
+        }
+    }
+}
+
+/// Decodes a `MultipleOrderPacket`, falling back to the layout used before `CondensedOrder` had
+/// relative time-to-live fields.
+pub fn decode_multiple_order_packet(bytes: &[u8]) -> Option<MultipleOrderPacket> {
+    match MultipleOrderPacket::try_from_slice(bytes) {
+        Ok(multiple_order_packet) => Some(multiple_order_packet),
+        Err(_) => {
+            let LegacyMultipleOrderPacket {
+                bids,
+                asks,
+                client_order_id,
+                failed_multiple_limit_order_behavior,
+            } = LegacyMultipleOrderPacket::try_from_slice(bytes).ok()?;
+            Some(MultipleOrderPacket {
+                bids: bids.into_iter().map(CondensedOrder::from).collect(),
+                asks: asks.into_iter().map(CondensedOrder::from).collect(),
+                client_order_id,
+                failed_multiple_limit_order_behavior,
+            })
         }
     }
 }
@@ -212,7 +276,7 @@ pub(crate) fn process_swap_with_free_funds<'a, 'info>(
     )
 }
 
-/// This function performs a Post-Only or Limit order against the specified market.
+/// This function performs a Post-Only, Limit or Market-to-Limit order against the specified market.
 /// Only users with a "seat" on the market are authorized to perform this action.
 pub(crate) fn process_place_limit_order<'a, 'info>(
     _program_id: &Pubkey,
@@ -233,7 +297,7 @@ pub(crate) fn process_place_limit_order<'a, 'info>(
     assert_with_msg(
         !order_packet.is_take_only(),
         ProgramError::InvalidInstructionData,
-        "Order type must be Limit or PostOnly",
+        "Order type must be Limit, PostOnly or MarketToLimit",
     )?;
     assert_with_msg(
         !order_packet.no_deposit_or_withdrawal(),
@@ -249,7 +313,7 @@ pub(crate) fn process_place_limit_order<'a, 'info>(
     )
 }
 
-/// This function performs a Post-Only or Limit order against the specified market
+/// This function performs a Post-Only, Limit or Market-to-Limit order against the specified market
 /// using only the funds already available to the trader.
 /// Only users with sufficient funds and a "seat" on the market are authorized
 /// to perform this action.
@@ -272,7 +336,7 @@ pub(crate) fn process_place_limit_order_with_free_funds<'a, 'info>(
     assert_with_msg(
         !order_packet.is_take_only(),
         ProgramError::InvalidInstructionData,
-        "Order type must be Limit or PostOnly",
+        "Order type must be Limit, PostOnly or MarketToLimit",
     )?;
     assert_with_msg(
         order_packet.no_deposit_or_withdrawal(),
@@ -301,7 +365,8 @@ pub(crate) fn process_place_multiple_post_only_orders<'a, 'info>(
     order_ids: &mut Vec<FIFOOrderId>,
 ) -> ProgramResult {
     let new_order_context = NewOrderContext::load_post_allowed(market_context, accounts, false)?;
-    let multiple_order_packet = MultipleOrderPacket::try_from_slice(data)?;
+    let multiple_order_packet =
+        decode_multiple_order_packet(data).ok_or(ProgramError::InvalidInstructionData)?;
     assert_with_msg(
         new_order_context.seat_option.is_some(),
         ProgramError::InvalidInstructionData,
@@ -333,7 +398,8 @@ pub(crate) fn process_place_multiple_post_only_orders_with_free_funds<'a, 'info>
     order_ids: &mut Vec<FIFOOrderId>,
 ) -> ProgramResult {
     let new_order_context = NewOrderContext::load_post_allowed(market_context, accounts, true)?;
-    let multiple_order_packet = MultipleOrderPacket::try_from_slice(data)?;
+    let multiple_order_packet =
+        decode_multiple_order_packet(data).ok_or(ProgramError::InvalidInstructionData)?;
     assert_with_msg(
         new_order_context.seat_option.is_some(),
         ProgramError::InvalidInstructionData,
@@ -365,6 +431,7 @@ fn process_new_order<'a, 'info>(
         let header = market_info.get_header()?;
         (header.get_quote_lot_size(), header.get_base_lot_size())
     };
+    let activity_slot = get_activity_slot(market_info)?;
 
     let side = order_packet.side();
     let (
@@ -376,7 +443,8 @@ fn process_new_order<'a, 'info>(
         let clock = Clock::get()?;
         let mut get_clock_fn = || (clock.slot, clock.unix_timestamp as u64);
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market_wrapper = load_with_dispatch_mut(&market_info.size_params, market_bytes)?;
+        let mut market_wrapper = load_with_dispatch_mut(&market_info.size_params, market_bytes)?;
+        record_trader_activity(market_wrapper.inner.as_mut(), trader.key, activity_slot);
 
         // If the order should fail silently on insufficient funds, and the trader does not have
         // sufficient funds for the order, return silently without modifying the book.
@@ -412,6 +480,18 @@ fn process_new_order<'a, 'info>(
             order_ids.push(order_id);
         }
 
+        if !order_packet.no_deposit_or_withdrawal() {
+            record_token_account_events(
+                market_wrapper.inner.as_ref(),
+                trader.key,
+                matching_engine_response.get_deposit_amount_ask_in_base_lots(),
+                matching_engine_response.get_deposit_amount_bid_in_quote_lots(),
+                matching_engine_response.num_base_lots_out,
+                matching_engine_response.num_quote_lots_out,
+                record_event_fn,
+            );
+        }
+
         (
             matching_engine_response.num_quote_lots_out * quote_lot_size,
             matching_engine_response.get_deposit_amount_bid_in_quote_lots() * quote_lot_size,
@@ -536,12 +616,14 @@ fn process_multiple_new_orders<'a, 'info>(
         let header = market_info.get_header()?;
         (header.get_quote_lot_size(), header.get_base_lot_size())
     };
+    let activity_slot = get_activity_slot(market_info)?;
 
     {
         let clock = Clock::get()?;
         let mut get_clock_fn = || (clock.slot, clock.unix_timestamp as u64);
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market_wrapper = load_with_dispatch_mut(&market_info.size_params, market_bytes)?;
+        let mut market_wrapper = load_with_dispatch_mut(&market_info.size_params, market_bytes)?;
+        record_trader_activity(market_wrapper.inner.as_mut(), trader.key, activity_slot);
 
         let (mut base_lots_available, mut quote_lots_available) =
             get_available_balances_for_trader(
@@ -558,6 +640,8 @@ fn process_multiple_new_orders<'a, 'info>(
                 size_in_base_lots,
                 last_valid_slot,
                 last_valid_unix_timestamp_in_seconds,
+                time_to_live_in_slots,
+                time_to_live_in_seconds,
             } in book_orders
                 .iter()
                 .sorted_by(|o1, o2| o1.price_in_ticks.cmp(&o2.price_in_ticks))
@@ -566,18 +650,28 @@ fn process_multiple_new_orders<'a, 'info>(
                         o.price_in_ticks,
                         o.last_valid_slot,
                         o.last_valid_unix_timestamp_in_seconds,
+                        o.time_to_live_in_slots,
+                        o.time_to_live_in_seconds,
                     )
                 })
                 .into_iter()
                 .map(
                     |(
-                        (price_in_ticks, last_valid_slot, last_valid_unix_timestamp_in_seconds),
+                        (
+                            price_in_ticks,
+                            last_valid_slot,
+                            last_valid_unix_timestamp_in_seconds,
+                            time_to_live_in_slots,
+                            time_to_live_in_seconds,
+                        ),
                         level,
                     )| CondensedOrder {
                         price_in_ticks,
                         size_in_base_lots: level.fold(0, |acc, o| acc + o.size_in_base_lots),
                         last_valid_slot,
                         last_valid_unix_timestamp_in_seconds,
+                        time_to_live_in_slots,
+                        time_to_live_in_seconds,
                     },
                 )
             {
@@ -592,6 +686,9 @@ fn process_multiple_new_orders<'a, 'info>(
                     last_valid_unix_timestamp_in_seconds,
                     fail_silently_on_insufficient_funds: failed_multiple_limit_order_behavior
                         .should_skip_orders_with_insufficient_funds(),
+                    link_id: None,
+                    time_to_live_in_slots,
+                    time_to_live_in_seconds,
                 };
 
                 let matching_engine_response = {
@@ -636,6 +733,18 @@ fn process_multiple_new_orders<'a, 'info>(
                 base_lots_to_deposit += base_lots_deposited;
             }
         }
+
+        if !no_deposit {
+            record_token_account_events(
+                market_wrapper.inner.as_ref(),
+                trader.key,
+                base_lots_to_deposit,
+                quote_lots_to_deposit,
+                BaseLots::ZERO,
+                QuoteLots::ZERO,
+                record_event_fn,
+            );
+        }
     }
 
     if !no_deposit {
@@ -745,3 +854,39 @@ fn order_packet_has_sufficient_funds<'a>(
     }
     true
 }
+
+/// Records the Deposit and Withdraw events for the funds that an order moves between the market
+/// and the trader's token accounts, with the free balances of the trader after the order. Traders
+/// without a seat on the market, such as swappers, are reported with zero free balances.
+fn record_token_account_events(
+    market: &dyn WritableMarket<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>,
+    trader: &Pubkey,
+    base_lots_deposited: BaseLots,
+    quote_lots_deposited: QuoteLots,
+    base_lots_withdrawn: BaseLots,
+    quote_lots_withdrawn: QuoteLots,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) {
+    let (base_lots_free, quote_lots_free) = market
+        .get_trader_state(trader)
+        .map(|trader_state| (trader_state.base_lots_free, trader_state.quote_lots_free))
+        .unwrap_or((BaseLots::ZERO, QuoteLots::ZERO));
+    if base_lots_deposited != BaseLots::ZERO || quote_lots_deposited != QuoteLots::ZERO {
+        record_event_fn(MarketEvent::Deposit {
+            trader: *trader,
+            base_lots_deposited,
+            quote_lots_deposited,
+            base_lots_free,
+            quote_lots_free,
+        });
+    }
+    if base_lots_withdrawn != BaseLots::ZERO || quote_lots_withdrawn != QuoteLots::ZERO {
+        record_event_fn(MarketEvent::Withdraw {
+            trader: *trader,
+            base_lots_withdrawn,
+            quote_lots_withdrawn,
+            base_lots_free,
+            quote_lots_free,
+        });
+    }
+}
@@ -17,6 +17,11 @@ use solana_program::{
 };
 use std::mem::size_of;
 
+use super::{
+    evict_trader::{get_activity_slot, record_trader_activity},
+    withdraw::record_withdraw_event,
+};
+
 #[derive(BorshDeserialize, BorshSerialize, Clone, Copy)]
 pub struct CancelOrderParams {
     pub side: Side,
@@ -60,13 +65,15 @@ pub(crate) fn process_reduce_order<'a, 'info>(
         signer: trader,
     } = market_context;
 
+    let activity_slot = get_activity_slot(market_info)?;
     let MatchingEngineResponse {
         num_quote_lots_out,
         num_base_lots_out,
         ..
     } = {
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader.key, activity_slot);
         sol_log_compute_units();
         market
             .reduce_order(
@@ -91,6 +98,13 @@ pub(crate) fn process_reduce_order<'a, 'info>(
         token_program,
     }) = vault_context_option
     {
+        record_withdraw_event(
+            market_info,
+            trader.key,
+            num_base_lots_out,
+            num_quote_lots_out,
+            record_event_fn,
+        )?;
         try_withdraw(
             market_info.key,
             &header.base_params,
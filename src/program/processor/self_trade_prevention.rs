@@ -0,0 +1,50 @@
+use crate::program::{
+    dispatch_market::load_with_dispatch_mut, ChangeSelfTradePreventionGroupContext, MarketHeader,
+    PhoenixError, PhoenixMarketContext,
+};
+use borsh::BorshDeserialize;
+use solana_program::{account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey};
+use std::mem::size_of;
+
+/// This instruction is used by a trader to join or leave a self trade prevention group.
+/// Joining a group requires a signature from the group key, leaving a group (setting the
+/// group to the default pubkey) does not.
+pub(crate) fn process_change_self_trade_prevention_group<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let group = Pubkey::try_from_slice(data)?;
+    let ChangeSelfTradePreventionGroupContext { trader, .. } =
+        ChangeSelfTradePreventionGroupContext::load(market_context, accounts, &group)?;
+    set_self_trade_prevention_group(market_context, &trader, group)
+}
+
+/// This instruction is used by the market authority to set the self trade prevention group of a trader
+pub(crate) fn process_change_self_trade_prevention_group_authorized<'a, 'info>(
+    _program_id: &Pubkey,
+    market_context: &PhoenixMarketContext<'a, 'info>,
+    accounts: &'a [AccountInfo<'info>],
+    data: &[u8],
+) -> ProgramResult {
+    let group = Pubkey::try_from_slice(data)?;
+    let ChangeSelfTradePreventionGroupContext { trader, .. } =
+        ChangeSelfTradePreventionGroupContext::load_authorized(market_context, accounts)?;
+    set_self_trade_prevention_group(market_context, &trader, group)
+}
+
+fn set_self_trade_prevention_group(
+    market_context: &PhoenixMarketContext,
+    trader: &Pubkey,
+    group: Pubkey,
+) -> ProgramResult {
+    let PhoenixMarketContext { market_info, .. } = market_context;
+    let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
+    let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+    market
+        .get_trader_state_mut(trader)
+        .ok_or(PhoenixError::TraderNotFound)?
+        .self_trade_prevention_group = group;
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use crate::{
     program::{
-        dispatch_market::load_with_dispatch_mut,
+        dispatch_market::{load_with_dispatch, load_with_dispatch_mut},
         error::{assert_with_msg, PhoenixError},
         loaders::CancelOrWithdrawContext as Withdraw,
         token_utils::try_withdraw,
@@ -8,7 +8,7 @@ use crate::{
         MarketHeader, PhoenixMarketContext, PhoenixVaultContext,
     },
     quantities::{BaseLots, QuoteLots, WrapperU64},
-    state::MatchingEngineResponse,
+    state::{markets::MarketEvent, MatchingEngineResponse},
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
@@ -17,6 +17,8 @@ use solana_program::{
 };
 use std::mem::size_of;
 
+use super::evict_trader::{get_activity_slot, record_trader_activity};
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub struct WithdrawParams {
     pub quote_lots_to_withdraw: Option<u64>,
@@ -28,6 +30,7 @@ pub(crate) fn process_withdraw_funds<'a, 'info>(
     market_context: &PhoenixMarketContext<'a, 'info>,
     accounts: &'a [AccountInfo<'info>],
     data: &[u8],
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     let Withdraw { vault_context } = Withdraw::load(market_context, accounts)?;
     let WithdrawParams {
@@ -45,6 +48,7 @@ pub(crate) fn process_withdraw_funds<'a, 'info>(
         quote_lots_to_withdraw,
         base_lots_to_withdraw,
         false,
+        record_event_fn,
     )
 }
 
@@ -56,6 +60,7 @@ pub(crate) fn process_withdraw<'a, 'info>(
     quote_lots_to_withdraw: Option<u64>,
     base_lots_to_withdraw: Option<u64>,
     evict_seat: bool,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
 ) -> ProgramResult {
     sol_log_compute_units();
 
@@ -66,6 +71,12 @@ pub(crate) fn process_withdraw<'a, 'info>(
         quote_vault,
         token_program,
     } = vault_context;
+    // Evicting a seat is done by the market authority, not by the trader
+    let activity_slot = if evict_seat {
+        None
+    } else {
+        get_activity_slot(market_info)?
+    };
     let MatchingEngineResponse {
         num_quote_lots_out,
         num_base_lots_out,
@@ -73,7 +84,8 @@ pub(crate) fn process_withdraw<'a, 'info>(
     } = {
         sol_log_compute_units();
         let market_bytes = &mut market_info.try_borrow_mut_data()?[size_of::<MarketHeader>()..];
-        let market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        let mut market = load_with_dispatch_mut(&market_info.size_params, market_bytes)?.inner;
+        record_trader_activity(market.as_mut(), trader.key, activity_slot);
         let response = market
             .claim_funds(
                 trader.key,
@@ -92,6 +104,13 @@ pub(crate) fn process_withdraw<'a, 'info>(
         }
         response
     };
+    record_withdraw_event(
+        market_info,
+        trader.key,
+        num_base_lots_out,
+        num_quote_lots_out,
+        record_event_fn,
+    )?;
     sol_log_compute_units();
     let header = market_info.get_header()?;
 
@@ -110,3 +129,31 @@ pub(crate) fn process_withdraw<'a, 'info>(
 
     Ok(())
 }
+
+/// Records a Withdraw event with the free balances of the trader after the withdrawal. Nothing is
+/// recorded if no funds were withdrawn.
+pub(crate) fn record_withdraw_event(
+    market_info: &MarketAccountInfo,
+    trader: &Pubkey,
+    base_lots_withdrawn: BaseLots,
+    quote_lots_withdrawn: QuoteLots,
+    record_event_fn: &mut dyn FnMut(MarketEvent<Pubkey>),
+) -> ProgramResult {
+    if base_lots_withdrawn == BaseLots::ZERO && quote_lots_withdrawn == QuoteLots::ZERO {
+        return Ok(());
+    }
+    let market_bytes = &market_info.try_borrow_data()?[size_of::<MarketHeader>()..];
+    let market = load_with_dispatch(&market_info.size_params, market_bytes)?.inner;
+    let (base_lots_free, quote_lots_free) = market
+        .get_trader_state(trader)
+        .map(|trader_state| (trader_state.base_lots_free, trader_state.quote_lots_free))
+        .unwrap_or((BaseLots::ZERO, QuoteLots::ZERO));
+    record_event_fn(MarketEvent::Withdraw {
+        trader: *trader,
+        base_lots_withdrawn,
+        quote_lots_withdrawn,
+        base_lots_free,
+        quote_lots_free,
+    });
+    Ok(())
+}
@@ -14,10 +14,10 @@ pub enum MarketStatus {
     /// Only reductions and withdrawals are accepted.
     Paused,
     /// Only reductions and withdrawals are accepted. The market authority can forcibly cancel
-    /// all orders.
+    /// all orders and withdraw the funds of all traders.
     Closed,
     /// Used to signal the market to be deleted. Can only be called in a Closed state where all orders
-    /// and traders are removed from the book
+    /// and traders are removed from the book. The market and vault accounts can then be closed.
     Tombstoned,
 }
 
@@ -106,10 +106,19 @@ impl MarketStatus {
         )
     }
 
-    // TODO: Implement instructions for authority to withdraw funds in a Closed state
     pub fn authority_can_cancel(&self) -> bool {
         matches!(self, MarketStatus::Closed)
     }
+
+    /// The pause guardian can only restrict trading on a market that is Active or PostOnly
+    pub fn pause_guardian_can_transition(&self, new_state: &MarketStatus) -> bool {
+        matches!(
+            (self, new_state),
+            (MarketStatus::Active, MarketStatus::PostOnly)
+                | (MarketStatus::Active, MarketStatus::Paused)
+                | (MarketStatus::PostOnly, MarketStatus::Paused)
+        )
+    }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
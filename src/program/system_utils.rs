@@ -4,7 +4,7 @@ use solana_program::{
     program::{invoke, invoke_signed},
     pubkey::Pubkey,
     rent::Rent,
-    system_instruction,
+    system_instruction, system_program,
 };
 
 pub fn create_account<'a, 'info>(
@@ -68,3 +68,16 @@ pub fn create_account<'a, 'info>(
         )
     }
 }
+
+/// Closes a program owned account by moving all of its lamports to the receiver and handing the
+/// emptied account back to the system program.
+pub fn close_account<'info>(
+    account: &AccountInfo<'info>,
+    receiver: &AccountInfo<'info>,
+) -> ProgramResult {
+    let receiver_starting_lamports = receiver.lamports();
+    **receiver.lamports.borrow_mut() = receiver_starting_lamports + account.lamports();
+    **account.lamports.borrow_mut() = 0;
+    account.assign(&system_program::id());
+    account.realloc(0, false)
+}
@@ -0,0 +1,394 @@
+//! Runs instructions through `process_instruction` against accounts held in memory, so that
+//! processors can be tested without a validator.
+//!
+//! Cross-program invocations are no-ops off chain, so token transfers and account creation by
+//! the program are not reflected in the accounts. Tests set up token balances and accounts
+//! directly instead.
+
+use super::{
+    get_seat_address, get_vault_address, load_with_dispatch, load_with_dispatch_init,
+    load_with_dispatch_mut,
+    status::{MarketStatus, SeatApprovalStatus},
+    MarketHeader, MarketSizeParams, Seat, TokenParams,
+};
+use crate::{
+    quantities::{
+        BaseAtomsPerBaseLot, BaseLots, BaseLotsPerBaseUnit, QuoteAtomsPerBaseUnitPerTick,
+        QuoteAtomsPerQuoteLot, QuoteLots, QuoteLotsPerBaseUnitPerTick, WrapperU64,
+    },
+    state::{
+        markets::{FIFOOrderId, FIFORestingOrder, Market, WritableMarket},
+        OrderPacket,
+    },
+};
+use solana_program::{
+    clock::Clock,
+    entrypoint::{deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER},
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::{cell::RefCell, collections::HashMap, mem::size_of, sync::Once};
+
+thread_local! {
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+}
+
+/// Serves the clock of the test running on the current thread and the default rent
+struct TestSyscallStubs;
+
+impl SyscallStubs for TestSyscallStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        CLOCK.with(|clock| unsafe { *(var_addr as *mut Clock) = clock.borrow().clone() });
+        solana_program::entrypoint::SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        solana_program::entrypoint::SUCCESS
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TestAccount {
+    pub(crate) owner: Pubkey,
+    pub(crate) lamports: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+/// A market with its vaults, along with every account created for the test.
+pub(crate) struct TestMarket {
+    pub(crate) accounts: HashMap<Pubkey, TestAccount>,
+    pub(crate) market: Pubkey,
+    pub(crate) authority: Pubkey,
+    pub(crate) base_mint: Pubkey,
+    pub(crate) quote_mint: Pubkey,
+    pub(crate) clock: Clock,
+}
+
+impl TestMarket {
+    /// Creates an Active market with 1 atom per lot and a tick size of 1 quote lot per base unit
+    pub(crate) fn new() -> Self {
+        static STUBS: Once = Once::new();
+        STUBS.call_once(|| {
+            set_syscall_stubs(Box::new(TestSyscallStubs));
+        });
+
+        let mut test_market = Self {
+            accounts: HashMap::new(),
+            market: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: Pubkey::new_unique(),
+            clock: Clock::default(),
+        };
+        let market_size_params = MarketSizeParams {
+            bids_size: 8,
+            asks_size: 8,
+            num_seats: 8,
+        };
+        let market_size =
+            size_of::<MarketHeader>() + super::get_market_size(&market_size_params).unwrap();
+        let mut data = vec![0; market_size];
+        {
+            let mut market = load_with_dispatch_init(
+                &market_size_params,
+                &mut data[size_of::<MarketHeader>()..],
+            )
+            .unwrap()
+            .inner;
+            market.initialize_with_params(
+                QuoteLotsPerBaseUnitPerTick::new(1),
+                BaseLotsPerBaseUnit::new(1),
+            );
+        }
+        let token_params = |mint: &Pubkey| {
+            let (vault_key, vault_bump) = get_vault_address(&test_market.market, mint);
+            TokenParams {
+                vault_bump: vault_bump as u32,
+                decimals: 0,
+                mint_key: *mint,
+                vault_key,
+            }
+        };
+        let mut header = MarketHeader::new(
+            market_size_params,
+            token_params(&test_market.base_mint),
+            BaseAtomsPerBaseLot::new(1),
+            token_params(&test_market.quote_mint),
+            QuoteAtomsPerQuoteLot::new(1),
+            QuoteAtomsPerBaseUnitPerTick::new(1),
+            test_market.authority,
+            test_market.authority,
+            test_market.authority,
+            1,
+        );
+        header.status = MarketStatus::Active as u64;
+        data[..size_of::<MarketHeader>()].copy_from_slice(bytemuck::bytes_of(&header));
+        test_market.accounts.insert(
+            test_market.market,
+            TestAccount {
+                owner: crate::id(),
+                lamports: Rent::default().minimum_balance(market_size),
+                data,
+            },
+        );
+        for params in [header.base_params, header.quote_params] {
+            test_market.create_token_account(
+                &params.vault_key,
+                &params.mint_key,
+                &params.vault_key,
+                0,
+            );
+        }
+        test_market
+    }
+
+    pub(crate) fn create_token_account(
+        &mut self,
+        key: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: *mint,
+            owner: *owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        self.accounts.insert(
+            *key,
+            TestAccount {
+                owner: spl_token::id(),
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+            },
+        );
+    }
+
+    pub(crate) fn token_balance(&self, key: &Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.accounts[key].data)
+            .unwrap()
+            .amount
+    }
+
+    /// Adds `amount` to the balance of a token account, as a transfer into it would
+    pub(crate) fn add_token_balance(&mut self, key: &Pubkey, amount: i64) {
+        let account = self.accounts.get_mut(key).unwrap();
+        let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+        token_account.amount = token_account.amount.checked_add_signed(amount).unwrap();
+        token_account.pack_into_slice(&mut account.data);
+    }
+
+    pub(crate) fn create_seat(&mut self, trader: &Pubkey, approval_status: SeatApprovalStatus) {
+        let mut seat = Seat::new_init(self.market, *trader).unwrap();
+        seat.approval_status = approval_status as u64;
+        self.accounts.insert(
+            get_seat_address(&self.market, trader).0,
+            TestAccount {
+                owner: crate::id(),
+                lamports: Rent::default().minimum_balance(size_of::<Seat>()),
+                data: bytemuck::bytes_of(&seat).to_vec(),
+            },
+        );
+    }
+
+    pub(crate) fn seat(&self, trader: &Pubkey) -> Option<Seat> {
+        self.accounts
+            .get(&get_seat_address(&self.market, trader).0)
+            .filter(|account| !account.data.is_empty())
+            .map(|account| *bytemuck::from_bytes::<Seat>(&account.data))
+    }
+
+    /// Creates a trader with an approved seat and associated token accounts, holding the given
+    /// free funds on the market. The vaults are credited with the funds.
+    pub(crate) fn add_trader(&mut self, base_lots_free: u64, quote_lots_free: u64) -> Pubkey {
+        let trader = Pubkey::new_unique();
+        self.create_seat(&trader, SeatApprovalStatus::Approved);
+        for mint in [self.base_mint, self.quote_mint] {
+            self.create_token_account(
+                &get_associated_token_address(&trader, &mint),
+                &mint,
+                &trader,
+                0,
+            );
+        }
+        self.with_market(|market| {
+            market.get_or_register_trader(&trader).unwrap();
+            let trader_state = market.get_trader_state_mut(&trader).unwrap();
+            trader_state.deposit_free_base_lots(BaseLots::new(base_lots_free));
+            trader_state.deposit_free_quote_lots(QuoteLots::new(quote_lots_free));
+        });
+        let header = self.header();
+        self.add_token_balance(&header.base_params.vault_key, base_lots_free as i64);
+        self.add_token_balance(&header.quote_params.vault_key, quote_lots_free as i64);
+        trader
+    }
+
+    pub(crate) fn header(&self) -> MarketHeader {
+        *bytemuck::from_bytes(&self.accounts[&self.market].data[..size_of::<MarketHeader>()])
+    }
+
+    pub(crate) fn update_header(&mut self, f: impl FnOnce(&mut MarketHeader)) {
+        let data = &mut self.accounts.get_mut(&self.market).unwrap().data;
+        f(bytemuck::from_bytes_mut(
+            &mut data[..size_of::<MarketHeader>()],
+        ));
+    }
+
+    pub(crate) fn market_data(
+        &self,
+    ) -> Box<dyn Market<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket> + '_> {
+        let (header_bytes, market_bytes) = self.accounts[&self.market]
+            .data
+            .split_at(size_of::<MarketHeader>());
+        let header = bytemuck::from_bytes::<MarketHeader>(header_bytes);
+        load_with_dispatch(&header.market_size_params, market_bytes)
+            .unwrap()
+            .inner
+    }
+
+    pub(crate) fn with_market<R>(
+        &mut self,
+        f: impl FnOnce(&mut dyn WritableMarket<Pubkey, FIFOOrderId, FIFORestingOrder, OrderPacket>) -> R,
+    ) -> R {
+        let header = self.header();
+        let data = &mut self.accounts.get_mut(&self.market).unwrap().data;
+        let mut market = load_with_dispatch_mut(
+            &header.market_size_params,
+            &mut data[size_of::<MarketHeader>()..],
+        )
+        .unwrap()
+        .inner;
+        f(market.as_mut())
+    }
+
+    /// Runs the instruction with the accounts it references. Accounts that do not exist yet are
+    /// passed in as empty system accounts. Changes are only kept if the instruction succeeds.
+    pub(crate) fn process(&mut self, instruction: &Instruction) -> ProgramResult {
+        CLOCK.with(|clock| *clock.borrow_mut() = self.clock.clone());
+
+        // Serialize the accounts in the format used by the runtime for the program entrypoint
+        let mut input = vec![];
+        let mut account_offsets = vec![];
+        input.extend_from_slice(&(instruction.accounts.len() as u64).to_le_bytes());
+        for (i, meta) in instruction.accounts.iter().enumerate() {
+            if let Some(j) = instruction.accounts[..i]
+                .iter()
+                .position(|other| other.pubkey == meta.pubkey)
+            {
+                input.push(j as u8);
+                input.extend_from_slice(&[0; 7]);
+                continue;
+            }
+            let account = self
+                .accounts
+                .get(&meta.pubkey)
+                .cloned()
+                .unwrap_or_else(|| TestAccount {
+                    owner: system_program::id(),
+                    ..Default::default()
+                });
+            let executable =
+                [crate::id(), spl_token::id(), system_program::id()].contains(&meta.pubkey);
+            input.extend_from_slice(&[
+                NON_DUP_MARKER,
+                meta.is_signer as u8,
+                meta.is_writable as u8,
+                executable as u8,
+            ]);
+            input.extend_from_slice(&[0; 4]);
+            input.extend_from_slice(meta.pubkey.as_ref());
+            input.extend_from_slice(account.owner.as_ref());
+            input.extend_from_slice(&account.lamports.to_le_bytes());
+            input.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+            account_offsets.push((meta.pubkey, input.len()));
+            input.extend_from_slice(&account.data);
+            input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+            input.resize(input.len().next_multiple_of(8), 0);
+            input.extend_from_slice(&u64::MAX.to_le_bytes());
+        }
+        input.extend_from_slice(&(instruction.data.len() as u64).to_le_bytes());
+        input.extend_from_slice(&instruction.data);
+        input.extend_from_slice(instruction.program_id.as_ref());
+
+        // The entrypoint expects the input to be 8 byte aligned
+        let mut buffer = vec![0_u64; input.len().div_ceil(8)];
+        bytemuck::cast_slice_mut::<u64, u8>(&mut buffer)[..input.len()].copy_from_slice(&input);
+        let buffer = bytemuck::cast_slice_mut::<u64, u8>(&mut buffer);
+        {
+            let (program_id, accounts, data) = unsafe { deserialize(buffer.as_mut_ptr()) };
+            crate::process_instruction(program_id, &accounts, data)?;
+        }
+
+        // Read back the owner, lamports, data length and data of every account, which are stored
+        // in that order ahead of the original data
+        for (key, data_offset) in account_offsets {
+            let read_u64 =
+                |offset: usize| u64::from_le_bytes(buffer[offset..offset + 8].try_into().unwrap());
+            let data_len = read_u64(data_offset - 8) as usize;
+            self.accounts.insert(
+                key,
+                TestAccount {
+                    owner: Pubkey::try_from(&buffer[data_offset - 48..data_offset - 16]).unwrap(),
+                    lamports: read_u64(data_offset - 16),
+                    data: buffer[data_offset..data_offset + data_len].to_vec(),
+                },
+            );
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_process_instruction_with_test_market() {
+    use super::create_change_market_status_instruction;
+
+    let mut test_market = TestMarket::new();
+    let trader = test_market.add_trader(5, 10);
+    assert_eq!(
+        test_market
+            .market_data()
+            .get_trader_state(&trader)
+            .unwrap()
+            .quote_lots_free,
+        QuoteLots::new(10)
+    );
+    assert_eq!(
+        test_market.token_balance(&test_market.header().quote_params.vault_key),
+        10
+    );
+
+    let authority = test_market.authority;
+    test_market
+        .process(&create_change_market_status_instruction(
+            &authority,
+            &test_market.market,
+            MarketStatus::Paused,
+        ))
+        .unwrap();
+    assert_eq!(test_market.header().status, MarketStatus::Paused as u64);
+    // Failed instructions leave the accounts untouched
+    assert!(test_market
+        .process(&create_change_market_status_instruction(
+            &Pubkey::new_unique(),
+            &test_market.market,
+            MarketStatus::Active,
+        ))
+        .is_err());
+    assert_eq!(test_market.header().status, MarketStatus::Paused as u64);
+}
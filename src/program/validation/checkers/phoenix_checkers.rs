@@ -1,8 +1,8 @@
 use crate::program::{
     error::assert_with_msg,
-    get_discriminant, get_seat_address,
+    get_discriminant, get_governance_proposal_address, get_seat_address,
     status::{MarketStatus, SeatApprovalStatus},
-    MarketHeader, MarketSizeParams, PhoenixError, Seat,
+    GovernanceProposal, MarketHeader, MarketRole, MarketSizeParams, PhoenixError, Seat,
 };
 use sokoban::node_allocator::ZeroCopy;
 use solana_program::{
@@ -89,6 +89,19 @@ impl<'a, 'info> MarketAccountInfo<'a, 'info> {
         )
     }
 
+    pub(crate) fn assert_authority_can_cancel(&self) -> ProgramResult {
+        let header = self.get_header()?;
+        let status = MarketStatus::from(header.status);
+        assert_with_msg(
+            status.authority_can_cancel(),
+            ProgramError::InvalidAccountData,
+            &format!(
+                "Authority withdrawal is not allowed, market status is {}",
+                status
+            ),
+        )
+    }
+
     pub(crate) fn assert_valid_authority(&self, authority: &Pubkey) -> ProgramResult {
         let header = self.get_header()?;
         assert_with_msg(
@@ -98,6 +111,24 @@ impl<'a, 'info> MarketAccountInfo<'a, 'info> {
         )
     }
 
+    pub(crate) fn assert_valid_role(&self, signer: &Pubkey, role: MarketRole) -> ProgramResult {
+        let header = self.get_header()?;
+        assert_with_msg(
+            header.has_role(signer, role),
+            PhoenixError::InvalidMarketAuthority,
+            &format!("Signer is neither the market authority nor the {:?}", role),
+        )
+    }
+
+    pub(crate) fn assert_not_governance_timelocked(&self) -> ProgramResult {
+        let header = self.get_header()?;
+        assert_with_msg(
+            !header.is_governance_timelocked(),
+            PhoenixError::GovernanceTimelocked,
+            "Market is governance timelocked, the action must be proposed",
+        )
+    }
+
     pub(crate) fn assert_valid_successor(&self, successor: &Pubkey) -> ProgramResult {
         let header = self.get_header()?;
         assert_with_msg(
@@ -273,3 +304,54 @@ impl<'a, 'info> Deref for SeatAccountInfo<'a, 'info> {
         self.info
     }
 }
+
+#[derive(Clone)]
+pub(crate) struct GovernanceProposalAccountInfo<'a, 'info> {
+    pub(crate) info: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> GovernanceProposalAccountInfo<'a, 'info> {
+    pub(crate) fn new(
+        info: &'a AccountInfo<'info>,
+        market: &Pubkey,
+    ) -> Result<GovernanceProposalAccountInfo<'a, 'info>, ProgramError> {
+        assert_with_msg(
+            info.owner == &crate::id(),
+            ProgramError::IllegalOwner,
+            "Proposal must be owned by the Phoenix program",
+        )?;
+        let proposal_bytes = info.try_borrow_data()?;
+        let proposal = GovernanceProposal::load_bytes(&proposal_bytes)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        assert_with_msg(
+            proposal.discriminant == get_discriminant::<GovernanceProposal>()?,
+            ProgramError::InvalidAccountData,
+            "Invalid discriminant for proposal",
+        )?;
+        assert_with_msg(
+            &proposal.market == market,
+            ProgramError::InvalidAccountData,
+            "Market on proposal does not match market in instruction",
+        )?;
+        let (proposal_address, _) = get_governance_proposal_address(market, proposal.proposal_id);
+        assert_with_msg(
+            &proposal_address == info.key,
+            ProgramError::InvalidInstructionData,
+            "Invalid address for proposal",
+        )?;
+        Ok(Self { info })
+    }
+
+    pub(crate) fn load(&self) -> Result<Ref<'_, GovernanceProposal>, ProgramError> {
+        let data = self.info.try_borrow_data()?;
+        Ok(Ref::map(data, |data| {
+            GovernanceProposal::load_bytes(data).unwrap()
+        }))
+    }
+}
+
+impl<'a, 'info> AsRef<AccountInfo<'info>> for GovernanceProposalAccountInfo<'a, 'info> {
+    fn as_ref(&self) -> &AccountInfo<'info> {
+        self.info
+    }
+}
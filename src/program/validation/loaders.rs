@@ -9,14 +9,16 @@
 //! current instruction.
 
 use super::checkers::{
-    phoenix_checkers::{MarketAccountInfo, SeatAccountInfo},
+    phoenix_checkers::{GovernanceProposalAccountInfo, MarketAccountInfo, SeatAccountInfo},
     MintAccountInfo, TokenAccountInfo, PDA,
 };
 use crate::{
     phoenix_log_authority,
     program::{
+        assert_with_msg,
+        status::{MarketStatus, SeatApprovalStatus},
         validation::checkers::{EmptyAccount, Program, Signer},
-        MarketHeader, TokenParams,
+        GovernanceAction, MarketHeader, MarketRole, PhoenixError, TokenParams,
     },
 };
 use core::slice::Iter;
@@ -36,6 +38,13 @@ pub fn get_seat_address(market: &Pubkey, trader: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[b"seat", market.as_ref(), trader.as_ref()], &crate::id())
 }
 
+pub fn get_governance_proposal_address(market: &Pubkey, proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"proposal", market.as_ref(), &proposal_id.to_le_bytes()],
+        &crate::id(),
+    )
+}
+
 pub(crate) struct PhoenixLogContext<'a, 'info> {
     pub(crate) phoenix_program: Program<'a, 'info>,
     pub(crate) log_authority: PDA<'a, 'info>,
@@ -323,7 +332,7 @@ impl<'a, 'info> AuthorizedActionContext<'a, 'info> {
             market_info,
             signer: authority,
         } = market_context;
-        market_info.assert_valid_authority(authority.key)?;
+        market_info.assert_valid_role(authority.key, MarketRole::SeatManager)?;
         let (base_params, quote_params) = {
             let header = market_info.get_header()?;
             (header.base_params, header.quote_params)
@@ -353,15 +362,311 @@ impl<'a, 'info> AuthorizedActionContext<'a, 'info> {
     }
 }
 
-pub(crate) struct ChangeMarketStatusContext<'a, 'info> {
-    pub(crate) receiver: Option<&'a AccountInfo<'info>>,
+pub(crate) struct AuthorizedBatchActionContext<'a, 'info> {
+    pub(crate) base_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) quote_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) token_program: Program<'a, 'info>,
+    /// Groups of (trader, seat, base_account, quote_account) of the traders to act on
+    pub(crate) trader_accounts: &'a [AccountInfo<'info>],
 }
 
-impl<'a, 'info> ChangeMarketStatusContext<'a, 'info> {
-    pub(crate) fn load(accounts: &'a [AccountInfo<'info>]) -> Result<Self, ProgramError> {
+impl<'a, 'info> AuthorizedBatchActionContext<'a, 'info> {
+    pub(crate) const ACCOUNTS_PER_TRADER: usize = 4;
+
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_role(authority.key, MarketRole::SeatManager)?;
+        let (base_params, quote_params) = {
+            let header = market_info.get_header()?;
+            (header.base_params, header.quote_params)
+        };
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            base_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &base_params.mint_key,
+                &base_params.vault_key,
+                &base_params.vault_key,
+            )?,
+            quote_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &quote_params.mint_key,
+                &quote_params.vault_key,
+                &quote_params.vault_key,
+            )?,
+            token_program: Program::new(next_account_info(account_iter)?, &spl_token::id())?,
+            trader_accounts: account_iter.as_slice(),
+        };
+        assert_with_msg(
+            ctx.trader_accounts
+                .chunks_exact(Self::ACCOUNTS_PER_TRADER)
+                .remainder()
+                .is_empty(),
+            ProgramError::NotEnoughAccountKeys,
+            "Expected a trader, seat, base account and quote account for each trader",
+        )?;
+        Ok(ctx)
+    }
+
+    /// Validates a (trader, seat, base_account, quote_account) group in the same way as
+    /// `AuthorizedActionContext`, and returns the trader with its vault context
+    pub(crate) fn load_trader(
+        &self,
+        market_info: &MarketAccountInfo<'a, 'info>,
+        trader_accounts: &'a [AccountInfo<'info>],
+    ) -> Result<(&'a AccountInfo<'info>, PhoenixVaultContext<'a, 'info>), ProgramError> {
+        let (base_params, quote_params) = {
+            let header = market_info.get_header()?;
+            (header.base_params, header.quote_params)
+        };
+        let account_iter = &mut trader_accounts.iter();
+        let trader = next_account_info(account_iter)?;
+        SeatAccountInfo::new_with_context(
+            next_account_info(account_iter)?,
+            market_info.key,
+            trader.key,
+            false,
+        )?;
+        let vault_context = PhoenixVaultContext {
+            base_account: TokenAccountInfo::new_with_owner(
+                next_account_info(account_iter)?,
+                &base_params.mint_key,
+                trader.key,
+            )?,
+            quote_account: TokenAccountInfo::new_with_owner(
+                next_account_info(account_iter)?,
+                &quote_params.mint_key,
+                trader.key,
+            )?,
+            base_vault: self.base_vault.clone(),
+            quote_vault: self.quote_vault.clone(),
+            token_program: self.token_program.clone(),
+        };
+        Ok((trader, vault_context))
+    }
+}
+
+pub(crate) struct ForceWithdrawFundsContext<'a, 'info> {
+    pub(crate) base_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) quote_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) token_program: Program<'a, 'info>,
+    /// Triples of (seat, base_account, quote_account) of the traders to withdraw funds for
+    pub(crate) trader_accounts: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> ForceWithdrawFundsContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+        market_info.assert_authority_can_cancel()?;
+        let (base_params, quote_params) = {
+            let header = market_info.get_header()?;
+            (header.base_params, header.quote_params)
+        };
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            base_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &base_params.mint_key,
+                &base_params.vault_key,
+                &base_params.vault_key,
+            )?,
+            quote_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &quote_params.mint_key,
+                &quote_params.vault_key,
+                &quote_params.vault_key,
+            )?,
+            token_program: Program::new(next_account_info(account_iter)?, &spl_token::id())?,
+            trader_accounts: account_iter.as_slice(),
+        };
+        assert_with_msg(
+            ctx.trader_accounts.chunks_exact(3).remainder().is_empty(),
+            ProgramError::NotEnoughAccountKeys,
+            "Expected a seat, a base account and a quote account for each trader",
+        )?;
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct ChangeSelfTradePreventionGroupContext<'a, 'info> {
+    pub(crate) trader: Pubkey,
+    _seat: SeatAccountInfo<'a, 'info>,
+    _group_authority: Option<Signer<'a, 'info>>,
+}
+
+impl<'a, 'info> ChangeSelfTradePreventionGroupContext<'a, 'info> {
+    /// Loads the accounts for a trader changing their own self trade prevention group.
+    /// Joining a group requires a signature from the group key.
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+        group: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: trader,
+        } = market_context;
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            trader: *trader.key,
+            _seat: SeatAccountInfo::new_with_context(
+                next_account_info(account_iter)?,
+                market_info.key,
+                trader.key,
+                true,
+            )?,
+            _group_authority: if group != &Pubkey::default() {
+                Some(Signer::new_with_key(
+                    next_account_info(account_iter)?,
+                    group,
+                )?)
+            } else {
+                None
+            },
+        };
+        Ok(ctx)
+    }
+
+    /// Loads the accounts for the market authority changing the self trade prevention group of a trader
+    pub(crate) fn load_authorized(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+        let account_iter = &mut accounts.iter();
+        let trader_info = next_account_info(account_iter)?;
+        let ctx = Self {
+            trader: *trader_info.key,
+            _seat: SeatAccountInfo::new_with_context(
+                next_account_info(account_iter)?,
+                market_info.key,
+                trader_info.key,
+                false,
+            )?,
+            _group_authority: None,
+        };
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct CloseMarketContext<'a, 'info> {
+    pub(crate) receiver: &'a AccountInfo<'info>,
+    pub(crate) base_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) quote_vault: TokenAccountInfo<'a, 'info>,
+    pub(crate) token_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> CloseMarketContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+        let (status, base_params, quote_params) = {
+            let header = market_info.get_header()?;
+            (
+                MarketStatus::from(header.status),
+                header.base_params,
+                header.quote_params,
+            )
+        };
+        assert_with_msg(
+            status == MarketStatus::Tombstoned,
+            ProgramError::InvalidAccountData,
+            &format!("Market must be Tombstoned to be closed, found {}", status),
+        )?;
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            receiver: next_account_info(account_iter)?,
+            base_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &base_params.mint_key,
+                &base_params.vault_key,
+                &base_params.vault_key,
+            )?,
+            quote_vault: TokenAccountInfo::new_with_owner_and_key(
+                next_account_info(account_iter)?,
+                &quote_params.mint_key,
+                &quote_params.vault_key,
+                &quote_params.vault_key,
+            )?,
+            token_program: Program::new(next_account_info(account_iter)?, &spl_token::id())?,
+        };
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct CloseSeatContext<'a, 'info> {
+    pub(crate) seat: SeatAccountInfo<'a, 'info>,
+    pub(crate) receiver: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CloseSeatContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
         let account_iter = &mut accounts.iter();
         let ctx = Self {
-            receiver: next_account_info(account_iter).ok(),
+            seat: SeatAccountInfo::new(next_account_info(account_iter)?, market_info.key)?,
+            receiver: next_account_info(account_iter)?,
+        };
+        let status = SeatApprovalStatus::from(ctx.seat.load_mut()?.approval_status);
+        assert_with_msg(
+            status == SeatApprovalStatus::Retired,
+            PhoenixError::InvalidSeatStatus,
+            &format!("Seat must be retired to be closed, found {}", status),
+        )?;
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct ExpandMarketContext<'a, 'info> {
+    pub(crate) payer: Signer<'a, 'info>,
+    pub(crate) system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> ExpandMarketContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            payer: Signer::new_payer(next_account_info(account_iter)?)?,
+            system_program: Program::new(next_account_info(account_iter)?, &system_program::id())?,
         };
         Ok(ctx)
     }
@@ -383,7 +688,7 @@ impl<'a, 'info> AuthorizedSeatRequestContext<'a, 'info> {
             market_info,
             signer: authority,
         } = market_context;
-        market_info.assert_valid_authority(authority.key)?;
+        market_info.assert_valid_role(authority.key, MarketRole::SeatManager)?;
 
         let account_iter = &mut accounts.iter();
         let ctx = Self {
@@ -399,9 +704,84 @@ impl<'a, 'info> AuthorizedSeatRequestContext<'a, 'info> {
 pub(crate) struct RequestSeatContext<'a, 'info> {
     pub(crate) seat: EmptyAccount<'a, 'info>,
     pub(crate) system_program: Program<'a, 'info>,
+    /// This is only loaded if the market charges a lamport fee for permissionless seats
+    pub(crate) fee_recipient: Option<&'a AccountInfo<'info>>,
+    /// This is only loaded if the market charges a quote token fee for permissionless seats
+    pub(crate) seat_fee_context: Option<SeatFeeContext<'a, 'info>>,
+}
+
+pub(crate) struct SeatFeeContext<'a, 'info> {
+    pub(crate) quote_account: TokenAccountInfo<'a, 'info>,
+    pub(crate) fee_recipient_quote_account: TokenAccountInfo<'a, 'info>,
+    pub(crate) token_program: Program<'a, 'info>,
 }
 
 impl<'a, 'info> RequestSeatContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: trader,
+        } = market_context;
+        market_info.assert_post_allowed()?;
+        let (seat_approval_params, quote_params, fee_recipient) = {
+            let header = market_info.get_header()?;
+            (
+                header.seat_approval_params,
+                header.quote_params,
+                header.fee_recipient,
+            )
+        };
+        let charges_fee = |fee: u64| seat_approval_params.is_permissionless() && fee > 0;
+
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            seat: EmptyAccount::new(next_account_info(account_iter)?)?,
+            system_program: Program::new(next_account_info(account_iter)?, &system_program::id())?,
+            fee_recipient: if charges_fee(seat_approval_params.seat_fee_in_lamports) {
+                let fee_recipient_info = next_account_info(account_iter)?;
+                assert_with_msg(
+                    fee_recipient_info.key == &fee_recipient,
+                    ProgramError::InvalidAccountData,
+                    "Invalid fee recipient",
+                )?;
+                Some(fee_recipient_info)
+            } else {
+                None
+            },
+            seat_fee_context: if charges_fee(seat_approval_params.seat_fee_in_quote_lots) {
+                Some(SeatFeeContext {
+                    quote_account: TokenAccountInfo::new_with_owner(
+                        next_account_info(account_iter)?,
+                        &quote_params.mint_key,
+                        trader.key,
+                    )?,
+                    fee_recipient_quote_account: TokenAccountInfo::new_with_owner(
+                        next_account_info(account_iter)?,
+                        &quote_params.mint_key,
+                        &fee_recipient,
+                    )?,
+                    token_program: Program::new(
+                        next_account_info(account_iter)?,
+                        &spl_token::id(),
+                    )?,
+                })
+            } else {
+                None
+            },
+        };
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct RequestSeatWithProofContext<'a, 'info> {
+    pub(crate) seat: EmptyAccount<'a, 'info>,
+    pub(crate) system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> RequestSeatWithProofContext<'a, 'info> {
     pub(crate) fn load(
         market_context: &PhoenixMarketContext<'a, 'info>,
         accounts: &'a [AccountInfo<'info>],
@@ -418,6 +798,69 @@ impl<'a, 'info> RequestSeatContext<'a, 'info> {
     }
 }
 
+pub(crate) struct EvictInactiveTraderContext<'a, 'info> {
+    pub(crate) seat: SeatAccountInfo<'a, 'info>,
+}
+
+impl<'a, 'info> EvictInactiveTraderContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext { market_info, .. } = market_context;
+        assert_with_msg(
+            market_info.get_header()?.evict_inactive_traders != 0,
+            PhoenixError::EvictionError,
+            "Market does not evict inactive traders",
+        )?;
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            seat: SeatAccountInfo::new(next_account_info(account_iter)?, market_info.key)?,
+        };
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct WithdrawEvictedFundsContext<'a, 'info> {
+    pub(crate) seat: SeatAccountInfo<'a, 'info>,
+    pub(crate) vault_context: PhoenixVaultContext<'a, 'info>,
+}
+
+impl<'a, 'info> WithdrawEvictedFundsContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer,
+        } = market_context;
+        let (base_params, quote_params) = {
+            let header = market_info.get_header()?;
+            (header.base_params, header.quote_params)
+        };
+        let account_iter = &mut accounts.iter();
+        let seat = SeatAccountInfo::new(next_account_info(account_iter)?, market_info.key)?;
+        let trader = seat.load_mut()?.trader;
+        // Once the market is closed, the authority can sweep the evicted funds to the trader so
+        // that the vaults can be emptied
+        if &trader != signer.key {
+            market_info.assert_valid_authority(signer.key)?;
+            market_info.assert_authority_can_cancel()?;
+        }
+        let ctx = Self {
+            seat,
+            vault_context: PhoenixVaultContext::load_from_iter(
+                account_iter,
+                &base_params,
+                &quote_params,
+                &trader,
+            )?,
+        };
+        Ok(ctx)
+    }
+}
+
 pub(crate) struct ModifySeatContext<'a, 'info> {
     pub(crate) seat: SeatAccountInfo<'a, 'info>,
 }
@@ -431,7 +874,7 @@ impl<'a, 'info> ModifySeatContext<'a, 'info> {
             market_info,
             signer: authority,
         } = market_context;
-        market_info.assert_valid_authority(authority.key)?;
+        market_info.assert_valid_role(authority.key, MarketRole::SeatManager)?;
 
         let account_iter = &mut accounts.iter();
         let ctx = Self {
@@ -489,7 +932,7 @@ impl<'a, 'info> ChangeFeeRecipientContext<'a, 'info> {
             market_info,
             signer: authority,
         } = market_context;
-        market_info.assert_valid_authority(authority.key)?;
+        market_info.assert_valid_role(authority.key, MarketRole::FeeManager)?;
         let current_fee_recipient = {
             let header = market_info.get_header()?;
             header.fee_recipient
@@ -504,3 +947,67 @@ impl<'a, 'info> ChangeFeeRecipientContext<'a, 'info> {
         Ok(ctx)
     }
 }
+
+pub(crate) struct ProposeGovernanceActionContext<'a, 'info> {
+    pub(crate) proposal: EmptyAccount<'a, 'info>,
+    pub(crate) system_program: Program<'a, 'info>,
+}
+
+impl<'a, 'info> ProposeGovernanceActionContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+        let account_iter = &mut accounts.iter();
+        let ctx = Self {
+            proposal: EmptyAccount::new(next_account_info(account_iter)?)?,
+            system_program: Program::new(next_account_info(account_iter)?, &system_program::id())?,
+        };
+        Ok(ctx)
+    }
+}
+
+pub(crate) struct GovernanceProposalContext<'a, 'info> {
+    pub(crate) proposal: GovernanceProposalAccountInfo<'a, 'info>,
+    /// This is only loaded when a fee recipient change is executed, and must be passed if there are
+    /// uncollected fees
+    pub(crate) previous_fee_recipient: Option<Signer<'a, 'info>>,
+}
+
+impl<'a, 'info> GovernanceProposalContext<'a, 'info> {
+    pub(crate) fn load(
+        market_context: &PhoenixMarketContext<'a, 'info>,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let PhoenixMarketContext {
+            market_info,
+            signer: authority,
+        } = market_context;
+        market_info.assert_valid_authority(authority.key)?;
+        let current_fee_recipient = market_info.get_header()?.fee_recipient;
+        let account_iter = &mut accounts.iter();
+        let proposal =
+            GovernanceProposalAccountInfo::new(next_account_info(account_iter)?, market_info.key)?;
+        let is_fee_recipient_change = matches!(
+            proposal.load()?.get_action()?,
+            GovernanceAction::ChangeFeeRecipient { .. }
+        );
+        // If the previous fee recipient is passed for a fee recipient change, it must be valid
+        let previous_fee_recipient = match next_account_info(account_iter) {
+            Ok(info) if is_fee_recipient_change => {
+                Some(Signer::new_with_key(info, &current_fee_recipient)?)
+            }
+            _ => None,
+        };
+        let ctx = Self {
+            proposal,
+            previous_fee_recipient,
+        };
+        Ok(ctx)
+    }
+}
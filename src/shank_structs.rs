@@ -7,7 +7,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 
 use crate::{
-    program::{MarketSizeParams, TokenParams},
+    program::{MarketSizeParams, SeatApprovalParams, TokenParams},
     state::{SelfTradeBehavior, Side},
 };
 
@@ -28,7 +28,20 @@ struct MarketHeader {
     successor: Pubkey,
     raw_base_units_per_base_unit: u32,
     _padding1: u32,
-    _padding2: [u64; 32],
+    seat_approval_params: SeatApprovalParams,
+    seat_allowlist_root: [u8; 32],
+    evict_inactive_traders: u64,
+    pause_guardian: Pubkey,
+    seat_manager: Pubkey,
+    fee_manager: Pubkey,
+    governance_delay_in_slots: u64,
+    governance_delay_in_seconds: u64,
+    governance_proposal_count: u64,
+    successor_expiry_unix_timestamp: i64,
+    scheduled_status: u64,
+    scheduled_status_unix_timestamp: i64,
+    scheduled_by_authority: u64,
+    _padding2: [u64; 5],
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug)]
@@ -43,6 +56,9 @@ enum OrderPacket {
         last_valid_slot: Option<u64>,
         last_valid_unix_timestamp_in_seconds: Option<u64>,
         fail_silently_on_insufficient_funds: bool,
+        link_id: Option<u32>,
+        time_to_live_in_slots: Option<u64>,
+        time_to_live_in_seconds: Option<u64>,
     },
     Limit {
         side: Side,
@@ -55,6 +71,11 @@ enum OrderPacket {
         last_valid_slot: Option<u64>,
         last_valid_unix_timestamp_in_seconds: Option<u64>,
         fail_silently_on_insufficient_funds: bool,
+        min_base_lots_to_fill: Option<u64>,
+        all_or_none_on_cross: bool,
+        link_id: Option<u32>,
+        time_to_live_in_slots: Option<u64>,
+        time_to_live_in_seconds: Option<u64>,
     },
     ImmediateOrCancel {
         side: Side,
@@ -70,4 +91,17 @@ enum OrderPacket {
         last_valid_slot: Option<u64>,
         last_valid_unix_timestamp_in_seconds: Option<u64>,
     },
+    MarketToLimit {
+        side: Side,
+        price_in_ticks: Option<u64>,
+        num_base_lots: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        match_limit: Option<u64>,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        last_valid_slot: Option<u64>,
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+        time_to_live_in_slots: Option<u64>,
+        time_to_live_in_seconds: Option<u64>,
+    },
 }
@@ -1,5 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 
+use crate::program::PhoenixError;
+
 #[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Side {
     Bid,
@@ -24,7 +26,46 @@ impl Side {
 
 #[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug)]
 pub enum SelfTradeBehavior {
+    /// Fail the entire order.
     Abort,
+    /// Cancel the resting order and continue matching.
     CancelProvide,
+    /// Reduce the resting order and the taker's budget by the crossing size and continue matching.
     DecrementTake,
+    /// Stop matching and cancel the remaining taker quantity. The resting order is left untouched.
+    CancelTake,
+    /// Cancel the resting order, stop matching and cancel the remaining taker quantity.
+    CancelBoth,
+    /// Leave the resting order untouched and continue matching against the orders behind it.
+    /// Any remainder of a limit order that would cross the trader's own orders is not posted.
+    SkipProvide,
+}
+
+/// Determines how the matching engine makes room for a new order when its side of the book is full.
+#[derive(BorshDeserialize, BorshSerialize, Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[repr(u64)]
+pub enum BookFullPolicy {
+    /// Evict the resting order with the least aggressive price.
+    #[default]
+    EvictLeastAggressive,
+    /// Never evict resting orders. The new order fails to be placed.
+    RejectNewOrder,
+    /// Evict the resting order with the smallest notional value (price * size).
+    EvictSmallestNotional,
+    /// Evict the resting order with the lowest order sequence number.
+    EvictOldest,
+}
+
+impl TryFrom<u64> for BookFullPolicy {
+    type Error = PhoenixError;
+
+    fn try_from(policy: u64) -> Result<Self, Self::Error> {
+        match policy {
+            0 => Ok(Self::EvictLeastAggressive),
+            1 => Ok(Self::RejectNewOrder),
+            2 => Ok(Self::EvictSmallestNotional),
+            3 => Ok(Self::EvictOldest),
+            _ => Err(PhoenixError::InvalidBookFullPolicy),
+        }
+    }
 }
@@ -33,6 +33,9 @@ pub(crate) struct InflightOrder {
     /// Number of quote lots paid in fees
     pub quote_lot_fees: QuoteLots,
 
+    /// Price of the most recent resting order that was matched against, if any
+    pub last_matched_price_in_ticks: Option<Ticks>,
+
     pub last_valid_slot: Option<u64>,
 
     pub last_valid_unix_timestamp_in_seconds: Option<u64>,
@@ -61,6 +64,7 @@ impl InflightOrder {
             matched_adjusted_quote_lots: AdjustedQuoteLots::ZERO,
             matched_base_lots: BaseLots::ZERO,
             quote_lot_fees: QuoteLots::ZERO,
+            last_matched_price_in_ticks: None,
             last_valid_slot,
             last_valid_unix_timestamp_in_seconds,
         }
@@ -74,6 +78,13 @@ impl InflightOrder {
             && !self.should_terminate
     }
 
+    /// Stops matching and cancels any quantity of the order that has not been matched yet
+    #[inline(always)]
+    pub(crate) fn cancel_remaining_budget(&mut self) {
+        self.base_lot_budget = BaseLots::ZERO;
+        self.should_terminate = true;
+    }
+
     pub(crate) fn process_match(
         &mut self,
         matched_adjusted_quote_lots: AdjustedQuoteLots,
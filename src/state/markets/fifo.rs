@@ -1,8 +1,11 @@
 use super::Market;
 use super::MarketEvent;
 use super::OrderId;
+use super::RedBlackTreeMap;
 use super::RestingOrder;
 use super::WritableMarket;
+use super::{get_tree_size, load_tree, load_tree_mut};
+use crate::program::PhoenixError;
 use crate::quantities::AdjustedQuoteLots;
 use crate::quantities::BaseLots;
 use crate::quantities::BaseLotsPerBaseUnit;
@@ -16,9 +19,12 @@ use crate::state::matching_engine_response::MatchingEngineResponse;
 use crate::state::*;
 use borsh::{BorshDeserialize, BorshSerialize};
 use bytemuck::{Pod, Zeroable};
-use sokoban::node_allocator::{NodeAllocatorMap, OrderedNodeAllocatorMap, ZeroCopy, SENTINEL};
-use sokoban::{FromSlice, RedBlackTree};
+use sokoban::node_allocator::{OrderedNodeAllocatorMap, SENTINEL};
+use solana_program::pubkey::Pubkey;
 use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::ops::{Deref, DerefMut};
 
 #[repr(C)]
 #[derive(
@@ -106,6 +112,12 @@ pub struct FIFORestingOrder {
     pub num_base_lots: BaseLots, // Number of base lots quoted
     pub last_valid_slot: u64,
     pub last_valid_unix_timestamp_in_seconds: u64,
+    /// Nonzero if the order is one leg of a one-cancels-other pair, in which case it is the location
+    /// of the other leg in the book: the side in the high bit and the address of its node in the
+    /// tree of that side in the remaining bits. When either leg is matched, the other leg is reduced
+    /// by the amount that was matched.
+    pub linked_order: u32,
+    _padding: u32,
 }
 
 impl FIFORestingOrder {
@@ -115,6 +127,8 @@ impl FIFORestingOrder {
             num_base_lots,
             last_valid_slot: 0,
             last_valid_unix_timestamp_in_seconds: 0,
+            linked_order: 0,
+            _padding: 0,
         }
     }
 
@@ -129,6 +143,8 @@ impl FIFORestingOrder {
             num_base_lots,
             last_valid_slot: last_valid_slot.unwrap_or(0),
             last_valid_unix_timestamp_in_seconds: last_valid_unix_timestamp_in_seconds.unwrap_or(0),
+            linked_order: 0,
+            _padding: 0,
         }
     }
 
@@ -142,6 +158,8 @@ impl FIFORestingOrder {
             num_base_lots,
             last_valid_slot,
             last_valid_unix_timestamp_in_seconds: 0,
+            linked_order: 0,
+            _padding: 0,
         }
     }
 
@@ -155,10 +173,32 @@ impl FIFORestingOrder {
             num_base_lots,
             last_valid_slot: 0,
             last_valid_unix_timestamp_in_seconds,
+            linked_order: 0,
+            _padding: 0,
         }
     }
 }
 
+/// The high bit of a resting order location is set for asks
+const ASK_ORDER_LOCATION: u32 = 1 << 31;
+
+#[inline(always)]
+fn encode_order_location(side: Side, addr: u32) -> u32 {
+    match side {
+        Side::Bid => addr,
+        Side::Ask => addr | ASK_ORDER_LOCATION,
+    }
+}
+
+#[inline(always)]
+fn decode_order_location(location: u32) -> (Side, u32) {
+    if location & ASK_ORDER_LOCATION != 0 {
+        (Side::Ask, location & !ASK_ORDER_LOCATION)
+    } else {
+        (Side::Bid, location)
+    }
+}
+
 impl RestingOrder for FIFORestingOrder {
     fn size(&self) -> u64 {
         self.num_base_lots.as_u64()
@@ -187,25 +227,20 @@ impl RestingOrder for FIFORestingOrder {
     }
 }
 
+/// Fixed-size fields of a `FIFOMarket`, stored at the start of the market data ahead of the
+/// order book and the trader map.
 #[repr(C)]
-#[derive(Default, Copy, Clone, Zeroable)]
-pub struct FIFOMarket<
-    MarketTraderId: Debug
-        + PartialOrd
-        + Ord
-        + Default
-        + Copy
-        + Clone
-        + Zeroable
-        + Pod
-        + BorshDeserialize
-        + BorshSerialize,
-    const BIDS_SIZE: usize,
-    const ASKS_SIZE: usize,
-    const NUM_SEATS: usize,
-> {
+#[derive(Default, Debug, Copy, Clone, Zeroable, Pod)]
+pub struct FIFOMarketState {
+    /// Policy used to make room for new orders when a side of the book is full. See `BookFullPolicy`.
+    book_full_policy: u64,
+
+    /// Minimum number of ticks by which a new order must improve on a resting order's price
+    /// for the resting order to be evicted. Values of 0 and 1 are equivalent.
+    min_eviction_improvement_in_ticks: Ticks,
+
     /// Padding
-    pub _padding: [u64; 32],
+    pub _padding: [u64; 30],
 
     /// Number of base lots in a base unit. For example, if the lot size is 0.001 SOL, then base_lots_per_base_unit is 1000.
     pub base_lots_per_base_unit: BaseLotsPerBaseUnit,
@@ -224,36 +259,57 @@ pub struct FIFOMarket<
 
     /// Amount of unclaimed fees accrued to the market, in quote lots.
     unclaimed_quote_lot_fees: QuoteLots,
+}
+
+/// A FIFO market loaded from account data. The capacities of the bids, the asks and the trader
+/// map are supplied at load time, and the data is laid out as a `FIFOMarketState` followed by the
+/// three trees.
+///
+/// The state and the trees are borrowed mutably by default. `FIFOMarketRef` borrows them
+/// immutably and only implements `Market`.
+///
+/// The fields of `FIFOMarketState` are accessible directly on the market through `Deref`.
+pub struct FIFOMarket<
+    'a,
+    MarketTraderId: Debug
+        + PartialOrd
+        + Ord
+        + Default
+        + Copy
+        + Clone
+        + Zeroable
+        + Pod
+        + BorshDeserialize
+        + BorshSerialize,
+    State = &'a mut FIFOMarketState,
+    Book = &'a mut dyn RedBlackTreeMap<FIFOOrderId, FIFORestingOrder>,
+    Traders = &'a mut dyn RedBlackTreeMap<MarketTraderId, TraderState>,
+> {
+    state: State,
 
     /// Red-black tree representing the bids in the order book.
-    pub bids: RedBlackTree<FIFOOrderId, FIFORestingOrder, BIDS_SIZE>,
+    pub bids: Book,
 
     /// Red-black tree representing the asks in the order book.
-    pub asks: RedBlackTree<FIFOOrderId, FIFORestingOrder, ASKS_SIZE>,
+    pub asks: Book,
 
     /// Red-black tree representing the authorized makers in the market.
-    pub traders: RedBlackTree<MarketTraderId, TraderState, NUM_SEATS>,
-}
+    pub traders: Traders,
 
-unsafe impl<
-        MarketTraderId: Debug
-            + PartialOrd
-            + Ord
-            + Default
-            + Copy
-            + Clone
-            + Zeroable
-            + Pod
-            + BorshDeserialize
-            + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
-    > Pod for FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
-{
+    _phantom: PhantomData<&'a MarketTraderId>,
 }
 
+/// A read-only FIFO market loaded from account data.
+pub type FIFOMarketRef<'a, MarketTraderId> = FIFOMarket<
+    'a,
+    MarketTraderId,
+    &'a FIFOMarketState,
+    &'a dyn RedBlackTreeMap<FIFOOrderId, FIFORestingOrder>,
+    &'a dyn RedBlackTreeMap<MarketTraderId, TraderState>,
+>;
+
 impl<
+        'a,
         MarketTraderId: Debug
             + PartialOrd
             + Ord
@@ -264,21 +320,20 @@ impl<
             + Pod
             + BorshDeserialize
             + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
-    > FromSlice for FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
+        State: Deref<Target = FIFOMarketState>,
+        Book,
+        Traders,
+    > Deref for FIFOMarket<'a, MarketTraderId, State, Book, Traders>
 {
-    fn new_from_slice(data: &mut [u8]) -> &mut Self {
-        let market = Self::load_mut_bytes(data).unwrap();
-        assert_eq!(market.base_lots_per_base_unit, BaseLotsPerBaseUnit::ZERO);
-        assert_eq!(market.order_sequence_number, 0);
-        market.initialize();
-        market
+    type Target = FIFOMarketState;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
     }
 }
 
 impl<
+        'a,
         MarketTraderId: Debug
             + PartialOrd
             + Ord
@@ -289,14 +344,18 @@ impl<
             + Pod
             + BorshDeserialize
             + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
-    > ZeroCopy for FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
+        State: DerefMut<Target = FIFOMarketState>,
+        Book,
+        Traders,
+    > DerefMut for FIFOMarket<'a, MarketTraderId, State, Book, Traders>
 {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
 }
 
 impl<
+        'a,
         MarketTraderId: Debug
             + PartialOrd
             + Ord
@@ -307,14 +366,19 @@ impl<
             + Pod
             + BorshDeserialize
             + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
+        State: Deref<Target = FIFOMarketState>,
+        Book: Deref<Target = dyn RedBlackTreeMap<FIFOOrderId, FIFORestingOrder> + 'a>,
+        Traders: Deref<Target = dyn RedBlackTreeMap<MarketTraderId, TraderState> + 'a>,
     > Market<MarketTraderId, FIFOOrderId, FIFORestingOrder, OrderPacket>
-    for FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
+    for FIFOMarket<'a, MarketTraderId, State, Book, Traders>
 {
     fn get_data_size(&self) -> usize {
-        std::mem::size_of::<Self>()
+        FIFOMarket::<MarketTraderId>::get_size(
+            self.bids.capacity(),
+            self.asks.capacity(),
+            self.traders.capacity(),
+        )
+        .unwrap_or_default()
     }
 
     fn get_taker_fee_bps(&self) -> u64 {
@@ -333,6 +397,14 @@ impl<
         self.order_sequence_number
     }
 
+    fn get_book_full_policy(&self) -> Result<BookFullPolicy, PhoenixError> {
+        BookFullPolicy::try_from(self.book_full_policy)
+    }
+
+    fn get_min_eviction_improvement_in_ticks(&self) -> Ticks {
+        self.min_eviction_improvement_in_ticks
+    }
+
     fn get_collected_fee_amount(&self) -> QuoteLots {
         self.collected_quote_lot_fees
     }
@@ -342,7 +414,7 @@ impl<
     }
 
     fn get_registered_traders(&self) -> &dyn OrderedNodeAllocatorMap<MarketTraderId, TraderState> {
-        &self.traders as &dyn OrderedNodeAllocatorMap<MarketTraderId, TraderState>
+        self.traders.as_map()
     }
 
     fn get_trader_state(&self, trader_id: &MarketTraderId) -> Option<&TraderState> {
@@ -370,13 +442,14 @@ impl<
     #[inline(always)]
     fn get_book(&self, side: Side) -> &dyn OrderedNodeAllocatorMap<FIFOOrderId, FIFORestingOrder> {
         match side {
-            Side::Bid => &self.bids,
-            Side::Ask => &self.asks,
+            Side::Bid => self.bids.as_map(),
+            Side::Ask => self.asks.as_map(),
         }
     }
 }
 
 impl<
+        'a,
         MarketTraderId: Debug
             + PartialOrd
             + Ord
@@ -387,11 +460,8 @@ impl<
             + Pod
             + BorshDeserialize
             + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
     > WritableMarket<MarketTraderId, FIFOOrderId, FIFORestingOrder, OrderPacket>
-    for FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
+    for FIFOMarket<'a, MarketTraderId>
 {
     fn initialize_with_params(
         &mut self,
@@ -408,10 +478,19 @@ impl<
         self.taker_fee_bps = taker_fee_bps;
     }
 
+    fn set_book_full_policy(
+        &mut self,
+        book_full_policy: BookFullPolicy,
+        min_eviction_improvement_in_ticks: Ticks,
+    ) {
+        self.book_full_policy = book_full_policy as u64;
+        self.min_eviction_improvement_in_ticks = min_eviction_improvement_in_ticks;
+    }
+
     fn get_registered_traders_mut(
         &mut self,
     ) -> &mut dyn OrderedNodeAllocatorMap<MarketTraderId, TraderState> {
-        &mut self.traders as &mut dyn OrderedNodeAllocatorMap<MarketTraderId, TraderState>
+        self.traders.as_map_mut()
     }
 
     fn get_trader_state_mut(&mut self, trader_id: &MarketTraderId) -> Option<&mut TraderState> {
@@ -422,14 +501,43 @@ impl<
         &mut self.traders.get_node_mut(index).value
     }
 
+    fn evict_inactive_trader(&mut self, trader_id: &MarketTraderId) -> Option<TraderState> {
+        if self.traders.len() < self.traders.capacity() {
+            return None;
+        }
+        let trader_index = self.get_trader_index(trader_id)?;
+        let has_open_orders = |market: &Self, index: u32| {
+            [Side::Bid, Side::Ask].iter().any(|side| {
+                market
+                    .get_book(*side)
+                    .iter()
+                    .any(|(_, order)| order.trader_index == index as u64)
+            })
+        };
+        let is_evictable = |state: &TraderState| {
+            state.base_lots_locked == BaseLots::ZERO && state.quote_lots_locked == QuoteLots::ZERO
+        };
+        let trader_state = *self.get_trader_state_from_index(trader_index);
+        if !is_evictable(&trader_state) || has_open_orders(self, trader_index) {
+            return None;
+        }
+        // Ties are broken in favor of the trader being evicted
+        if self.traders.iter().any(|(_, state)| {
+            is_evictable(state) && state.last_activity_slot < trader_state.last_activity_slot
+        }) {
+            return None;
+        }
+        self.traders.remove(trader_id)
+    }
+
     #[inline(always)]
     fn get_book_mut(
         &mut self,
         side: Side,
     ) -> &mut dyn OrderedNodeAllocatorMap<FIFOOrderId, FIFORestingOrder> {
         match side {
-            Side::Bid => &mut self.bids,
-            Side::Ask => &mut self.asks,
+            Side::Bid => self.bids.as_map_mut(),
+            Side::Ask => self.asks.as_map_mut(),
         }
     }
 
@@ -463,6 +571,30 @@ impl<
         )
     }
 
+    fn increase_order(
+        &mut self,
+        trader_id: &MarketTraderId,
+        order_id: &FIFOOrderId,
+        side: Side,
+        size: BaseLots,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
+        get_clock_fn: &mut dyn FnMut() -> (u64, u64),
+    ) -> Option<(FIFOOrderId, MatchingEngineResponse)> {
+        let trader_index = self.get_trader_index(trader_id)?;
+        self.increase_order_inner(
+            trader_index,
+            order_id,
+            side,
+            size,
+            client_order_id,
+            use_only_deposited_funds,
+            record_event_fn,
+            get_clock_fn,
+        )
+    }
+
     fn cancel_all_orders(
         &mut self,
         trader_id: &MarketTraderId,
@@ -529,7 +661,7 @@ impl<
         record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
     ) -> QuoteLots {
         let quote_lot_fees = self.unclaimed_quote_lot_fees;
-        self.collected_quote_lot_fees += self.unclaimed_quote_lot_fees;
+        self.collected_quote_lot_fees += quote_lot_fees;
         self.unclaimed_quote_lot_fees = QuoteLots::ZERO;
         let fees_collected_in_quote_lots = quote_lot_fees;
         record_event_fn(MarketEvent::Fee {
@@ -540,6 +672,7 @@ impl<
 }
 
 impl<
+        'a,
         MarketTraderId: Debug
             + PartialOrd
             + Ord
@@ -550,21 +683,112 @@ impl<
             + Pod
             + BorshDeserialize
             + BorshSerialize,
-        const BIDS_SIZE: usize,
-        const ASKS_SIZE: usize,
-        const NUM_SEATS: usize,
-    > FIFOMarket<MarketTraderId, BIDS_SIZE, ASKS_SIZE, NUM_SEATS>
+    > FIFOMarket<'a, MarketTraderId>
 {
-    pub fn new(
-        tick_size_in_quote_lots_per_base_unit: QuoteLotsPerBaseUnitPerTick,
-        base_lots_per_base_unit: BaseLotsPerBaseUnit,
-    ) -> Self {
-        let mut market = Self::default();
-        market.set_initial_params(
-            tick_size_in_quote_lots_per_base_unit,
-            base_lots_per_base_unit,
-        );
-        market
+    /// Returns the number of bytes needed to store a market with the given capacities.
+    pub fn get_size(bids_size: usize, asks_size: usize, num_seats: usize) -> Option<usize> {
+        let [_, _, (traders_offset, traders_len)] =
+            Self::get_tree_layout(bids_size, asks_size, num_seats)?;
+        traders_offset.checked_add(traders_len)
+    }
+
+    /// Returns the offset and length in bytes of the bids, the asks and the trader map, in that
+    /// order, for a market with the given capacities.
+    fn get_tree_layout(
+        bids_size: usize,
+        asks_size: usize,
+        num_seats: usize,
+    ) -> Option<[(usize, usize); 3]> {
+        let bids_offset = size_of::<FIFOMarketState>();
+        let bids_len = get_tree_size::<FIFOOrderId, FIFORestingOrder>(bids_size)?;
+        let asks_offset = bids_offset.checked_add(bids_len)?;
+        let asks_len = get_tree_size::<FIFOOrderId, FIFORestingOrder>(asks_size)?;
+        let traders_offset = asks_offset.checked_add(asks_len)?;
+        let traders_len = get_tree_size::<MarketTraderId, TraderState>(num_seats)?;
+        Some([
+            (bids_offset, bids_len),
+            (asks_offset, asks_len),
+            (traders_offset, traders_len),
+        ])
+    }
+
+    /// Moves the trees of a market into the layout for larger capacities, in place. Every node
+    /// keeps its index, so order ids, sequence numbers and trader indices are all preserved.
+    ///
+    /// `data` must already be large enough to hold the market with the new capacities. Returns
+    /// None if the data is too small or if any of the new capacities is smaller than the current one.
+    pub fn expand(
+        data: &mut [u8],
+        (bids_size, asks_size, num_seats): (usize, usize, usize),
+        (new_bids_size, new_asks_size, new_num_seats): (usize, usize, usize),
+    ) -> Option<()> {
+        if new_bids_size < bids_size || new_asks_size < asks_size || new_num_seats < num_seats {
+            return None;
+        }
+        let current_layout = Self::get_tree_layout(bids_size, asks_size, num_seats)?;
+        let new_layout = Self::get_tree_layout(new_bids_size, new_asks_size, new_num_seats)?;
+        let new_size = Self::get_size(new_bids_size, new_asks_size, new_num_seats)?;
+        if data.len() < new_size {
+            return None;
+        }
+        // Trees only ever move towards the end of the buffer, so they are moved starting from the
+        // last one to avoid overwriting a tree that has not been moved yet
+        for ((offset, len), (new_offset, _)) in
+            current_layout.iter().zip(new_layout.iter()).skip(1).rev()
+        {
+            data.copy_within(*offset..offset + len, *new_offset);
+        }
+        // Clear the stale bytes left between the trees so that the new nodes start out zeroed
+        for (i, ((_, len), (new_offset, _))) in
+            current_layout.iter().zip(new_layout.iter()).enumerate()
+        {
+            let end = new_layout
+                .get(i + 1)
+                .map_or(new_size, |(next_offset, _)| *next_offset);
+            data[new_offset + len..end].fill(0);
+        }
+        Some(())
+    }
+
+    pub fn load_mut_bytes(
+        data: &'a mut [u8],
+        bids_size: usize,
+        asks_size: usize,
+        num_seats: usize,
+    ) -> Option<Self> {
+        let [(_, bids_len), (_, asks_len), (_, traders_len)] =
+            Self::get_tree_layout(bids_size, asks_size, num_seats)?;
+        let (state, trees) = data.split_at_mut_checked(size_of::<FIFOMarketState>())?;
+        let (bids, trees) = trees.split_at_mut_checked(bids_len)?;
+        let (asks, traders) = trees.split_at_mut_checked(asks_len)?;
+        Some(Self {
+            state: bytemuck::try_from_bytes_mut(state).ok()?,
+            bids: load_tree_mut(bids, bids_size)?,
+            asks: load_tree_mut(asks, asks_size)?,
+            traders: load_tree_mut(traders.get_mut(..traders_len)?, num_seats)?,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Loads a read-only market from a given buffer.
+    pub fn load_bytes(
+        data: &'a [u8],
+        bids_size: usize,
+        asks_size: usize,
+        num_seats: usize,
+    ) -> Option<FIFOMarketRef<'a, MarketTraderId>> {
+        let [(_, bids_len), (_, asks_len), (_, traders_len)] =
+            Self::get_tree_layout(bids_size, asks_size, num_seats)?;
+        let (state, trees) = data.split_at_checked(size_of::<FIFOMarketState>())?;
+        let (bids, trees) = trees.split_at_checked(bids_len)?;
+        let (asks, traders) = trees.split_at_checked(asks_len)?;
+        Some(FIFOMarket {
+            state: bytemuck::try_from_bytes(state).ok()?,
+            bids: load_tree(bids, bids_size)?,
+            asks: load_tree(asks, asks_size)?,
+            traders: load_tree(traders.get(..traders_len)?, num_seats)?,
+            _phantom: PhantomData,
+        })
     }
 
     fn initialize(&mut self) {
@@ -609,6 +833,28 @@ impl<
         )
     }
 
+    #[inline]
+    /// Matched adjusted quote lots are rounded up for buys and down for sells to yield a whole
+    /// number of quote lots
+    fn round_matched_quote_lots(
+        &self,
+        side: Side,
+        matched_adjusted_quote_lots: AdjustedQuoteLots,
+    ) -> QuoteLots {
+        let rounded_adjusted_quote_lots = match side {
+            Side::Bid => self.round_adjusted_quote_lots_up(matched_adjusted_quote_lots),
+            Side::Ask => self.round_adjusted_quote_lots_down(matched_adjusted_quote_lots),
+        };
+        rounded_adjusted_quote_lots / self.base_lots_per_base_unit
+    }
+
+    #[inline]
+    /// Fee in quote lots charged to a taker for the given matched size
+    fn compute_quote_lot_fees(&self, size_in_adjusted_quote_lots: AdjustedQuoteLots) -> QuoteLots {
+        self.round_adjusted_quote_lots_up(self.compute_fee(size_in_adjusted_quote_lots))
+            / self.base_lots_per_base_unit
+    }
+
     #[inline]
     /// Quote lot budget with fees adjusted (buys)
     ///
@@ -739,11 +985,7 @@ impl<
                 .min(trader_state.base_lots_free);
             trader_state.quote_lots_free -= quote_lots_free;
             trader_state.base_lots_free -= base_lots_free;
-            (
-                *trader_state == TraderState::default(),
-                quote_lots_free,
-                base_lots_free,
-            )
+            (trader_state.is_empty(), quote_lots_free, base_lots_free)
         };
         if is_empty && allow_seat_eviction {
             let trader_id = self.get_trader_id_from_index(trader_index);
@@ -809,11 +1051,19 @@ impl<
 
         let (current_slot, current_unix_timestamp) = get_clock_fn();
 
+        // Relative time-in-force is resolved against the clock at the time the order is placed
+        order_packet.resolve_time_to_live(current_slot, current_unix_timestamp);
+
         if order_packet.is_expired(current_slot, current_unix_timestamp) {
             // Do not fail the transaction if the order is expired, but do not place or match the order
             return Some((None, MatchingEngineResponse::default()));
         }
 
+        // A link id of zero is reserved for unlinked orders
+        if order_packet.link_id() == Some(0) {
+            return None;
+        }
+
         let (resting_order, mut matching_engine_response) = if let OrderPacket::PostOnly {
             price_in_ticks,
             reject_post_only,
@@ -890,26 +1140,28 @@ impl<
             let resting_order = self
                 .match_order(
                     &mut inflight_order,
+                    trader_id,
                     trader_index,
                     record_event_fn,
                     current_slot,
                     current_unix_timestamp,
                 )
                 .map_or_else(|| None, Some)?;
-            // matched_adjusted_quote_lots is rounded down to the nearest tick for buys and up for
-            // sells to yield a whole number of matched_quote_lots.
+            if let OrderPacket::MarketToLimit { price_in_ticks, .. } = &mut order_packet {
+                // The unfilled remainder of a market-to-limit order is posted at the price of the
+                // last fill. If the order did not match at all, there is no price to post the
+                // remainder at and the order is voided.
+                *price_in_ticks = Some(inflight_order.last_matched_price_in_ticks?);
+            }
             let matched_quote_lots = match side {
                 // We add the quote_lot_fees to account for the fee being paid on a buy order
                 Side::Bid => {
-                    (self.round_adjusted_quote_lots_up(inflight_order.matched_adjusted_quote_lots)
-                        / self.base_lots_per_base_unit)
+                    self.round_matched_quote_lots(side, inflight_order.matched_adjusted_quote_lots)
                         + inflight_order.quote_lot_fees
                 }
                 // We subtract the quote_lot_fees to account for the fee being paid on a sell order
                 Side::Ask => {
-                    (self
-                        .round_adjusted_quote_lots_down(inflight_order.matched_adjusted_quote_lots)
-                        / self.base_lots_per_base_unit)
+                    self.round_matched_quote_lots(side, inflight_order.matched_adjusted_quote_lots)
                         - inflight_order.quote_lot_fees
                 }
             };
@@ -985,17 +1237,43 @@ impl<
                 }
             };
 
+            if let OrderPacket::Limit {
+                min_base_lots_to_fill,
+                all_or_none_on_cross,
+                ..
+            } = order_packet
+            {
+                // For Limit orders, if the order's minimum fill requirement is not met, or if part of
+                // the order still crosses the book after matching, then the order is voided
+                if min_base_lots_to_fill.is_some_and(|min_base_lots_to_fill| {
+                    matching_engine_response.num_base_lots() < min_base_lots_to_fill
+                }) || (all_or_none_on_cross
+                    && limit_order_crosses
+                    && resting_order.num_base_lots > BaseLots::ZERO)
+                {
+                    return None;
+                }
+            }
+
             // Only place an order if there is more size to place and the limit order doesn't cross the book
             if resting_order.num_base_lots > BaseLots::ZERO && !limit_order_crosses {
                 // Evict order from the book if it is at capacity
                 placed_order_id = Some(order_id);
                 if book_full {
-                    self.evict_least_aggressive_order(side, record_event_fn, &order_id);
+                    self.evict_order(side, record_event_fn, &order_id);
                 }
                 // Add new order to the book
-                self.get_book_mut(side)
+                let addr = self
+                    .get_book_mut(side)
                     .insert(order_id, resting_order)
                     .map_or_else(|| None, Some)?;
+                if let Some(link_id) = order_packet.link_id() {
+                    self.link_to_pending_order(
+                        trader_index,
+                        link_id,
+                        encode_order_location(side, addr),
+                    );
+                }
                 // These constants need to be copied because we mutably borrow below
                 let tick_size_in_quote_lots_per_base_unit =
                     self.tick_size_in_quote_lots_per_base_unit;
@@ -1101,22 +1379,61 @@ impl<
         Some((placed_order_id, matching_engine_response))
     }
 
-    fn evict_least_aggressive_order(
+    /// Removes a resting order from a full side of the book to make room for the order being placed.
+    /// The order to evict is chosen by the market's `BookFullPolicy` among the resting orders that the
+    /// new order improves on by at least `min_eviction_improvement_in_ticks` (and at least one tick).
+    fn evict_order(
         &mut self,
         side: Side,
         record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
         placed_order_id: &FIFOOrderId,
     ) -> Option<FIFORestingOrder> {
         let (order_id, resting_order) = {
-            // Find the least aggressive order in the book
-            let (fifo_order_id, resting_order) = self.get_book_mut(side).get_max()?;
+            let min_improvement = self.min_eviction_improvement_in_ticks.as_u64().max(1);
+            let placed_price = placed_order_id.price_in_ticks.as_u64();
+            let is_eligible = |order_id: &FIFOOrderId| match side {
+                Side::Bid => {
+                    placed_price.saturating_sub(order_id.price_in_ticks.as_u64()) >= min_improvement
+                }
+                Side::Ask => {
+                    order_id
+                        .price_in_ticks
+                        .as_u64()
+                        .saturating_sub(placed_price)
+                        >= min_improvement
+                }
+            };
+            // The policy is validated when the market is loaded, see `dispatch_market_mut`.
+            // Candidates are visited from least to most aggressive, so ties are broken in favor of
+            // evicting the less aggressive order
+            let (fifo_order_id, resting_order) = match self.get_book_full_policy().ok()? {
+                BookFullPolicy::RejectNewOrder => None,
+                BookFullPolicy::EvictLeastAggressive => self
+                    .get_book_mut(side)
+                    .get_max()
+                    .filter(|(fifo_order_id, _)| is_eligible(fifo_order_id)),
+                BookFullPolicy::EvictSmallestNotional => self
+                    .get_book(side)
+                    .iter()
+                    .rev()
+                    .filter(|(fifo_order_id, _)| is_eligible(fifo_order_id))
+                    .min_by_key(|(fifo_order_id, resting_order)| {
+                        fifo_order_id.price_in_ticks.as_u128()
+                            * resting_order.num_base_lots.as_u128()
+                    })
+                    .map(|(fifo_order_id, resting_order)| (*fifo_order_id, *resting_order)),
+                BookFullPolicy::EvictOldest => self
+                    .get_book(side)
+                    .iter()
+                    .rev()
+                    .filter(|(fifo_order_id, _)| is_eligible(fifo_order_id))
+                    .min_by_key(|(fifo_order_id, _)| match side {
+                        Side::Bid => !fifo_order_id.order_sequence_number,
+                        Side::Ask => fifo_order_id.order_sequence_number,
+                    })
+                    .map(|(fifo_order_id, resting_order)| (*fifo_order_id, *resting_order)),
+            }?;
             let maker_id = self.get_trader_id_from_index(resting_order.trader_index as u32);
-            if match side {
-                Side::Bid => fifo_order_id.price_in_ticks >= placed_order_id.price_in_ticks,
-                Side::Ask => fifo_order_id.price_in_ticks <= placed_order_id.price_in_ticks,
-            } {
-                return None;
-            }
             self.get_book_mut(side).remove(&fifo_order_id)?;
             record_event_fn(MarketEvent::<MarketTraderId>::Evict {
                 maker_id,
@@ -1146,16 +1463,27 @@ impl<
     fn match_order(
         &mut self,
         inflight_order: &mut InflightOrder,
+        current_trader_id: &MarketTraderId,
         current_trader_index: u32,
         record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
         current_slot: u64,
         current_unix_timestamp: u64,
     ) -> Option<FIFORestingOrder> {
         let mut total_matched_adjusted_quote_lots = AdjustedQuoteLots::ZERO;
+        // Resting orders that are skipped due to the SkipProvide self trade behavior
+        let mut skipped_orders = vec![];
+        // Orders from traders in the same self trade prevention group are treated as self trades
+        let current_trader_group = if current_trader_index != u32::MAX {
+            self.get_trader_state_from_index(current_trader_index)
+                .self_trade_prevention_group
+        } else {
+            Pubkey::default()
+        };
         while inflight_order.in_progress() {
             // Find the first order on the opposite side of the book that matches the inflight order.
             let (
                 trader_index,
+                linked_order,
                 order_id,
                 num_base_lots_quoted,
                 last_valid_slot,
@@ -1168,9 +1496,11 @@ impl<
                     order_id,
                     FIFORestingOrder {
                         trader_index,
+                        linked_order,
                         num_base_lots: num_base_lots_quoted,
                         last_valid_slot,
                         last_valid_unix_timestamp_in_seconds,
+                        ..
                     },
                 ) = if let Some((o_id, quote)) = book.get_min() {
                     (
@@ -1197,7 +1527,8 @@ impl<
                     continue;
                 }
                 (
-                    trader_index,
+                    trader_index as u32,
+                    linked_order,
                     order_id,
                     num_base_lots_quoted,
                     last_valid_slot,
@@ -1205,6 +1536,19 @@ impl<
                 )
             };
 
+            // The location of a linked order is needed to find the other leg once the order is removed,
+            // or to update the links to the order if it is skipped
+            let order_location = if linked_order != 0
+                || self
+                    .get_trader_state_from_index(trader_index)
+                    .pending_link_id
+                    != 0
+            {
+                self.get_order_location(inflight_order.side.opposite(), &order_id)
+            } else {
+                0
+            };
+
             // This block is entered if the order has expired. The order is removed from the book and
             // the match limit is decremented.
             if (last_valid_slot != 0 && last_valid_slot < current_slot)
@@ -1212,7 +1556,7 @@ impl<
                     && last_valid_unix_timestamp_in_seconds < current_unix_timestamp)
             {
                 self.reduce_order_inner(
-                    trader_index as u32,
+                    trader_index,
                     &order_id,
                     inflight_order.side.opposite(),
                     None,
@@ -1225,17 +1569,23 @@ impl<
             }
 
             // Handle self trade
-            if trader_index == current_trader_index as u64 {
+            if trader_index == current_trader_index
+                || (current_trader_group != Pubkey::default()
+                    && self
+                        .get_trader_state_from_index(trader_index)
+                        .self_trade_prevention_group
+                        == current_trader_group)
+            {
                 match inflight_order.self_trade_behavior {
                     SelfTradeBehavior::Abort => return None,
-                    SelfTradeBehavior::CancelProvide => {
+                    SelfTradeBehavior::CancelProvide | SelfTradeBehavior::CancelBoth => {
                         // This block is entered if the self trade behavior for the crossing order is
-                        // CancelProvide
+                        // CancelProvide or CancelBoth
                         //
                         // We cancel the order from the book and free up the locked quote_lots or base_lots, but
                         // we do not claim them as part of the match
                         self.reduce_order_inner(
-                            current_trader_index,
+                            trader_index,
                             &order_id,
                             inflight_order.side.opposite(),
                             None,
@@ -1244,6 +1594,9 @@ impl<
                             record_event_fn,
                         )?;
                         inflight_order.match_limit -= 1;
+                        if inflight_order.self_trade_behavior == SelfTradeBehavior::CancelBoth {
+                            inflight_order.cancel_remaining_budget();
+                        }
                     }
                     SelfTradeBehavior::DecrementTake => {
                         let base_lots_removed = inflight_order
@@ -1259,7 +1612,7 @@ impl<
                             .min(num_base_lots_quoted);
 
                         self.reduce_order_inner(
-                            current_trader_index,
+                            trader_index,
                             &order_id,
                             inflight_order.side.opposite(),
                             Some(base_lots_removed),
@@ -1284,7 +1637,28 @@ impl<
                         // exhausted
                         inflight_order.should_terminate = base_lots_removed < num_base_lots_quoted;
                     }
+                    SelfTradeBehavior::CancelTake => {
+                        // The resting order is left untouched
+                        inflight_order.cancel_remaining_budget();
+                    }
+                    SelfTradeBehavior::SkipProvide => {
+                        // The resting order is temporarily taken out of the book so that matching can
+                        // continue against the orders behind it. It is reinserted with the same order id
+                        // once matching is complete, so it keeps its time priority.
+                        let resting_order = self
+                            .get_book_mut(inflight_order.side.opposite())
+                            .remove(&order_id)?;
+                        skipped_orders.push((order_id, resting_order, order_location));
+                        // Self trades will count towards the match limit
+                        inflight_order.match_limit -= 1;
+                    }
                 }
+                record_event_fn(MarketEvent::<MarketTraderId>::SelfTrade {
+                    maker_id: self.get_trader_id_from_index(trader_index),
+                    order_sequence_number: order_id.order_sequence_number,
+                    price_in_ticks: order_id.price_in_ticks,
+                    self_trade_behavior: inflight_order.self_trade_behavior,
+                });
                 continue;
             }
 
@@ -1342,24 +1716,42 @@ impl<
             inflight_order.process_match(matched_adjusted_quote_lots, matched_base_lots);
 
             // Increment the matched adjusted quote lots for fee calculation
+            let previous_matched_adjusted_quote_lots = total_matched_adjusted_quote_lots;
             total_matched_adjusted_quote_lots += matched_adjusted_quote_lots;
 
             // If the matched base lots is zero, we don't record the fill event
             if matched_base_lots != BaseLots::ZERO {
+                inflight_order.last_matched_price_in_ticks = Some(order_id.price_in_ticks);
+                // The quote lots and fee of each fill are the increase in the rounded totals of the
+                // whole order, so that the fills add up to the amounts settled with the taker
+                let quote_lots_filled = self.round_matched_quote_lots(
+                    inflight_order.side,
+                    total_matched_adjusted_quote_lots,
+                ) - self.round_matched_quote_lots(
+                    inflight_order.side,
+                    previous_matched_adjusted_quote_lots,
+                );
+                let fee_in_quote_lots = self
+                    .compute_quote_lot_fees(total_matched_adjusted_quote_lots)
+                    - self.compute_quote_lot_fees(previous_matched_adjusted_quote_lots);
                 // The fill event is recorded
                 record_event_fn(MarketEvent::<MarketTraderId>::Fill {
-                    maker_id: self.get_trader_id_from_index(trader_index as u32),
+                    maker_id: self.get_trader_id_from_index(trader_index),
                     order_sequence_number: order_id.order_sequence_number,
                     price_in_ticks: order_id.price_in_ticks,
                     base_lots_filled: matched_base_lots,
                     base_lots_remaining: order_remaining_base_lots,
+                    taker_id: *current_trader_id,
+                    taker_side: inflight_order.side,
+                    quote_lots_filled,
+                    fee_in_quote_lots,
                 });
             } else if !inflight_order.should_terminate {
             }
 
             let base_lots_per_base_unit = self.base_lots_per_base_unit;
             // Update the maker's state to reflect the match
-            let trader_state = self.get_trader_state_from_index_mut(trader_index as u32);
+            let trader_state = self.get_trader_state_from_index_mut(trader_index);
             match inflight_order.side {
                 Side::Bid => trader_state.process_limit_sell(
                     matched_base_lots,
@@ -1370,11 +1762,55 @@ impl<
                     matched_base_lots,
                 ),
             }
+
+            // If the matched order is linked to another order, the linked order is reduced by the
+            // amount that was matched
+            if linked_order != 0 && matched_base_lots != BaseLots::ZERO {
+                if let Some((side, linked_order_id)) =
+                    self.get_linked_order(linked_order, order_location, trader_index)
+                {
+                    self.reduce_order_inner(
+                        trader_index,
+                        &linked_order_id,
+                        side,
+                        Some(matched_base_lots),
+                        false,
+                        false,
+                        record_event_fn,
+                    )?;
+                }
+            }
+        }
+        // Skipped orders are returned to the book. They may get a new location in the book, so the
+        // links to them are updated once all of them are reinserted (both legs may have been skipped).
+        let mut relocated_orders = vec![];
+        for (order_id, resting_order, order_location) in skipped_orders {
+            let addr = self
+                .get_book_mut(inflight_order.side.opposite())
+                .insert(order_id, resting_order)?;
+            if order_location != 0 {
+                relocated_orders.push((
+                    order_location,
+                    encode_order_location(inflight_order.side.opposite(), addr),
+                    resting_order,
+                ));
+            }
+        }
+        for (old_location, new_location, resting_order) in relocated_orders.iter() {
+            match relocated_orders.iter().find(|(location, ..)| {
+                resting_order.linked_order != 0 && *location == resting_order.linked_order
+            }) {
+                Some((_, linked_order_location, linked_order)) => {
+                    if linked_order.linked_order == *old_location {
+                        self.link_orders(*new_location, *linked_order_location);
+                    }
+                }
+                None => self.relocate_order(*old_location, *new_location, resting_order),
+            }
         }
         // Fees are updated based on the total amount matched
-        inflight_order.quote_lot_fees = self
-            .round_adjusted_quote_lots_up(self.compute_fee(total_matched_adjusted_quote_lots))
-            / self.base_lots_per_base_unit;
+        inflight_order.quote_lot_fees =
+            self.compute_quote_lot_fees(total_matched_adjusted_quote_lots);
         self.unclaimed_quote_lot_fees += inflight_order.quote_lot_fees;
 
         Some(FIFORestingOrder::new(
@@ -1385,6 +1821,218 @@ impl<
         ))
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn increase_order_inner(
+        &mut self,
+        trader_index: u32,
+        order_id: &FIFOOrderId,
+        side: Side,
+        size: BaseLots,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
+        get_clock_fn: &mut dyn FnMut() -> (u64, u64),
+    ) -> Option<(FIFOOrderId, MatchingEngineResponse)> {
+        if size == BaseLots::ZERO || self.order_sequence_number == u64::MAX >> 1 {
+            return None;
+        }
+        let (current_slot, current_unix_timestamp) = get_clock_fn();
+        let resting_order = *self.get_book(side).get(order_id)?;
+        if resting_order.trader_index != trader_index as u64
+            || resting_order.num_base_lots == BaseLots::ZERO
+            || resting_order.is_expired(current_slot, current_unix_timestamp)
+        {
+            return None;
+        }
+
+        // The order is removed from its place in the queue and reinserted at the back of the queue
+        // at the same price with a new order sequence number
+        let old_location = self.get_order_location(side, order_id);
+        self.get_book_mut(side).remove(order_id)?;
+        record_event_fn(MarketEvent::<MarketTraderId>::Reduce {
+            order_sequence_number: order_id.order_sequence_number,
+            price_in_ticks: order_id.price_in_ticks,
+            base_lots_removed: resting_order.num_base_lots,
+            base_lots_remaining: BaseLots::ZERO,
+        });
+        let new_order_id = match side {
+            Side::Bid => FIFOOrderId::new(order_id.price_in_ticks, !self.order_sequence_number),
+            Side::Ask => FIFOOrderId::new(order_id.price_in_ticks, self.order_sequence_number),
+        };
+        let new_resting_order = FIFORestingOrder {
+            num_base_lots: resting_order.num_base_lots + size,
+            ..resting_order
+        };
+        // This will never return None because an order was just removed from the book
+        let addr = self
+            .get_book_mut(side)
+            .insert(new_order_id, new_resting_order)?;
+        self.relocate_order(
+            old_location,
+            encode_order_location(side, addr),
+            &new_resting_order,
+        );
+
+        // Only the added size needs to be locked, the funds for the original order remain locked
+        let mut matching_engine_response = MatchingEngineResponse::default();
+        // These constants need to be copied because we mutably borrow below
+        let tick_size_in_quote_lots_per_base_unit = self.tick_size_in_quote_lots_per_base_unit;
+        let base_lots_per_base_unit = self.base_lots_per_base_unit;
+        let trader_state = self.get_trader_state_from_index_mut(trader_index);
+        match side {
+            Side::Bid => {
+                let quote_lots_for_base_lots = |num_base_lots: BaseLots| {
+                    (tick_size_in_quote_lots_per_base_unit
+                        * new_order_id.price_in_ticks
+                        * num_base_lots)
+                        / base_lots_per_base_unit
+                };
+                // The difference is locked (rather than the quote lots for `size`) so that the total
+                // amount locked matches the amount unlocked when the full order is cancelled
+                let quote_lots_to_lock = quote_lots_for_base_lots(new_resting_order.num_base_lots)
+                    - quote_lots_for_base_lots(resting_order.num_base_lots);
+                let quote_lots_free_to_use = quote_lots_to_lock.min(trader_state.quote_lots_free);
+                trader_state.use_free_quote_lots(quote_lots_free_to_use);
+                trader_state.lock_quote_lots(quote_lots_to_lock);
+                matching_engine_response.post_quote_lots(quote_lots_to_lock);
+                matching_engine_response.use_free_quote_lots(quote_lots_free_to_use);
+            }
+            Side::Ask => {
+                let base_lots_free_to_use = size.min(trader_state.base_lots_free);
+                trader_state.use_free_base_lots(base_lots_free_to_use);
+                trader_state.lock_base_lots(size);
+                matching_engine_response.post_base_lots(size);
+                matching_engine_response.use_free_base_lots(base_lots_free_to_use);
+            }
+        }
+        if use_only_deposited_funds && !matching_engine_response.verify_no_deposit() {
+            return None;
+        }
+
+        record_event_fn(MarketEvent::<MarketTraderId>::Place {
+            order_sequence_number: new_order_id.order_sequence_number,
+            price_in_ticks: new_order_id.price_in_ticks,
+            base_lots_placed: new_resting_order.num_base_lots,
+            client_order_id,
+        });
+        if new_resting_order.last_valid_slot != 0
+            || new_resting_order.last_valid_unix_timestamp_in_seconds != 0
+        {
+            record_event_fn(MarketEvent::<MarketTraderId>::TimeInForce {
+                order_sequence_number: new_order_id.order_sequence_number,
+                last_valid_slot: new_resting_order.last_valid_slot,
+                last_valid_unix_timestamp_in_seconds: new_resting_order
+                    .last_valid_unix_timestamp_in_seconds,
+            });
+        }
+
+        self.order_sequence_number += 1;
+        Some((new_order_id, matching_engine_response))
+    }
+
+    fn get_order_tree(&self, side: Side) -> &dyn RedBlackTreeMap<FIFOOrderId, FIFORestingOrder> {
+        match side {
+            Side::Bid => &*self.bids,
+            Side::Ask => &*self.asks,
+        }
+    }
+
+    fn get_order_tree_mut(
+        &mut self,
+        side: Side,
+    ) -> &mut dyn RedBlackTreeMap<FIFOOrderId, FIFORestingOrder> {
+        match side {
+            Side::Bid => &mut *self.bids,
+            Side::Ask => &mut *self.asks,
+        }
+    }
+
+    /// Returns the location of a resting order in the book, as stored in `FIFORestingOrder::linked_order`
+    fn get_order_location(&self, side: Side, order_id: &FIFOOrderId) -> u32 {
+        encode_order_location(side, self.get_order_tree(side).get_addr(order_id))
+    }
+
+    /// Returns the side and id of the resting order at `location` if it is an order of the trader
+    /// that is linked to the order at `linked_from`. Nodes are never moved while they are in the tree,
+    /// so a stale location is detected by checking that the node is still in the tree and that it
+    /// links back to `linked_from`.
+    fn get_linked_order(
+        &self,
+        location: u32,
+        linked_from: u32,
+        trader_index: u32,
+    ) -> Option<(Side, FIFOOrderId)> {
+        let (side, addr) = decode_order_location(location);
+        let tree = self.get_order_tree(side);
+        if addr == SENTINEL || addr as usize > tree.capacity() {
+            return None;
+        }
+        let node = tree.get_node(addr);
+        (tree.get_addr(&node.key) == addr
+            && node.value.trader_index == trader_index as u64
+            && node.value.linked_order == linked_from
+            && node.value.num_base_lots > BaseLots::ZERO)
+            .then_some((side, node.key))
+    }
+
+    /// Links two resting orders of a trader
+    fn link_orders(&mut self, location: u32, other_location: u32) {
+        for (location, linked_order) in [(location, other_location), (other_location, location)] {
+            let (side, addr) = decode_order_location(location);
+            self.get_order_tree_mut(side)
+                .get_node_mut(addr)
+                .value
+                .linked_order = linked_order;
+        }
+    }
+
+    /// Links a newly placed order to the trader's pending linked order if they share the link id.
+    /// Otherwise, the new order becomes the pending linked order of the trader, so the two legs of
+    /// a pair must be placed before the first leg of another pair.
+    fn link_to_pending_order(&mut self, trader_index: u32, link_id: u32, location: u32) {
+        let trader_state = *self.get_trader_state_from_index(trader_index);
+        // The pending order must still be on the book and must not be linked to another order
+        let is_pending_order_linkable = trader_state.pending_link_id == link_id
+            && self
+                .get_linked_order(trader_state.pending_linked_order, 0, trader_index)
+                .is_some();
+        if is_pending_order_linkable {
+            self.link_orders(location, trader_state.pending_linked_order);
+        }
+        let trader_state = self.get_trader_state_from_index_mut(trader_index);
+        if is_pending_order_linkable {
+            trader_state.pending_link_id = 0;
+            trader_state.pending_linked_order = 0;
+        } else {
+            trader_state.pending_link_id = link_id;
+            trader_state.pending_linked_order = location;
+        }
+    }
+
+    /// Updates the links to an order that moved from `old_location` to `new_location` in the book
+    fn relocate_order(
+        &mut self,
+        old_location: u32,
+        new_location: u32,
+        resting_order: &FIFORestingOrder,
+    ) {
+        if resting_order.linked_order != 0
+            && self
+                .get_linked_order(
+                    resting_order.linked_order,
+                    old_location,
+                    resting_order.trader_index as u32,
+                )
+                .is_some()
+        {
+            self.link_orders(new_location, resting_order.linked_order);
+        }
+        let trader_state = self.get_trader_state_from_index_mut(resting_order.trader_index as u32);
+        if trader_state.pending_link_id != 0 && trader_state.pending_linked_order == old_location {
+            trader_state.pending_linked_order = new_location;
+        }
+    }
+
     fn cancel_all_orders_inner(
         &mut self,
         trader_id: &MarketTraderId,
@@ -1,6 +1,9 @@
 use borsh::BorshDeserialize;
+use solana_program::pubkey::Pubkey;
 
+use crate::program::status::{MarketStatus, SeatApprovalStatus};
 use crate::quantities::{BaseLots, QuoteLots, Ticks};
+use crate::state::{SelfTradeBehavior, Side};
 
 #[derive(Debug, Copy, Clone)]
 pub enum MarketEvent<MarketTraderId: BorshDeserialize + BorshDeserialize> {
@@ -10,6 +13,10 @@ pub enum MarketEvent<MarketTraderId: BorshDeserialize + BorshDeserialize> {
         price_in_ticks: Ticks,
         base_lots_filled: BaseLots,
         base_lots_remaining: BaseLots,
+        taker_id: MarketTraderId,
+        taker_side: Side,
+        quote_lots_filled: QuoteLots,
+        fee_in_quote_lots: QuoteLots,
     },
     Place {
         order_sequence_number: u64,
@@ -49,4 +56,64 @@ pub enum MarketEvent<MarketTraderId: BorshDeserialize + BorshDeserialize> {
         price_in_ticks: Ticks,
         base_lots_removed: BaseLots,
     },
+    SelfTrade {
+        maker_id: MarketTraderId,
+        order_sequence_number: u64,
+        price_in_ticks: Ticks,
+        self_trade_behavior: SelfTradeBehavior,
+    },
+    SeatAllowlistRootUpdate {
+        previous_root: [u8; 32],
+        new_root: [u8; 32],
+    },
+    SuccessorNomination {
+        previous_successor: Pubkey,
+        successor: Pubkey,
+        expiry_unix_timestamp: i64,
+    },
+    SuccessorCancellation {
+        successor: Pubkey,
+    },
+    AuthorityClaim {
+        previous_authority: Pubkey,
+        new_authority: Pubkey,
+    },
+    MarketStatusChange {
+        previous_status: MarketStatus,
+        new_status: MarketStatus,
+    },
+    Deposit {
+        trader: MarketTraderId,
+        base_lots_deposited: BaseLots,
+        quote_lots_deposited: QuoteLots,
+        base_lots_free: BaseLots,
+        quote_lots_free: QuoteLots,
+    },
+    Withdraw {
+        trader: MarketTraderId,
+        base_lots_withdrawn: BaseLots,
+        quote_lots_withdrawn: QuoteLots,
+        base_lots_free: BaseLots,
+        quote_lots_free: QuoteLots,
+    },
+    FeeRecipientChange {
+        previous_fee_recipient: Pubkey,
+        new_fee_recipient: Pubkey,
+    },
+    SeatRequest {
+        trader: MarketTraderId,
+    },
+    SeatStatusChange {
+        trader: MarketTraderId,
+        previous_status: SeatApprovalStatus,
+        new_status: SeatApprovalStatus,
+    },
+    SeatClosure {
+        trader: MarketTraderId,
+    },
+    TraderEviction {
+        trader: MarketTraderId,
+        base_lots_evicted: BaseLots,
+        quote_lots_evicted: QuoteLots,
+    },
 }
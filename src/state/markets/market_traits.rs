@@ -1,6 +1,7 @@
 use itertools::Itertools;
 
 use crate::{
+    program::PhoenixError,
     quantities::{
         BaseLots, BaseLotsPerBaseUnit, QuoteLots, QuoteLotsPerBaseUnitPerTick, Ticks, WrapperU64,
     },
@@ -145,6 +146,8 @@ pub trait Market<
     fn get_tick_size(&self) -> QuoteLotsPerBaseUnitPerTick;
     fn get_base_lots_per_base_unit(&self) -> BaseLotsPerBaseUnit;
     fn get_sequence_number(&self) -> u64;
+    fn get_book_full_policy(&self) -> Result<BookFullPolicy, PhoenixError>;
+    fn get_min_eviction_improvement_in_ticks(&self) -> Ticks;
     fn get_registered_traders(&self) -> &dyn OrderedNodeAllocatorMap<MarketTraderId, TraderState>;
     fn get_trader_state(&self, key: &MarketTraderId) -> Option<&TraderState>;
     fn get_trader_state_from_index(&self, index: u32) -> &TraderState;
@@ -171,6 +174,12 @@ pub(crate) trait WritableMarket<
 
     fn set_fee(&mut self, taker_fee_bps: u64);
 
+    fn set_book_full_policy(
+        &mut self,
+        book_full_policy: BookFullPolicy,
+        min_eviction_improvement_in_ticks: Ticks,
+    );
+
     fn get_trader_state_mut(&mut self, key: &MarketTraderId) -> Option<&mut TraderState>;
 
     fn get_registered_traders_mut(
@@ -187,6 +196,12 @@ pub(crate) trait WritableMarket<
         self.get_trader_index(trader)
     }
 
+    /// Removes an inactive trader from the market to make room for a new trader when all seats
+    /// are taken. The trader must have no open orders or locked lots, and must be the least recently
+    /// active of all such traders. Returns the state of the evicted trader, whose free lots are no
+    /// longer tracked by the market.
+    fn evict_inactive_trader(&mut self, trader_id: &MarketTraderId) -> Option<TraderState>;
+
     fn get_book_mut(
         &mut self,
         side: Side,
@@ -210,6 +225,21 @@ pub(crate) trait WritableMarket<
         record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
     ) -> Option<MatchingEngineResponse>;
 
+    /// Adds size to an existing resting order. The order loses its time priority and is given a new
+    /// order id at the same price, which is returned along with the funds that need to be locked.
+    #[allow(clippy::too_many_arguments)]
+    fn increase_order(
+        &mut self,
+        trader_id: &MarketTraderId,
+        order_id: &MarketOrderId,
+        side: Side,
+        size: BaseLots,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+        record_event_fn: &mut dyn FnMut(MarketEvent<MarketTraderId>),
+        get_clock_fn: &mut dyn FnMut() -> (u64, u64),
+    ) -> Option<(MarketOrderId, MatchingEngineResponse)>;
+
     fn cancel_all_orders(
         &mut self,
         trader_id: &MarketTraderId,
@@ -8,11 +8,9 @@ pub(crate) struct MarketWrapperMut<
     MarketRestingOrder,
     MarketOrderPacket,
 > {
-    pub inner: &'a mut dyn WritableMarket<
-        MarketTraderId,
-        MarketOrderId,
-        MarketRestingOrder,
-        MarketOrderPacket,
+    pub inner: Box<
+        dyn WritableMarket<MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>
+            + 'a,
     >,
 }
 
@@ -20,11 +18,9 @@ impl<'a, MarketTraderId, MarketOrderPacket, MarketRestingOrder, MarketOrderId>
     MarketWrapperMut<'a, MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>
 {
     pub(crate) fn new(
-        market: &'a mut dyn WritableMarket<
-            MarketTraderId,
-            MarketOrderId,
-            MarketRestingOrder,
-            MarketOrderPacket,
+        market: Box<
+            dyn WritableMarket<MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>
+                + 'a,
         >,
     ) -> Self {
         Self { inner: market }
@@ -33,18 +29,16 @@ impl<'a, MarketTraderId, MarketOrderPacket, MarketRestingOrder, MarketOrderId>
 
 /// Struct that holds an object implementing the Market trait.
 pub struct MarketWrapper<'a, MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket> {
-    pub inner: &'a dyn Market<MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>,
+    pub inner:
+        Box<dyn Market<MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket> + 'a>,
 }
 
 impl<'a, MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>
     MarketWrapper<'a, MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket>
 {
     pub fn new(
-        market: &'a dyn Market<
-            MarketTraderId,
-            MarketOrderId,
-            MarketRestingOrder,
-            MarketOrderPacket,
+        market: Box<
+            dyn Market<MarketTraderId, MarketOrderId, MarketRestingOrder, MarketOrderPacket> + 'a,
         >,
     ) -> Self {
         Self { inner: market }
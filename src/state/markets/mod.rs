@@ -2,8 +2,10 @@ pub mod fifo;
 pub mod market_events;
 pub mod market_traits;
 pub mod market_wrapper;
+pub mod red_black_tree_map;
 
 pub use fifo::*;
 pub use market_events::*;
 pub use market_traits::*;
 pub use market_wrapper::*;
+pub use red_black_tree_map::*;
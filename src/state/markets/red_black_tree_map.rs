@@ -0,0 +1,173 @@
+use bytemuck::{Pod, Zeroable};
+use sokoban::node_allocator::OrderedNodeAllocatorMap;
+use sokoban::red_black_tree::RBNode;
+use sokoban::RedBlackTree;
+use std::{fmt::Debug, mem::size_of};
+
+/// Expands `$body` once for every supported tree capacity, with `$capacity` bound to a constant
+/// equal to the capacity that was matched. Evaluates to None for unsupported capacities.
+///
+/// `sokoban::RedBlackTree` is sized at compile time, so a tree can only be loaded with one of
+/// these capacities. Powers of two cover new markets and the remaining values are the capacities
+/// of markets created before market sizes became configurable.
+macro_rules! dispatch_tree_capacity {
+    ($size:expr, |$capacity:ident| $body:expr) => {
+        dispatch_tree_capacity!(
+            @match $size, $capacity, $body,
+            4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096, 8192, 16384, 32768, 65536,
+            1025, 1153, 2049, 2177, 4097, 4225, 8193, 8321
+        )
+    };
+    (@match $size:expr, $capacity:ident, $body:expr, $($n:literal),*) => {
+        match $size {
+            $(
+                $n => {
+                    const $capacity: usize = $n;
+                    Some($body)
+                }
+            )*
+            _ => None,
+        }
+    };
+}
+
+/// A `sokoban::RedBlackTree` whose capacity is only known at runtime. Market accounts store their
+/// trees with the capacities from `MarketSizeParams`, so the trees are loaded as trait objects.
+pub trait RedBlackTreeMap<K, V>: OrderedNodeAllocatorMap<K, V>
+where
+    K: Debug + PartialOrd + Ord + Copy + Clone + Default + Pod + Zeroable,
+    V: Default + Copy + Clone + Pod + Zeroable,
+{
+    fn initialize(&mut self);
+
+    /// Returns the address of the node with the given key, or `SENTINEL` if the key is not in the tree.
+    fn get_addr(&self, key: &K) -> u32;
+
+    fn get_node(&self, addr: u32) -> &RBNode<K, V>;
+
+    fn get_node_mut(&mut self, addr: u32) -> &mut RBNode<K, V>;
+
+    fn as_map(&self) -> &dyn OrderedNodeAllocatorMap<K, V>;
+
+    fn as_map_mut(&mut self) -> &mut dyn OrderedNodeAllocatorMap<K, V>;
+}
+
+impl<K, V, const MAX_SIZE: usize> RedBlackTreeMap<K, V> for RedBlackTree<K, V, MAX_SIZE>
+where
+    K: Debug + PartialOrd + Ord + Copy + Clone + Default + Pod + Zeroable,
+    V: Default + Copy + Clone + Pod + Zeroable,
+{
+    fn initialize(&mut self) {
+        RedBlackTree::initialize(self)
+    }
+
+    fn get_addr(&self, key: &K) -> u32 {
+        RedBlackTree::get_addr(self, key)
+    }
+
+    fn get_node(&self, addr: u32) -> &RBNode<K, V> {
+        RedBlackTree::get_node(self, addr)
+    }
+
+    fn get_node_mut(&mut self, addr: u32) -> &mut RBNode<K, V> {
+        RedBlackTree::get_node_mut(self, addr)
+    }
+
+    fn as_map(&self) -> &dyn OrderedNodeAllocatorMap<K, V> {
+        self
+    }
+
+    fn as_map_mut(&mut self) -> &mut dyn OrderedNodeAllocatorMap<K, V> {
+        self
+    }
+}
+
+/// Returns the number of bytes needed to store a tree with the given capacity, or None if the
+/// capacity is not supported.
+pub fn get_tree_size<K, V>(capacity: usize) -> Option<usize>
+where
+    K: Debug + PartialOrd + Ord + Copy + Clone + Default + Pod + Zeroable,
+    V: Default + Copy + Clone + Pod + Zeroable,
+{
+    dispatch_tree_capacity!(
+        capacity,
+        |CAPACITY| size_of::<RedBlackTree<K, V, CAPACITY>>()
+    )
+}
+
+/// Loads a tree with the given capacity from the start of `data`.
+pub fn load_tree<K, V>(data: &[u8], capacity: usize) -> Option<&dyn RedBlackTreeMap<K, V>>
+where
+    K: Debug + PartialOrd + Ord + Copy + Clone + Default + Pod + Zeroable,
+    V: Default + Copy + Clone + Pod + Zeroable,
+{
+    let bytes = data.get(..get_tree_size::<K, V>(capacity)?)?;
+    dispatch_tree_capacity!(capacity, |CAPACITY| bytemuck::try_from_bytes::<
+        RedBlackTree<K, V, CAPACITY>,
+    >(bytes)
+    .ok()?
+        as &dyn RedBlackTreeMap<K, V>)
+}
+
+/// Loads a mutable tree with the given capacity from the start of `data`.
+pub fn load_tree_mut<K, V>(
+    data: &mut [u8],
+    capacity: usize,
+) -> Option<&mut dyn RedBlackTreeMap<K, V>>
+where
+    K: Debug + PartialOrd + Ord + Copy + Clone + Default + Pod + Zeroable,
+    V: Default + Copy + Clone + Pod + Zeroable,
+{
+    let bytes = data.get_mut(..get_tree_size::<K, V>(capacity)?)?;
+    dispatch_tree_capacity!(capacity, |CAPACITY| bytemuck::try_from_bytes_mut::<
+        RedBlackTree<K, V, CAPACITY>,
+    >(bytes)
+    .ok()?
+        as &mut dyn RedBlackTreeMap<K, V>)
+}
+
+#[test]
+fn test_tree_sizes() {
+    use crate::state::markets::{FIFOOrderId, FIFORestingOrder};
+
+    // Trees are laid out as a 32 byte header followed by the nodes
+    let node_size = 16 + size_of::<FIFOOrderId>() + size_of::<FIFORestingOrder>();
+    for capacity in [4, 256, 1153, 65536] {
+        assert_eq!(
+            get_tree_size::<FIFOOrderId, FIFORestingOrder>(capacity),
+            Some(32 + capacity * node_size)
+        );
+    }
+    for capacity in [0, 1, 10, 1000, 1 << 17] {
+        assert_eq!(
+            get_tree_size::<FIFOOrderId, FIFORestingOrder>(capacity),
+            None
+        );
+    }
+
+    // Only powers of two and the capacities of legacy markets are supported
+    let supported_capacities = (0..=1 << 17)
+        .filter(|capacity| get_tree_size::<u64, u64>(*capacity).is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        supported_capacities,
+        [
+            4, 8, 16, 32, 64, 128, 256, 512, 1024, 1025, 1153, 2048, 2049, 2177, 4096, 4097, 4225,
+            8192, 8193, 8321, 16384, 32768, 65536
+        ]
+    );
+
+    let mut buffer = vec![0_u64; get_tree_size::<u64, u64>(8).unwrap() / 8];
+    let bytes = bytemuck::cast_slice_mut::<u64, u8>(&mut buffer);
+    let tree = load_tree_mut::<u64, u64>(bytes, 8).unwrap();
+    tree.initialize();
+    for key in 0..10 {
+        assert_eq!(tree.insert(key, key * 2).is_some(), key < 8);
+    }
+    let addr = tree.get_addr(&3);
+    tree.get_node_mut(addr).value += 1;
+    let tree = load_tree::<u64, u64>(bytemuck::cast_slice(&buffer), 8).unwrap();
+    assert_eq!(tree.get(&3), Some(&7));
+    assert_eq!(tree.get_node(tree.get_addr(&5)).key, 5);
+    assert!(load_tree::<u64, u64>(bytemuck::cast_slice(&buffer), 16).is_none());
+}
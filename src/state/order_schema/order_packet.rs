@@ -50,6 +50,22 @@ pub enum OrderPacket {
 
         /// If this is set, the order will fail silently if there are insufficient funds
         fail_silently_on_insufficient_funds: bool,
+
+        /// If this is set, the resting order is linked to the trader's previously placed resting order
+        /// with the same nonzero link id, if that order is still on the book and not linked yet. When
+        /// either order is matched, the other is reduced by the same number of base lots
+        /// (one-cancels-other). Only the trader's most recent unlinked order can be linked to, so the
+        /// two legs of a pair must be placed back to back, without another linked order in between.
+        /// Orders placed with `PlaceMultiplePostOnlyOrders` can't be linked.
+        link_id: Option<u32>,
+
+        /// If this is set, the order will be invalid after this many slots have passed since it was placed.
+        /// If `last_valid_slot` is also set, the earlier of the two expiries is used
+        time_to_live_in_slots: Option<u64>,
+
+        /// If this is set, the order will be invalid after this many seconds have passed since it was placed.
+        /// If `last_valid_unix_timestamp_in_seconds` is also set, the earlier of the two expiries is used
+        time_to_live_in_seconds: Option<u64>,
     },
 
     /// This order type is used to place a limit order on the book
@@ -86,6 +102,30 @@ pub enum OrderPacket {
 
         /// If this is set, the order will fail silently if there are insufficient funds
         fail_silently_on_insufficient_funds: bool,
+
+        /// If this is set, the order will be voided unless at least this many base lots are matched
+        /// against the book before the remainder is posted
+        min_base_lots_to_fill: Option<BaseLots>,
+
+        /// If this is set to true, the order will be voided if any part of it that crosses the book is
+        /// left unmatched (e.g. because the match limit was reached)
+        all_or_none_on_cross: bool,
+
+        /// If this is set, the resting order is linked to the trader's previously placed resting order
+        /// with the same nonzero link id, if that order is still on the book and not linked yet. When
+        /// either order is matched, the other is reduced by the same number of base lots
+        /// (one-cancels-other). Only the trader's most recent unlinked order can be linked to, so the
+        /// two legs of a pair must be placed back to back, without another linked order in between.
+        /// Orders placed with `PlaceMultiplePostOnlyOrders` can't be linked.
+        link_id: Option<u32>,
+
+        /// If this is set, the order will be invalid after this many slots have passed since it was placed.
+        /// If `last_valid_slot` is also set, the earlier of the two expiries is used
+        time_to_live_in_slots: Option<u64>,
+
+        /// If this is set, the order will be invalid after this many seconds have passed since it was placed.
+        /// If `last_valid_unix_timestamp_in_seconds` is also set, the earlier of the two expiries is used
+        time_to_live_in_seconds: Option<u64>,
     },
 
     /// This order type is used to place an order that will be matched against existing resting orders
@@ -139,6 +179,50 @@ pub enum OrderPacket {
         /// If this is set, the order will be invalid after the specified unix timestamp
         last_valid_unix_timestamp_in_seconds: Option<u64>,
     },
+
+    /// This order type is used to sweep the book like a market order and post any unfilled
+    /// remainder on the book at the price of the last fill.
+    /// If the order is not matched against any resting orders, the order is voided and the
+    /// instruction fails.
+    MarketToLimit {
+        side: Side,
+
+        /// The most aggressive price the order can be matched at. If this value is None, the order
+        /// is matched at any price until its size is exhausted or the match limit is reached
+        /// (asks are matched down to 1 tick, the lowest valid price).
+        price_in_ticks: Option<Ticks>,
+
+        /// Total number of base lots to fill, or to place on the book if unfilled
+        num_base_lots: BaseLots,
+
+        /// How the matching engine should handle a self trade
+        self_trade_behavior: SelfTradeBehavior,
+
+        /// Number of orders to match against. If this is `None` there is no limit
+        match_limit: Option<u64>,
+
+        /// Client order id used to identify the order in the response to the client
+        client_order_id: u128,
+
+        /// Flag for whether or not the order should only use funds that are already in the account.
+        /// Using only deposited funds will allow the trader to pass in less accounts per instruction and
+        /// save transaction space as well as compute. This is only for traders who have a seat
+        use_only_deposited_funds: bool,
+
+        /// If this is set, the order will be invalid after the specified slot
+        last_valid_slot: Option<u64>,
+
+        /// If this is set, the order will be invalid after the specified unix timestamp
+        last_valid_unix_timestamp_in_seconds: Option<u64>,
+
+        /// If this is set, the order will be invalid after this many slots have passed since it was placed.
+        /// If `last_valid_slot` is also set, the earlier of the two expiries is used
+        time_to_live_in_slots: Option<u64>,
+
+        /// If this is set, the order will be invalid after this many seconds have passed since it was placed.
+        /// If `last_valid_unix_timestamp_in_seconds` is also set, the earlier of the two expiries is used
+        time_to_live_in_seconds: Option<u64>,
+    },
 }
 
 impl OrderPacketMetadata for OrderPacket {
@@ -180,6 +264,10 @@ impl OrderPacketMetadata for OrderPacket {
                 use_only_deposited_funds,
                 ..
             } => use_only_deposited_funds,
+            Self::MarketToLimit {
+                use_only_deposited_funds,
+                ..
+            } => use_only_deposited_funds,
         }
     }
 }
@@ -196,6 +284,9 @@ impl OrderPacket {
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
             fail_silently_on_insufficient_funds: false,
+            link_id: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
         }
     }
 
@@ -215,6 +306,9 @@ impl OrderPacket {
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
             fail_silently_on_insufficient_funds: false,
+            link_id: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
         }
     }
 
@@ -234,6 +328,9 @@ impl OrderPacket {
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
             fail_silently_on_insufficient_funds: false,
+            link_id: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
         }
     }
 
@@ -255,6 +352,9 @@ impl OrderPacket {
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
             fail_silently_on_insufficient_funds: false,
+            link_id: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
         }
     }
 
@@ -307,6 +407,11 @@ impl OrderPacket {
             last_valid_slot: None,
             last_valid_unix_timestamp_in_seconds: None,
             fail_silently_on_insufficient_funds: false,
+            min_base_lots_to_fill: None,
+            all_or_none_on_cross: false,
+            link_id: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
         }
     }
 
@@ -465,6 +570,42 @@ impl OrderPacket {
         )
     }
 
+    pub fn new_market_to_limit_order_default(side: Side, num_base_lots: u64) -> Self {
+        Self::new_market_to_limit_order(
+            side,
+            None,
+            num_base_lots,
+            SelfTradeBehavior::CancelProvide,
+            None,
+            0,
+            false,
+        )
+    }
+
+    pub fn new_market_to_limit_order(
+        side: Side,
+        price_in_ticks: Option<u64>,
+        num_base_lots: u64,
+        self_trade_behavior: SelfTradeBehavior,
+        match_limit: Option<u64>,
+        client_order_id: u128,
+        use_only_deposited_funds: bool,
+    ) -> Self {
+        Self::MarketToLimit {
+            side,
+            price_in_ticks: price_in_ticks.map(Ticks::new),
+            num_base_lots: BaseLots::new(num_base_lots),
+            self_trade_behavior,
+            match_limit,
+            client_order_id,
+            use_only_deposited_funds,
+            last_valid_slot: None,
+            last_valid_unix_timestamp_in_seconds: None,
+            time_to_live_in_slots: None,
+            time_to_live_in_seconds: None,
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn new_ioc(
         side: Side,
@@ -503,6 +644,7 @@ impl OrderPacket {
             Self::PostOnly { side, .. } => *side,
             Self::Limit { side, .. } => *side,
             Self::ImmediateOrCancel { side, .. } => *side,
+            Self::MarketToLimit { side, .. } => *side,
         }
     }
 
@@ -517,6 +659,7 @@ impl OrderPacket {
                 ..
             } => *fail_silently_on_insufficient_funds,
             Self::ImmediateOrCancel { .. } => false,
+            Self::MarketToLimit { .. } => false,
         }
     }
 
@@ -531,6 +674,18 @@ impl OrderPacket {
             Self::ImmediateOrCancel {
                 client_order_id, ..
             } => *client_order_id,
+            Self::MarketToLimit {
+                client_order_id, ..
+            } => *client_order_id,
+        }
+    }
+
+    pub fn link_id(&self) -> Option<u32> {
+        match self {
+            Self::PostOnly { link_id, .. } => *link_id,
+            Self::Limit { link_id, .. } => *link_id,
+            Self::ImmediateOrCancel { .. } => None,
+            Self::MarketToLimit { .. } => None,
         }
     }
 
@@ -539,6 +694,7 @@ impl OrderPacket {
             Self::PostOnly { num_base_lots, .. } => *num_base_lots,
             Self::Limit { num_base_lots, .. } => *num_base_lots,
             Self::ImmediateOrCancel { num_base_lots, .. } => *num_base_lots,
+            Self::MarketToLimit { num_base_lots, .. } => *num_base_lots,
         }
     }
 
@@ -547,6 +703,7 @@ impl OrderPacket {
             Self::PostOnly { .. } => QuoteLots::ZERO,
             Self::Limit { .. } => QuoteLots::ZERO,
             Self::ImmediateOrCancel { num_quote_lots, .. } => *num_quote_lots,
+            Self::MarketToLimit { .. } => QuoteLots::ZERO,
         }
     }
 
@@ -573,6 +730,7 @@ impl OrderPacket {
             Self::PostOnly { .. } => u64::MAX,
             Self::Limit { match_limit, .. } => match_limit.unwrap_or(u64::MAX),
             Self::ImmediateOrCancel { match_limit, .. } => match_limit.unwrap_or(u64::MAX),
+            Self::MarketToLimit { match_limit, .. } => match_limit.unwrap_or(u64::MAX),
         }
     }
 
@@ -587,6 +745,10 @@ impl OrderPacket {
                 self_trade_behavior,
                 ..
             } => *self_trade_behavior,
+            Self::MarketToLimit {
+                self_trade_behavior,
+                ..
+            } => *self_trade_behavior,
         }
     }
 
@@ -594,7 +756,8 @@ impl OrderPacket {
         match self {
             Self::PostOnly { price_in_ticks, .. } => *price_in_ticks,
             Self::Limit { price_in_ticks, .. } => *price_in_ticks,
-            Self::ImmediateOrCancel { price_in_ticks, .. } => {
+            Self::ImmediateOrCancel { price_in_ticks, .. }
+            | Self::MarketToLimit { price_in_ticks, .. } => {
                 price_in_ticks.unwrap_or(match self.side() {
                     Side::Bid => Ticks::MAX,
                     Side::Ask => Ticks::MIN,
@@ -617,6 +780,10 @@ impl OrderPacket {
                 price_in_ticks: old_price_in_ticks,
                 ..
             } => *old_price_in_ticks = Some(price_in_ticks),
+            Self::MarketToLimit {
+                price_in_ticks: old_price_in_ticks,
+                ..
+            } => *old_price_in_ticks = Some(price_in_ticks),
         }
     }
 
@@ -631,6 +798,9 @@ impl OrderPacket {
             Self::ImmediateOrCancel {
                 last_valid_slot, ..
             } => *last_valid_slot,
+            Self::MarketToLimit {
+                last_valid_slot, ..
+            } => *last_valid_slot,
         }
     }
 
@@ -648,6 +818,56 @@ impl OrderPacket {
                 last_valid_unix_timestamp_in_seconds,
                 ..
             } => *last_valid_unix_timestamp_in_seconds,
+            Self::MarketToLimit {
+                last_valid_unix_timestamp_in_seconds,
+                ..
+            } => *last_valid_unix_timestamp_in_seconds,
+        }
+    }
+
+    /// Converts the relative time-to-live of the order into an absolute expiry based on the current
+    /// clock. The resolved expiry replaces `last_valid_slot` and `last_valid_unix_timestamp_in_seconds`
+    /// if it is earlier.
+    pub fn resolve_time_to_live(
+        &mut self,
+        current_slot: u64,
+        current_unix_timestamp_in_seconds: u64,
+    ) {
+        match self {
+            Self::PostOnly {
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                time_to_live_in_slots,
+                time_to_live_in_seconds,
+                ..
+            }
+            | Self::Limit {
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                time_to_live_in_slots,
+                time_to_live_in_seconds,
+                ..
+            }
+            | Self::MarketToLimit {
+                last_valid_slot,
+                last_valid_unix_timestamp_in_seconds,
+                time_to_live_in_slots,
+                time_to_live_in_seconds,
+                ..
+            } => {
+                if let Some(time_to_live_in_slots) = time_to_live_in_slots.take() {
+                    let expiry = current_slot.saturating_add(time_to_live_in_slots);
+                    *last_valid_slot = Some(last_valid_slot.map_or(expiry, |s| s.min(expiry)));
+                }
+                if let Some(time_to_live_in_seconds) = time_to_live_in_seconds.take() {
+                    let expiry =
+                        current_unix_timestamp_in_seconds.saturating_add(time_to_live_in_seconds);
+                    *last_valid_unix_timestamp_in_seconds = Some(
+                        last_valid_unix_timestamp_in_seconds.map_or(expiry, |t| t.min(expiry)),
+                    );
+                }
+            }
+            Self::ImmediateOrCancel { .. } => {}
         }
     }
 
@@ -685,6 +905,11 @@ pub fn decode_order_packet(bytes: &[u8]) -> Option<OrderPacket> {
                 0_u8, /* last_valid_slot */
                 0_u8, /* last_valid_unix_timestamp_in_seconds */
                 0_u8, /* fail_silently_on_insufficient_funds */
+                0_u8, /* min_base_lots_to_fill */
+                0_u8, /* all_or_none_on_cross */
+                0_u8, /* link_id */
+                0_u8, /* time_to_live_in_slots */
+                0_u8, /* time_to_live_in_seconds */
             ];
             let mut padded_bytes = [bytes, additional_fields].concat();
             for _ in 0..additional_fields.len() {
@@ -697,3 +922,14 @@ pub fn decode_order_packet(bytes: &[u8]) -> Option<OrderPacket> {
         }
     }
 }
+
+#[test]
+fn test_decode_order_packet_without_optional_fields() {
+    let order_packet = OrderPacket::new_limit_order_default(Side::Bid, 100, 10);
+    let bytes = order_packet.try_to_vec().unwrap();
+    // Packets encoded before the optional trailing fields existed end after `use_only_deposited_funds`
+    let num_optional_field_bytes = 8;
+    let legacy_bytes = &bytes[..bytes.len() - num_optional_field_bytes];
+    assert_eq!(decode_order_packet(legacy_bytes), Some(order_packet));
+    assert_eq!(decode_order_packet(&bytes), Some(order_packet));
+}
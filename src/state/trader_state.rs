@@ -1,5 +1,6 @@
 use crate::quantities::{BaseLots, QuoteLots};
 use bytemuck::{Pod, Zeroable};
+use solana_program::pubkey::Pubkey;
 
 #[repr(C)]
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Zeroable, Pod)]
@@ -8,10 +9,29 @@ pub struct TraderState {
     pub quote_lots_free: QuoteLots,
     pub base_lots_locked: BaseLots,
     pub base_lots_free: BaseLots,
-    _padding: [u64; 8],
+    /// Traders that share a non-default self trade prevention group are treated as the same
+    /// trader when matching orders
+    pub self_trade_prevention_group: Pubkey,
+    /// The slot of the trader's last order, cancel, deposit or withdrawal on the market. This is only
+    /// tracked while the market evicts inactive traders
+    pub last_activity_slot: u64,
+    /// The link id of the trader's most recently placed linked order that is not yet paired with
+    /// another order, and the location of that order in the book (see `FIFORestingOrder::linked_order`)
+    pub pending_link_id: u32,
+    pub pending_linked_order: u32,
+    _padding: [u64; 2],
 }
 
 impl TraderState {
+    /// Returns true if the trader has no locked or free funds on the market
+    #[inline(always)]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.quote_lots_locked == QuoteLots::ZERO
+            && self.quote_lots_free == QuoteLots::ZERO
+            && self.base_lots_locked == BaseLots::ZERO
+            && self.base_lots_free == BaseLots::ZERO
+    }
+
     #[inline(always)]
     pub(crate) fn unlock_quote_lots(&mut self, quote_lots: QuoteLots) {
         self.quote_lots_locked -= quote_lots;